@@ -6,4 +6,7 @@
 
 extern crate alloc;
 
+#[cfg(test)]
+extern crate std;
+
 pub mod single;
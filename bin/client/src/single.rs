@@ -2,7 +2,7 @@ use alloc::sync::Arc;
 use alloy_consensus::Sealed;
 use alloy_primitives::B256;
 use core::fmt::Debug;
-use hana_celestia::{CelestiaDADataSource, CelestiaDASource};
+use hana_celestia::{CelestiaDADataSource, CelestiaDASource, CelestiaProvider};
 use hana_oracle::provider::OracleCelestiaProvider;
 use kona_client::{fpvm_evm::FpvmOpEvmFactory, single::FaultProofProgramError};
 use kona_derive::EthereumDataSource;
@@ -25,6 +25,23 @@ pub async fn run<P, H>(oracle_client: P, hint_client: H) -> Result<(), FaultProo
 where
     P: PreimageOracleClient + Send + Sync + Debug + Clone + 'static,
     H: HintWriterClient + Send + Sync + Debug + Clone + 'static,
+{
+    run_with_celestia_provider(oracle_client, hint_client, OracleCelestiaProvider::new).await
+}
+
+/// Executes the fault proof program with the given [PreimageOracleClient] and [HintWriterClient],
+/// fetching Celestia blobs from the [CelestiaProvider] built by `celestia_provider` from the
+/// caching oracle.
+pub async fn run_with_celestia_provider<P, H, C, F>(
+    oracle_client: P,
+    hint_client: H,
+    celestia_provider: F,
+) -> Result<(), FaultProofProgramError>
+where
+    P: PreimageOracleClient + Send + Sync + Debug + Clone + 'static,
+    H: HintWriterClient + Send + Sync + Debug + Clone + 'static,
+    C: CelestiaProvider + Send + Sync + Debug + Clone + 'static,
+    F: FnOnce(Arc<CachingOracle<P, H>>) -> C,
 {
     const ORACLE_LRU_SIZE: usize = 1024;
 
@@ -98,7 +115,9 @@ where
 
     let ethereum_data_source =
         EthereumDataSource::new_from_parts(l1_provider.clone(), beacon, &rollup_config);
-    let celestia_data_source = CelestiaDASource::new(OracleCelestiaProvider::new(oracle.clone()));
+    // Blobs of pointers without a namespace are checked in the namespace the provider reports,
+    // for the oracle provider the one their shares were proven in.
+    let celestia_data_source = CelestiaDASource::new(celestia_provider(oracle.clone()));
     let da_provider = CelestiaDADataSource::new(ethereum_data_source, celestia_data_source);
 
     let pipeline = OraclePipeline::new(
@@ -181,3 +200,281 @@ where
         .try_into()
         .map_err(OracleProviderError::SliceConversion)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+    use alloy_consensus::{
+        transaction::SignerRecoverable, Header, Signed, TxEnvelope, TxLegacy, EMPTY_ROOT_HASH,
+    };
+    use alloy_eips::{eip1898::BlockNumHash, Encodable2718};
+    use alloy_primitives::{keccak256, Address, Bytes, Signature, TxKind, U256};
+    use alloy_rlp::Encodable;
+    use async_trait::async_trait;
+    use celestia_types::{
+        consts::appconsts::SHARE_VERSION_ZERO, nmt::Namespace, AppVersion, Commitment,
+    };
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use hana_celestia::CelestiaBlobPointer;
+    use kona_derive::{PipelineError, PipelineErrorKind};
+    use kona_genesis::{ChainGenesis, RollupConfig, SystemConfig};
+    use kona_preimage::errors::{PreimageOracleError, PreimageOracleResult};
+    use kona_proof::boot::{
+        L1_HEAD_KEY, L2_CHAIN_ID_KEY, L2_CLAIM_BLOCK_NUMBER_KEY, L2_CLAIM_KEY, L2_OUTPUT_ROOT_KEY,
+        L2_ROLLUP_CONFIG_KEY,
+    };
+    use std::sync::Mutex;
+
+    /// An oracle serving fixed preimages and recording the hints written to it.
+    #[derive(Debug, Clone, Default)]
+    struct MockOracle {
+        preimages: Arc<Vec<(PreimageKey, Vec<u8>)>>,
+        hints: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PreimageOracleClient for MockOracle {
+        async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+            self.preimages
+                .iter()
+                .find(|(preimage_key, _)| *preimage_key == key)
+                .map(|(_, preimage)| preimage.clone())
+                .ok_or(PreimageOracleError::KeyNotFound)
+        }
+
+        async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
+            let preimage = self.get(key).await?;
+            assert_eq!(preimage.len(), buf.len(), "preimage of unexpected length");
+            buf.copy_from_slice(&preimage);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HintWriterClient for MockOracle {
+        async fn write(&self, hint: &str) -> PreimageOracleResult<()> {
+            self.hints.lock().unwrap().push(hint.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_safe_head_hash_reads_the_output_preimage() {
+        let output_root = B256::repeat_byte(0x11);
+        let safe_head_hash = B256::repeat_byte(0x22);
+        let mut output = vec![0u8; 96];
+        output.extend_from_slice(safe_head_hash.as_slice());
+        let oracle = MockOracle {
+            preimages: Arc::new(vec![(PreimageKey::new_keccak256(*output_root), output)]),
+            ..Default::default()
+        };
+
+        let hash = fetch_safe_head_hash(&oracle, output_root).await.unwrap();
+
+        assert_eq!(hash, safe_head_hash);
+        let hints = oracle.hints.lock().unwrap();
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0].contains(&alloy_primitives::hex::encode(output_root)));
+    }
+
+    /// The error of [MockCelestiaProvider], which never fails.
+    #[derive(Debug)]
+    struct NeverFails;
+
+    impl core::fmt::Display for NeverFails {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("never fails")
+        }
+    }
+
+    impl From<NeverFails> for PipelineErrorKind {
+        fn from(err: NeverFails) -> Self {
+            PipelineError::Provider(err.to_string()).crit()
+        }
+    }
+
+    /// A Celestia provider serving the same blob for every request, recording the requests.
+    #[derive(Debug, Clone)]
+    struct MockCelestiaProvider {
+        blob: Bytes,
+        requests: Arc<Mutex<Vec<(u64, Namespace, Commitment)>>>,
+    }
+
+    #[async_trait]
+    impl CelestiaProvider for MockCelestiaProvider {
+        type Error = NeverFails;
+
+        async fn blob_get(&self, _: u64, _: Commitment) -> Result<Bytes, Self::Error> {
+            panic!("the test pointer carries its namespace")
+        }
+
+        async fn blob_get_namespaced(
+            &self,
+            height: u64,
+            namespace: Namespace,
+            commitment: Commitment,
+        ) -> Result<Bytes, Self::Error> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((height, namespace, commitment));
+            Ok(self.blob.clone())
+        }
+    }
+
+    /// The encoding of a trie made of a single leaf at the key of the first item of an ordered
+    /// list, holding `value`.
+    fn single_leaf_trie(value: &Bytes) -> Vec<u8> {
+        // The hex prefix encoding of the even length leaf path `rlp(0)`.
+        let path = Bytes::from_static(&[0x20, 0x80]);
+        let mut leaf = Vec::new();
+        alloy_rlp::Header {
+            list: true,
+            payload_length: path.length() + value.length(),
+        }
+        .encode(&mut leaf);
+        path.encode(&mut leaf);
+        value.encode(&mut leaf);
+        leaf
+    }
+
+    #[tokio::test]
+    async fn test_derivation_consumes_the_blob_of_the_injected_provider() {
+        let namespace = Namespace::new_v0(b"hana").unwrap();
+        let blob = Bytes::from_static(b"hana-frame");
+        let commitment = Commitment::from_blob(
+            namespace,
+            &blob,
+            SHARE_VERSION_ZERO,
+            None,
+            AppVersion::latest(),
+        )
+        .unwrap();
+        let pointer = CelestiaBlobPointer::new_namespaced(42, commitment, namespace).encode();
+
+        // The only L1 block holds the batcher transaction posting the pointer.
+        let batch_inbox = Address::repeat_byte(0xff);
+        let tx = TxEnvelope::Legacy(Signed::new_unchecked(
+            TxLegacy {
+                to: TxKind::Call(batch_inbox),
+                input: pointer.into(),
+                ..Default::default()
+            },
+            Signature::test_signature(),
+            B256::ZERO,
+        ));
+        let batcher = tx.recover_signer().unwrap();
+        let transactions = single_leaf_trie(&tx.encoded_2718().into());
+        let l1_header = Header {
+            transactions_root: keccak256(&transactions),
+            ..Default::default()
+        };
+        let l1_head = l1_header.hash_slow();
+
+        // The safe head is the L2 genesis, derived from the L1 block.
+        let l2_header = Header::default();
+        let safe_head_hash = l2_header.hash_slow();
+        let rollup_config = RollupConfig {
+            l1_chain_id: 1,
+            batch_inbox_address: batch_inbox,
+            genesis: ChainGenesis {
+                l1: BlockNumHash::new(l1_head, 0),
+                l2: BlockNumHash::new(safe_head_hash, 0),
+                system_config: Some(SystemConfig {
+                    batcher_address: batcher,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (agreed, claimed) = (B256::repeat_byte(0xa0), B256::repeat_byte(0xc0));
+        let mut output = vec![0u8; 96];
+        output.extend_from_slice(safe_head_hash.as_slice());
+        let local = |key: U256| PreimageKey::new_local(key.to());
+        let oracle = MockOracle {
+            preimages: Arc::new(vec![
+                (local(L1_HEAD_KEY), l1_head.to_vec()),
+                (local(L2_OUTPUT_ROOT_KEY), agreed.to_vec()),
+                (local(L2_CLAIM_KEY), claimed.to_vec()),
+                (
+                    local(L2_CLAIM_BLOCK_NUMBER_KEY),
+                    1u64.to_be_bytes().to_vec(),
+                ),
+                (local(L2_CHAIN_ID_KEY), 424242u64.to_be_bytes().to_vec()),
+                (
+                    local(L2_ROLLUP_CONFIG_KEY),
+                    serde_json::to_vec(&rollup_config).unwrap(),
+                ),
+                (PreimageKey::new_keccak256(*agreed), output),
+                (
+                    PreimageKey::new_keccak256(*safe_head_hash),
+                    alloy_rlp::encode(&l2_header),
+                ),
+                (
+                    PreimageKey::new_keccak256(*l1_head),
+                    alloy_rlp::encode(&l1_header),
+                ),
+                (
+                    PreimageKey::new_keccak256(*l1_header.transactions_root),
+                    transactions,
+                ),
+                // The empty transactions trie of the L2 genesis.
+                (PreimageKey::new_keccak256(*EMPTY_ROOT_HASH), vec![0x80]),
+            ]),
+            ..Default::default()
+        };
+        let provider = MockCelestiaProvider {
+            blob,
+            requests: Default::default(),
+        };
+
+        // The blob isn't a valid frame, so the safe head can't advance and the claim is rejected
+        // once the L1 data runs out.
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            run_with_celestia_provider(oracle.clone(), oracle, {
+                let provider = provider.clone();
+                move |_| provider
+            }),
+        )
+        .await
+        .expect("derivation stops once the L1 data runs out");
+        assert!(result.is_err());
+
+        // Derivation followed the pointer to the injected provider, and was served its blob.
+        assert_eq!(
+            *provider.requests.lock().unwrap(),
+            [(42, namespace, commitment)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_with_celestia_provider_builds_the_provider_after_boot() {
+        let built = Arc::new(AtomicBool::new(false));
+        let oracle = MockOracle::default();
+
+        // Without boot info the program stops before the pipeline, and the injected provider.
+        let err = run_with_celestia_provider(oracle.clone(), oracle, {
+            let built = built.clone();
+            move |oracle| {
+                built.store(true, Ordering::SeqCst);
+                OracleCelestiaProvider::new(oracle)
+            }
+        })
+        .await
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            FaultProofProgramError::OracleProviderError(_)
+        ));
+        assert!(!built.load(Ordering::SeqCst));
+    }
+}
@@ -13,6 +13,7 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use kona_cli::{cli_styles, LogArgs, LogConfig};
 use serde::Serialize;
+use std::process::ExitCode;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
@@ -22,7 +23,7 @@ Hana in order to handle Celestia DA derivation and proofs.
 The host can run in two modes: server mode and native mode. In server mode, the host runs the pre-image
 server and waits for the client program in the parent process to request pre-images. In native
 mode, the host runs the client program in a separate thread with the pre-image server in the
-primary thread.
+primary thread, or as a child process when `--client-subprocess` is set.
 ";
 
 /// The host binary CLI application arguments.
@@ -43,20 +44,41 @@ pub enum HostMode {
     /// Run the host in single-chain mode.
     #[cfg(feature = "celestia")]
     Celestia(hana_host::celestia::CelestiaChainHost),
+    /// Fetch and store the Celestia oracle payloads of a range of heights ahead of a run.
+    #[cfg(feature = "celestia")]
+    WarmCache(hana_host::celestia::WarmCache),
 }
 
 #[tokio::main(flavor = "multi_thread")]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     let cfg = HostCli::parse();
     LogConfig::new(cfg.log_args).init_tracing_subscriber(None::<EnvFilter>)?;
 
-    match cfg.mode {
+    let exit_code = match cfg.mode {
+        #[cfg(feature = "celestia")]
+        HostMode::Celestia(cfg) => cfg.run().await,
         #[cfg(feature = "celestia")]
-        HostMode::Celestia(cfg) => {
+        HostMode::WarmCache(cfg) => {
             cfg.start().await?;
+            ExitCode::SUCCESS
         }
-    }
+    };
 
     info!("Exiting host program.");
-    Ok(())
+    Ok(exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbosity_flag_sets_the_log_level() {
+        // Blob lengths and commitments are logged at debug level (4), their contents at trace
+        // level (5).
+        for (flag, level) in [("-vvvv", 4), ("-vvvvv", 5)] {
+            let cli = HostCli::try_parse_from(["hana-host", flag, "celestia"]).unwrap();
+            assert_eq!(cli.log_args.level, level, "{flag}");
+        }
+    }
 }
@@ -0,0 +1,169 @@
+//! Cross-checks the L1 headers Blobstream proofs are anchored at against the beacon chain.
+
+use alloy_consensus::Header;
+use alloy_primitives::B256;
+use anyhow::{anyhow, ensure, Result};
+use serde_json::Value;
+
+/// Checks that execution headers are the canonical ones according to a beacon node.
+#[derive(Debug, Clone)]
+pub struct BeaconHeaderVerifier {
+    /// The HTTP client.
+    client: reqwest::Client,
+    /// The base URL of the beacon API.
+    base_url: String,
+    /// The genesis time of the beacon chain.
+    genesis_time: u64,
+    /// The duration of a slot in seconds.
+    seconds_per_slot: u64,
+}
+
+impl BeaconHeaderVerifier {
+    /// Creates a verifier querying the beacon API at `base_url`.
+    pub async fn new(base_url: String) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let base_url = base_url.trim_end_matches('/').to_string();
+
+        let genesis = get_json(&client, &format!("{base_url}/eth/v1/beacon/genesis")).await?;
+        let genesis_time = parse_u64(&genesis["data"]["genesis_time"])?;
+        let spec = get_json(&client, &format!("{base_url}/eth/v1/config/spec")).await?;
+        let seconds_per_slot = parse_u64(&spec["data"]["SECONDS_PER_SLOT"])?;
+        ensure!(seconds_per_slot > 0, "beacon spec has no slot duration");
+
+        Ok(Self {
+            client,
+            base_url,
+            genesis_time,
+            seconds_per_slot,
+        })
+    }
+
+    /// Ensures `header` is the execution payload of the beacon block at its slot.
+    pub async fn verify_header(&self, header: &Header) -> Result<()> {
+        ensure!(
+            header.timestamp >= self.genesis_time,
+            "L1 block {} predates the beacon chain",
+            header.number
+        );
+        let slot = (header.timestamp - self.genesis_time) / self.seconds_per_slot;
+
+        let block = get_json(
+            &self.client,
+            &format!("{}/eth/v2/beacon/blocks/{slot}", self.base_url),
+        )
+        .await?;
+        let beacon_hash: B256 = block["data"]["message"]["body"]["execution_payload"]["block_hash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("beacon block at slot {slot} has no execution payload"))?
+            .parse()?;
+
+        let hash = header.hash_slow();
+        ensure!(
+            hash == beacon_hash,
+            "L1 block {} has hash {hash}, but the beacon block at slot {slot} has {beacon_hash}",
+            header.number
+        );
+
+        Ok(())
+    }
+}
+
+/// Fetches `url` and parses the response as JSON.
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<Value> {
+    Ok(client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Parses a beacon API quantity, encoded as a decimal string.
+fn parse_u64(value: &Value) -> Result<u64> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected a quantity, got {value}"))?
+        .parse()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serves a beacon API with 12 second slots from genesis, whose block at slot 2 has the
+    /// execution payload `block_hash`, and returns its base URL.
+    async fn serve_beacon(block_hash: B256) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let read = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..read]);
+                let body = match request.split_whitespace().nth(1).unwrap_or_default() {
+                    "/eth/v1/beacon/genesis" => json!({ "data": { "genesis_time": "0" } }),
+                    "/eth/v1/config/spec" => json!({ "data": { "SECONDS_PER_SLOT": "12" } }),
+                    "/eth/v2/beacon/blocks/2" => json!({
+                        "data": { "message": { "body": {
+                            "execution_payload": { "block_hash": block_hash }
+                        } } }
+                    }),
+                    _ => {
+                        let response = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                        continue;
+                    }
+                }
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        format!("http://{addr}/")
+    }
+
+    /// Returns an execution header produced during slot 2.
+    fn header() -> Header {
+        Header {
+            number: 1,
+            timestamp: 24,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_header_accepts_the_canonical_header() {
+        let verifier = BeaconHeaderVerifier::new(serve_beacon(header().hash_slow()).await)
+            .await
+            .unwrap();
+
+        verifier.verify_header(&header()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verify_header_rejects_a_header_the_beacon_chain_disagrees_with() {
+        let verifier = BeaconHeaderVerifier::new(serve_beacon(B256::repeat_byte(0xaa)).await)
+            .await
+            .unwrap();
+
+        let err = verifier.verify_header(&header()).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("but the beacon block at slot 2 has"),
+            "{err}"
+        );
+    }
+}
@@ -1,37 +1,73 @@
 //! This module contains all CLI-specific code for the single celestia chain entrypoint.
 
-use celestia_types::nmt::Namespace;
-use clap::Parser;
-use hana_oracle::hint::HintWrapper;
+use alloy_chains::NamedChain;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use celestia_types::nmt::{Namespace, NS_ID_SIZE, NS_SIZE};
+use clap::{Parser, ValueEnum};
+use hana_oracle::{
+    hint::HintWrapper, metrics::VerificationMetrics, provider::OracleCelestiaProvider,
+};
 use kona_genesis::RollupConfig;
 use kona_host::{
     eth::rpc_provider,
     single::{SingleChainHost, SingleChainHostError, SingleChainLocalInputs, SingleChainProviders},
-    DiskKeyValueStore, MemoryKeyValueStore, OfflineHostBackend, OnlineHostBackend,
+    DiskKeyValueStore, KeyValueStore, MemoryKeyValueStore, OfflineHostBackend, OnlineHostBackend,
     OnlineHostBackendCfg, PreimageServer, SharedKeyValueStore, SplitKeyValueStore,
 };
 
 use kona_cli::cli_styles;
+use kona_client::single::FaultProofProgramError;
 use serde::Serialize;
 
-use alloy_primitives::hex;
+use alloy_primitives::{hex, Address, B256};
+use alloy_provider::{Provider, RootProvider};
 use anyhow::{anyhow, Result};
+use hana_blobstream::blobstream::{
+    blobstream_address, blobstream_celestia_network, resolve_blobstream_address,
+    BlobstreamAddresses, CelestiaNetwork,
+};
+use hana_proofs::{
+    blobstream_inclusion::{ProofConcurrency, ProofOptions},
+    commitment_index::CommitmentIndex,
+    header_cache::{HeaderCache, DEFAULT_HEADER_CACHE_SIZE},
+    rpc_counter::RpcCallCounter,
+};
 use kona_preimage::{
     BidirectionalChannel, Channel, HintReader, HintWriter, OracleReader, OracleServer,
 };
 use kona_providers_alloy::{OnlineBeaconClient, OnlineBlobProvider};
+use kona_registry::ROLLUP_CONFIGS;
 use kona_std_fpvm::{FileChannel, FileDescriptor};
 use op_alloy_network::Optimism;
-use std::sync::Arc;
+use std::{
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 use tokio::{
-    sync::RwLock,
-    task::{self, JoinHandle},
+    sync::{Mutex, RwLock},
+    task::{self, JoinError, JoinHandle},
+    time::timeout,
 };
+use tracing::{error, info, warn};
 
-use super::{CelestiaChainHintHandler, CelestiaChainProviders, OnlineCelestiaProvider};
+use super::{
+    counting_rpc_provider,
+    exit::host_errors,
+    serve_health,
+    subprocess::{spawn_client, ClientProcess},
+    BeaconHeaderVerifier, BlobPoll, BlobstreamProofGenerator, CelestiaChainHintHandler,
+    CelestiaChainProviders, CelestiaLocalInputs, DerivationManifest, HealthState, HostExitCode,
+    ManifestBackend, ManifestRecorder, OnlineCelestiaProvider, ProofFailure, SharedHostResources,
+    SharedPreimageStore,
+};
 
 /// The host binary CLI application arguments.
-#[derive(Default, Parser, Serialize, Clone, Debug)]
+#[derive(Parser, Serialize, Clone, Debug)]
 #[command(styles = cli_styles())]
 pub struct CelestiaChainHost {
     #[clap(flatten)]
@@ -41,7 +77,7 @@ pub struct CelestiaChainHost {
 }
 
 /// The host binary CLI application arguments.
-#[derive(Default, Parser, Serialize, Clone, Debug)]
+#[derive(Parser, Serialize, Clone, Debug)]
 #[command(styles = cli_styles())]
 pub struct CelestiaCfg {
     /// Connection to celestia network
@@ -50,25 +86,276 @@ pub struct CelestiaCfg {
     /// Token for the Celestia node connection
     #[clap(long, alias = "celestia-auth", env)]
     pub auth_token: Option<String>,
-    /// Celestia Namespace to fetch data from
-    #[clap(long, alias = "celestia-namespace", env)]
-    pub namespace: Option<String>,
+    /// Celestia Namespace to fetch data from, either a namespace id or a full namespace, hex
+    /// encoded or prefixed with `base64:` when base64 encoded
+    #[clap(long, alias = "celestia-namespace", env, value_parser = parse_namespace_arg)]
+    pub namespace: Option<NamespaceArg>,
+    /// Version of the Celestia namespace. Namespace ids are built with this version, v0 when
+    /// unset, and full namespaces must carry it
+    #[clap(long, env)]
+    pub namespace_version: Option<u8>,
+    /// Path of the Blobstream commitment index, loaded on startup and updated as commitments are
+    /// discovered
+    #[clap(long, env)]
+    pub commitment_index: Option<PathBuf>,
+    /// Path to the client program binary. When set in native mode, the client runs as a child
+    /// process talking to the host over the hint and preimage file descriptors, rather than in a
+    /// thread of the host process
+    #[clap(long, env)]
+    pub client_subprocess: Option<PathBuf>,
+    /// Number of blocks behind the L1 head to anchor Blobstream storage proofs at, for L1 nodes
+    /// that can't serve proofs at the head. The client rejects proofs anchored more than 256
+    /// blocks behind
+    #[clap(long, env, default_value_t = 0)]
+    pub l1_anchor_depth: u64,
+    /// Minimum number of blocks the L1 node's head must be ahead of the block Blobstream storage
+    /// proofs are anchored at, guarding proofs against reorgs. 0 disables the check
+    #[clap(long, env, default_value_t = DEFAULT_L1_CONFIRMATIONS)]
+    pub l1_confirmations: u64,
+    /// Blobstream deployments of L1 chains without a canonical one, as comma separated
+    /// `<l1 chain id>=<address>` entries. The table is served to the client as a local input,
+    /// which a client reading it must commit to itself
+    #[clap(long, env, value_delimiter = ',', value_parser = parse_blobstream_address_entry)]
+    pub blobstream_addresses: Vec<(u64, Address)>,
+    /// Expected hash of the Blobstream contract code, proof generation fails if the deployed code
+    /// doesn't match
+    #[clap(long, env)]
+    pub blobstream_code_hash: Option<B256>,
+    /// Directory to write every generated Celestia oracle payload to, named by height and
+    /// commitment, for debugging the in-program verification
+    #[clap(long, env)]
+    pub dump_payloads: Option<PathBuf>,
+    /// Celestia network the data is posted to (mainnet, mocha or arabica), checked against the
+    /// network relayed by the Blobstream deployment on the L1 chain
+    #[clap(long, env)]
+    pub celestia_network: Option<CelestiaNetwork>,
+    /// Address of an archive L1 node, used to fetch Blobstream storage proofs the L1 node can no
+    /// longer serve
+    #[clap(long, env)]
+    pub l1_archive_node_address: Option<String>,
+    /// Backend of the preimage key-value store. Defaults to `disk` when a data directory is set,
+    /// `memory` otherwise
+    #[clap(long, env, value_enum)]
+    pub kv_backend: Option<KvBackend>,
+    /// Maximum runtime of a native run in seconds. When exceeded, the client and preimage server
+    /// are stopped and the host exits with the timeout exit code
+    #[clap(long, env)]
+    pub max_runtime: Option<u64>,
+    /// Whether to run the client's verification of every generated Blobstream proof before
+    /// storing it, aborting on proofs that fail to verify
+    #[clap(long, env, default_value_t = true, action = clap::ArgAction::Set)]
+    pub verify_on_generate: bool,
+    /// Maximum number of concurrent requests to the Celestia node while generating proofs, at
+    /// least 1
+    #[clap(long, env)]
+    pub celestia_concurrency: Option<NonZeroUsize>,
+    /// Maximum number of concurrent requests to the L1 node while generating proofs, at least 1
+    #[clap(long, env)]
+    pub l1_concurrency: Option<NonZeroUsize>,
+    /// Number of L1 headers kept in memory while generating proofs, so that the proofs anchored at
+    /// the same L1 head don't fetch its headers again. Disabled when 0
+    #[clap(long, env, default_value_t = DEFAULT_HEADER_CACHE_SIZE)]
+    pub l1_header_cache_size: usize,
+    /// Address to serve the `/healthz`, `/readyz` and `/metrics` endpoints on
+    #[clap(long, env)]
+    pub health_addr: Option<SocketAddr>,
+    /// Check the L1 header every Blobstream proof is anchored at against the execution payload
+    /// of the beacon chain, for L1 execution nodes trusted less than the beacon node
+    #[clap(long, env)]
+    pub verify_l1_header_with_beacon: bool,
+    /// The rollup only posts Celestia pointers, so the host runs without a beacon API. Requests
+    /// for L1 blobs fail if the derivation ever makes one
+    #[clap(long, env)]
+    pub celestia_only: bool,
+    /// Count the calls made to the L1 and Celestia nodes by RPC method and log a summary when
+    /// the run ends
+    #[clap(long, env)]
+    pub count_rpc_calls: bool,
+    /// Log the time spent in each stage of every Blobstream proof generated, as structured fields
+    #[clap(long, env)]
+    pub timing: bool,
+    /// Name of a known L2 chain (e.g. `base` or `optimism-sepolia`) to take the rollup config
+    /// from the superchain registry, instead of `--rollup-config-path`
+    #[clap(long, env)]
+    pub l2_chain: Option<String>,
+    /// Seconds to keep polling for a Celestia blob the node reports as not found, for deriving at
+    /// the tip of the Celestia chain. Disabled when 0
+    #[clap(long, env, default_value_t = 0)]
+    pub blob_poll_window: u64,
+    /// Milliseconds before retrying a Celestia blob that is not found, doubled after every retry
+    #[clap(long, env, default_value_t = 500)]
+    pub blob_poll_interval: u64,
+    /// Path to write a JSON manifest of the run to when it succeeds, listing the claimed output
+    /// root, the L1 head, the L2 chain id and every Celestia blob served to the client
+    #[clap(long, env)]
+    pub manifest_out: Option<PathBuf>,
+    /// The recorder of the blobs listed in the manifest, shared by every clone of the config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub manifest: ManifestRecorder,
+    /// The counter RPC calls are recorded in when `count_rpc_calls` is set, shared by every clone
+    /// of the config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub rpc_counter: RpcCallCounter,
+    /// The cache of L1 headers, created with `l1_header_cache_size` on first use and shared by
+    /// every clone of the config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub header_cache: Arc<OnceLock<HeaderCache>>,
+    /// The limits on concurrent proof requests, created from `celestia_concurrency` and
+    /// `l1_concurrency` on first use and shared by every clone of the config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub proof_concurrency: Arc<OnceLock<ProofConcurrency>>,
+    /// The counters of the Blobstream proofs failing verification in a native run, exported by
+    /// the health server and shared by every clone of the config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub verification_metrics: Arc<VerificationMetrics>,
+    /// The exit code of the last failed Celestia proof generation, shared by every clone of the
+    /// config
+    #[clap(skip)]
+    #[serde(skip)]
+    pub proof_failure: ProofFailure,
+}
+
+/// The namespace version namespace ids are built with when no version is given.
+pub const DEFAULT_NAMESPACE_VERSION: u8 = 0;
+
+/// The slot duration of the placeholder blob provider created without a beacon API.
+const DEFAULT_SECONDS_PER_SLOT: u64 = 12;
+
+/// The confirmations required by default for the L1 block proofs are anchored at: two epochs,
+/// after which the block is finalized on Ethereum.
+pub const DEFAULT_L1_CONFIRMATIONS: u64 = 64;
+
+/// The backends the preimage key-value store can be kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KvBackend {
+    /// Keep preimages in memory, they are lost when the host exits.
+    Memory,
+    /// Keep preimages on disk, in the data directory.
+    Disk,
+}
+
+/// The arguments of a host given no flags, with the default values of the CLI.
+impl Default for CelestiaCfg {
+    fn default() -> Self {
+        Self {
+            celestia_connection: None,
+            auth_token: None,
+            namespace: None,
+            namespace_version: None,
+            commitment_index: None,
+            client_subprocess: None,
+            l1_anchor_depth: 0,
+            l1_confirmations: DEFAULT_L1_CONFIRMATIONS,
+            blobstream_addresses: Vec::new(),
+            blobstream_code_hash: None,
+            dump_payloads: None,
+            celestia_network: None,
+            l1_archive_node_address: None,
+            kv_backend: None,
+            max_runtime: None,
+            verify_on_generate: true,
+            celestia_concurrency: None,
+            l1_concurrency: None,
+            l1_header_cache_size: DEFAULT_HEADER_CACHE_SIZE,
+            health_addr: None,
+            verify_l1_header_with_beacon: false,
+            celestia_only: false,
+            count_rpc_calls: false,
+            timing: false,
+            l2_chain: None,
+            blob_poll_window: 0,
+            blob_poll_interval: 500,
+            manifest_out: None,
+            manifest: ManifestRecorder::default(),
+            rpc_counter: RpcCallCounter::default(),
+            header_cache: Arc::default(),
+            proof_concurrency: Arc::default(),
+            verification_metrics: Arc::default(),
+            proof_failure: ProofFailure::default(),
+        }
+    }
 }
 
 impl CelestiaChainHost {
-    /// Starts the [SingleChainHost] application.
-    pub async fn start(self) -> Result<(), SingleChainHostError> {
+    /// Starts the host like [CelestiaChainHost::start], also reporting the errors of the host
+    /// itself by their [HostExitCode], e.g. [HostExitCode::ConfigError] for an invalid
+    /// configuration.
+    pub async fn run(self) -> ExitCode {
+        match self.start().await {
+            Ok(exit_code) => exit_code,
+            Err(err) => {
+                error!(target: "host", "Host failed: {err}");
+                HostExitCode::from_host_error(&err).into()
+            }
+        }
+    }
+
+    /// Starts the [SingleChainHost] application, returning the exit code of the run.
+    pub async fn start(mut self) -> Result<ExitCode, SingleChainHostError> {
+        // The namespace is parsed with the arguments, check it agrees with its version before
+        // starting anything.
+        if self.celestia_args.namespace.is_some() {
+            self.namespace()?;
+        }
+
+        // The local inputs served to the client read the rollup config of a known chain id from
+        // the registry.
+        if let Some(ref name) = self.celestia_args.l2_chain {
+            let chain_id = registry_chain_id(name).map_err(|e| {
+                error!(target: "host", "{e}");
+                SingleChainHostError::Other(host_errors::UNKNOWN_L2_CHAIN)
+            })?;
+            self.single_host.l2_chain_id = Some(chain_id);
+        }
+
         if self.single_host.server {
             let hint = FileChannel::new(FileDescriptor::HintRead, FileDescriptor::HintWrite);
             let preimage =
                 FileChannel::new(FileDescriptor::PreimageRead, FileDescriptor::PreimageWrite);
 
-            self.start_server(hint, preimage).await?.await?
+            let result = self.start_server(hint, preimage).await?.await?;
+            self.log_rpc_calls();
+            result.map(|()| ExitCode::SUCCESS)
         } else {
             self.start_native().await
         }
     }
 
+    /// Writes the manifest of the run to `--manifest-out`, if set.
+    fn write_manifest(&self) {
+        let Some(ref path) = self.celestia_args.manifest_out else {
+            return;
+        };
+
+        let l2_chain_id = match self.read_rollup_config() {
+            Ok(config) => u64::from(config.l2_chain_id),
+            Err(_) => self.single_host.l2_chain_id.unwrap_or_default(),
+        };
+        let manifest = DerivationManifest {
+            l2_output_root: self.single_host.claimed_l2_output_root,
+            l1_head: self.single_host.l1_head,
+            l2_chain_id,
+            blobs: self.celestia_args.manifest.blobs(),
+        };
+        match manifest.write(path) {
+            Ok(()) => info!(target: "host", "Wrote the run manifest to {}", path.display()),
+            Err(e) => error!(target: "host", "Failed to write the run manifest: {e}"),
+        }
+    }
+
+    /// Logs the RPC calls made during the run, if they are counted.
+    fn log_rpc_calls(&self) {
+        if self.celestia_args.count_rpc_calls {
+            info!(target: "host", "RPC calls: {}", self.celestia_args.rpc_counter);
+        }
+    }
+
     /// Starts the preimage server, communicating with the client over the provided channels.
     pub async fn start_server<C>(
         &self,
@@ -78,61 +365,275 @@ impl CelestiaChainHost {
     where
         C: Channel + Send + Sync + 'static,
     {
-        let kv_store = self.create_key_value_store()?;
+        let health = Arc::new(HealthState::new(
+            self.celestia_args.verification_metrics.clone(),
+        ));
+        if let Some(addr) = self.celestia_args.health_addr {
+            let health = health.clone();
+            task::spawn(async move {
+                if let Err(e) = serve_health(addr, health).await {
+                    error!(target: "host", "Health server failed: {e}");
+                }
+            });
+        }
 
-        let task_handle = if self.is_offline() {
-            task::spawn(async {
-                PreimageServer::new(
-                    OracleServer::new(preimage),
-                    HintReader::new(hint),
-                    Arc::new(OfflineHostBackend::new(kv_store)),
-                )
-                .start()
-                .await
-                .map_err(SingleChainHostError::from)
-            })
+        let resources = self.create_shared_resources().await?;
+        let task_handle = self.start_server_with(hint, preimage, &resources);
+        health.set_ready(resources.providers);
+
+        Ok(task_handle)
+    }
+
+    /// Creates the preimage store and providers, to be reused by every run started with
+    /// [CelestiaChainHost::start_server_with].
+    pub async fn create_shared_resources(
+        &self,
+    ) -> Result<SharedHostResources, SingleChainHostError> {
+        let store = self.create_preimage_store()?;
+        let providers = if self.is_offline() {
+            None
         } else {
-            let providers = self.create_providers().await?;
-            let backend = OnlineHostBackend::new(
-                self.clone(),
-                kv_store.clone(),
-                providers,
-                CelestiaChainHintHandler,
-            );
+            Some(self.create_providers().await?)
+        };
 
-            task::spawn(async {
+        Ok(SharedHostResources { store, providers })
+    }
+
+    /// Starts the preimage server of the run described by this config, communicating with the
+    /// client over the provided channels and reusing the providers and preimages of `resources`.
+    ///
+    /// The resources may have been created by the config of another run, e.g. with another l1
+    /// head, as long as it talks to the same nodes. Each run serves its own local inputs.
+    pub fn start_server_with<C>(
+        &self,
+        hint: C,
+        preimage: C,
+        resources: &SharedHostResources,
+    ) -> JoinHandle<Result<(), SingleChainHostError>>
+    where
+        C: Channel + Send + Sync + 'static,
+    {
+        let kv_store = self.key_value_store_with(resources.store.clone());
+        let manifest = self
+            .celestia_args
+            .manifest_out
+            .as_ref()
+            .map(|_| self.celestia_args.manifest.clone());
+
+        match resources.providers {
+            None => task::spawn(async {
                 PreimageServer::new(
                     OracleServer::new(preimage),
                     HintReader::new(hint),
-                    Arc::new(backend),
+                    Arc::new(ManifestBackend::new(
+                        OfflineHostBackend::new(kv_store),
+                        manifest,
+                    )),
                 )
                 .start()
                 .await
                 .map_err(SingleChainHostError::from)
-            })
-        };
+            }),
+            Some(ref providers) => {
+                let backend = ManifestBackend::new(
+                    OnlineHostBackend::new(
+                        self.clone(),
+                        kv_store,
+                        providers.clone(),
+                        CelestiaChainHintHandler,
+                    ),
+                    manifest,
+                );
 
-        Ok(task_handle)
+                task::spawn(async {
+                    PreimageServer::new(
+                        OracleServer::new(preimage),
+                        HintReader::new(hint),
+                        Arc::new(backend),
+                    )
+                    .start()
+                    .await
+                    .map_err(SingleChainHostError::from)
+                })
+            }
+        }
     }
 
     /// Starts the host in native mode, running both the client and preimage server in the same
     /// process.
-    async fn start_native(&self) -> Result<(), SingleChainHostError> {
+    ///
+    /// Returns the [HostExitCode] describing the outcome of the run.
+    async fn start_native(&self) -> Result<ExitCode, SingleChainHostError> {
+        if let Some(ref client_bin) = self.celestia_args.client_subprocess {
+            return self.start_subprocess(client_bin).await;
+        }
+
+        let exit_code = match self.run_native().await {
+            Ok(None) => {
+                error!(target: "host", "Run exceeded the maximum runtime, stopped");
+                self.timeout_exit_code()
+            }
+            Ok(Some(Ok(()))) => HostExitCode::Success,
+            Ok(Some(Err(err))) => {
+                error!(target: "host", "Client program failed: {err}");
+                HostExitCode::from_client_error(&err, &self.celestia_args.verification_metrics)
+            }
+            Err(err) => {
+                error!(target: "host", "Host failed: {err}");
+                HostExitCode::from_host_error(&err)
+            }
+        };
+
+        self.log_rpc_calls();
+        if exit_code == HostExitCode::Success {
+            self.write_manifest();
+        }
+
+        Ok(exit_code.into())
+    }
+
+    /// Runs the client program and preimage server in the same process, returning the result of
+    /// the client program, or `None` if the run exceeded the maximum runtime.
+    ///
+    /// A run exceeding the maximum runtime cancels the client and server tasks and waits for them
+    /// to stop.
+    async fn run_native(
+        &self,
+    ) -> Result<Option<Result<(), FaultProofProgramError>>, SingleChainHostError> {
         let hint = BidirectionalChannel::new()?;
         let preimage = BidirectionalChannel::new()?;
 
-        let server_task = self.start_server(hint.host, preimage.host).await?;
+        let mut server_task = self.start_server(hint.host, preimage.host).await?;
         let hint_writer = HintWriter::new(hint.client);
         let oracle_reader = OracleReader::new(preimage.client);
-        let client_task = task::spawn(hana_client::single::run(
+        let metrics = self.celestia_args.verification_metrics.clone();
+        let mut client_task = task::spawn(hana_client::single::run_with_celestia_provider(
             oracle_reader.clone(),
             hint_writer.clone(),
+            move |oracle| OracleCelestiaProvider::new(oracle).with_metrics(metrics),
         ));
 
-        let (_, client_result) = tokio::try_join!(server_task, client_task)?;
+        let max_runtime = self.celestia_args.max_runtime.map(Duration::from_secs);
+        match join_within(max_runtime, &mut server_task, &mut client_task).await {
+            Some(joined) => {
+                let (_, client_result) = joined?;
+                Ok(Some(client_result))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Starts the preimage server and runs the client program at `client_bin` as a child process,
+    /// mirroring the topology of an FPVM deployment.
+    ///
+    /// Returns the exit code of the client program.
+    async fn start_subprocess(&self, client_bin: &Path) -> Result<ExitCode, SingleChainHostError> {
+        let ClientProcess {
+            hint,
+            preimage,
+            mut child,
+        } = spawn_client(client_bin)?;
 
-        // Bubble up the exit status of the client program if execution completes.
-        std::process::exit(client_result.is_err() as i32)
+        let server_task = self.start_server(hint, preimage).await?;
+        let status = match self.celestia_args.max_runtime {
+            Some(max_runtime) => {
+                match timeout(Duration::from_secs(max_runtime), child.wait()).await {
+                    Ok(status) => status?,
+                    Err(_) => {
+                        error!(target: "host", "Run exceeded the maximum runtime, stopping");
+                        child.kill().await?;
+                        server_task.abort();
+                        let _ = server_task.await;
+                        self.log_rpc_calls();
+                        return Ok(self.timeout_exit_code().into());
+                    }
+                }
+            }
+            None => child.wait().await?,
+        };
+        server_task.abort();
+        let _ = server_task.await;
+
+        info!(target: "host", "Client program exited with {status}");
+        self.log_rpc_calls();
+        if status.success() {
+            self.write_manifest();
+        }
+        Ok(status
+            .code()
+            .and_then(|code| u8::try_from(code).ok())
+            .map_or(HostExitCode::Other.into(), ExitCode::from))
+    }
+
+    /// Returns the exit code of a run exceeding the maximum runtime: the code of the proof error
+    /// being retried if the last Celestia proof failed, [HostExitCode::Timeout] otherwise.
+    fn timeout_exit_code(&self) -> HostExitCode {
+        self.celestia_args
+            .proof_failure
+            .exit_code()
+            .unwrap_or(HostExitCode::Timeout)
+    }
+
+    /// Returns the Celestia namespace given with `--namespace`, of the `--namespace-version`.
+    fn namespace(&self) -> Result<Namespace, SingleChainHostError> {
+        self.celestia_args
+            .namespace
+            .as_ref()
+            .ok_or(SingleChainHostError::Other(host_errors::MISSING_NAMESPACE))?
+            .namespace(self.celestia_args.namespace_version)
+            .map_err(|e| {
+                error!(target: "host", "{e}");
+                SingleChainHostError::Other(host_errors::INVALID_NAMESPACE)
+            })
+    }
+
+    /// Returns the custom Blobstream deployments given with `--blobstream-addresses`.
+    pub fn blobstream_addresses(&self) -> BlobstreamAddresses {
+        self.celestia_args
+            .blobstream_addresses
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Returns the Blobstream address the client verifies proofs against, resolved from the L1
+    /// chain id like the address the proofs are generated for.
+    pub async fn expected_blobstream_address(&self, l1_provider: &RootProvider) -> Result<Address> {
+        let chain_id = l1_provider.get_chain_id().await?;
+        resolve_blobstream_address(chain_id, &self.blobstream_addresses())
+            .ok_or_else(|| anyhow!("no Blobstream deployment known for L1 chain {chain_id}"))
+    }
+
+    /// Returns the [ProofOptions] used when generating Blobstream proofs. The concurrency limits
+    /// and the header cache are created once, every call returns options sharing them.
+    pub fn proof_options(&self) -> ProofOptions {
+        ProofOptions {
+            anchor_depth: self.celestia_args.l1_anchor_depth,
+            min_confirmations: self.celestia_args.l1_confirmations,
+            blobstream_addresses: self.blobstream_addresses(),
+            expected_code_hash: self.celestia_args.blobstream_code_hash,
+            concurrency: self
+                .celestia_args
+                .proof_concurrency
+                .get_or_init(|| {
+                    ProofConcurrency::new(
+                        self.celestia_args.celestia_concurrency,
+                        self.celestia_args.l1_concurrency,
+                    )
+                })
+                .clone(),
+            rpc_counter: self
+                .celestia_args
+                .count_rpc_calls
+                .then(|| self.celestia_args.rpc_counter.clone()),
+            header_cache: self
+                .celestia_args
+                .header_cache
+                .get_or_init(|| HeaderCache::new(self.celestia_args.l1_header_cache_size))
+                .clone(),
+            timing: self.celestia_args.timing,
+            ..Default::default()
+        }
     }
 
     /// Returns `true` if the host is running in offline mode.
@@ -143,8 +644,17 @@ impl CelestiaChainHost {
             && self.single_host.data_dir.is_some()
     }
 
-    /// Reads the [RollupConfig] from the file system and returns it as a string.
+    /// Reads the [RollupConfig] of the `--l2-chain` from the registry, or from the file system
+    /// when no chain is named.
     pub fn read_rollup_config(&self) -> Result<RollupConfig> {
+        if let Some(ref name) = self.celestia_args.l2_chain {
+            let chain_id = registry_chain_id(name)?;
+            return ROLLUP_CONFIGS
+                .get(&chain_id)
+                .cloned()
+                .ok_or_else(|| anyhow!("No rollup config for chain `{name}` in the registry"));
+        }
+
         let path = self
             .single_host
             .rollup_config_path
@@ -165,63 +675,148 @@ impl CelestiaChainHost {
     }
 
     /// Creates the key-value store for the host backend.
-    fn create_key_value_store(&self) -> Result<SharedKeyValueStore, SingleChainHostError> {
-        let local_kv_store = SingleChainLocalInputs::new(self.single_host.clone());
+    pub(crate) fn create_key_value_store(
+        &self,
+    ) -> Result<SharedKeyValueStore, SingleChainHostError> {
+        Ok(self.key_value_store_with(self.create_preimage_store()?))
+    }
 
-        let kv_store: SharedKeyValueStore = if let Some(ref data_dir) = self.single_host.data_dir {
-            let disk_kv_store = DiskKeyValueStore::new(data_dir.clone());
-            let split_kv_store = SplitKeyValueStore::new(local_kv_store, disk_kv_store);
-            Arc::new(RwLock::new(split_kv_store))
-        } else {
-            let mem_kv_store = MemoryKeyValueStore::new();
-            let split_kv_store = SplitKeyValueStore::new(local_kv_store, mem_kv_store);
-            Arc::new(RwLock::new(split_kv_store))
+    /// Creates the store keeping the preimages fetched by the host, in the configured backend.
+    fn create_preimage_store(&self) -> Result<SharedPreimageStore, SingleChainHostError> {
+        let backend = self
+            .celestia_args
+            .kv_backend
+            .unwrap_or(match self.single_host.data_dir {
+                Some(_) => KvBackend::Disk,
+                None => KvBackend::Memory,
+            });
+
+        let store = match backend {
+            KvBackend::Disk => {
+                let data_dir = self
+                    .single_host
+                    .data_dir
+                    .clone()
+                    .ok_or(SingleChainHostError::Other(host_errors::MISSING_DATA_DIR))?;
+                SharedPreimageStore::new(DiskKeyValueStore::new(data_dir))
+            }
+            KvBackend::Memory => SharedPreimageStore::new(MemoryKeyValueStore::new()),
         };
 
-        Ok(kv_store)
+        Ok(store)
+    }
+
+    /// Creates a key-value store keeping preimages in `backend`, behind the local inputs of the
+    /// host, see [CelestiaLocalInputs].
+    pub fn key_value_store_with<B>(&self, backend: B) -> SharedKeyValueStore
+    where
+        B: KeyValueStore + Send + Sync + 'static,
+    {
+        let local_kv_store = CelestiaLocalInputs::new(
+            SingleChainLocalInputs::new(self.single_host.clone()),
+            &self.blobstream_addresses(),
+        );
+        Arc::new(RwLock::new(SplitKeyValueStore::new(
+            local_kv_store,
+            backend,
+        )))
     }
 
     /// Creates the providers required for the host backend.
-    async fn create_providers(&self) -> Result<CelestiaChainProviders, SingleChainHostError> {
-        let l1_provider = rpc_provider(
-            self.single_host
-                .l1_node_address
-                .as_ref()
-                .ok_or(SingleChainHostError::Other("Provider must be set"))?,
-        )
-        .await;
-        let blob_provider = OnlineBlobProvider::init(OnlineBeaconClient::new_http(
-            self.single_host
-                .l1_beacon_address
-                .clone()
-                .ok_or(SingleChainHostError::Other("Beacon API URL must be set"))?,
-        ))
-        .await;
+    pub(crate) async fn create_providers(
+        &self,
+    ) -> Result<CelestiaChainProviders, SingleChainHostError> {
+        let l1_provider = self
+            .l1_rpc_provider(
+                self.single_host
+                    .l1_node_address
+                    .as_ref()
+                    .ok_or(SingleChainHostError::Other(host_errors::MISSING_L1_NODE))?,
+            )
+            .await?;
+        if let Some(network) = self.celestia_args.celestia_network {
+            let chain_id = l1_provider
+                .get_chain_id()
+                .await
+                .map_err(|_| SingleChainHostError::Other(host_errors::L1_CHAIN_ID_UNAVAILABLE))?;
+            check_celestia_network(network, chain_id)?;
+        }
+
+        let has_beacon_api = self.single_host.l1_beacon_address.is_some();
+        let blob_provider = match self.single_host.l1_beacon_address.clone() {
+            Some(address) => OnlineBlobProvider::init(OnlineBeaconClient::new_http(address)).await,
+            None if self.celestia_args.celestia_only => {
+                warn!(target: "host", "No beacon API set, requests for L1 blobs will fail");
+                // A placeholder never queried: the hint handler rejects L1 blob hints with
+                // `NoBeaconApi` when there is no beacon API.
+                OnlineBlobProvider::new(
+                    OnlineBeaconClient::new_http(String::new()),
+                    0,
+                    DEFAULT_SECONDS_PER_SLOT,
+                )
+            }
+            None => return Err(SingleChainHostError::Other(host_errors::MISSING_BEACON_API)),
+        };
         let l2_provider = rpc_provider::<Optimism>(
             self.single_host
                 .l2_node_address
                 .as_ref()
-                .ok_or(SingleChainHostError::Other("L2 node address must be set"))?,
+                .ok_or(SingleChainHostError::Other(host_errors::MISSING_L2_NODE))?,
         )
         .await;
 
         let celestia_client =
             celestia_rpc::Client::new(
                 self.celestia_args.celestia_connection.as_ref().ok_or(
-                    SingleChainHostError::Other("Celestia connection must be set"),
+                    SingleChainHostError::Other(host_errors::MISSING_CELESTIA_CONNECTION),
                 )?,
                 self.celestia_args.auth_token.as_deref(),
             )
             .await
             .expect("Failed creating rpc client");
 
-        let namespace_bytes = hex::decode(self.celestia_args.namespace.as_ref().ok_or(
-            SingleChainHostError::Other("Celestia Namespace must be set"),
-        )?)
-        .expect("Invalid hex");
-        let namespace = Namespace::new_v0(&namespace_bytes).expect("Invalid namespace");
+        let namespace = self.namespace()?;
+
+        let mut celestia_provider = OnlineCelestiaProvider::new(celestia_client, namespace);
+        if self.celestia_args.count_rpc_calls {
+            celestia_provider =
+                celestia_provider.with_rpc_counter(self.celestia_args.rpc_counter.clone());
+        }
+        if self.celestia_args.blob_poll_window > 0 {
+            celestia_provider = celestia_provider.with_blob_poll(BlobPoll {
+                window: Duration::from_secs(self.celestia_args.blob_poll_window),
+                interval: Duration::from_millis(self.celestia_args.blob_poll_interval),
+            });
+        }
+
+        let l1_archive = match self.celestia_args.l1_archive_node_address {
+            Some(ref address) => Some(self.l1_rpc_provider(address).await?),
+            None => None,
+        };
 
-        let celestia_provider = OnlineCelestiaProvider::new(celestia_client, namespace);
+        let beacon_verifier = if self.celestia_args.verify_l1_header_with_beacon {
+            let beacon_address = self
+                .single_host
+                .l1_beacon_address
+                .clone()
+                .ok_or(SingleChainHostError::Other(host_errors::MISSING_BEACON_API))?;
+            let verifier = BeaconHeaderVerifier::new(beacon_address)
+                .await
+                .map_err(|e| {
+                    error!(target: "host", "Failed to create the beacon header verifier: {e}");
+                    SingleChainHostError::Other(host_errors::BEACON_VERIFIER_UNAVAILABLE)
+                })?;
+            Some(verifier)
+        } else {
+            None
+        };
+
+        let commitment_index = match self.celestia_args.commitment_index {
+            Some(ref path) => Some(Arc::new(Mutex::new(
+                self.load_commitment_index(path, &l1_provider).await,
+            ))),
+            None => None,
+        };
 
         Ok(CelestiaChainProviders {
             inner_providers: SingleChainProviders {
@@ -230,8 +825,212 @@ impl CelestiaChainHost {
                 l2: l2_provider,
             },
             celestia: celestia_provider,
+            commitment_index,
+            l1_archive,
+            beacon_verifier,
+            proof_generator: Arc::new(BlobstreamProofGenerator),
+            has_beacon_api,
         })
     }
+
+    /// Creates a provider for the L1 node at `address`, counting its calls if enabled.
+    async fn l1_rpc_provider(&self, address: &str) -> Result<RootProvider, SingleChainHostError> {
+        if !self.celestia_args.count_rpc_calls {
+            return Ok(rpc_provider(address).await);
+        }
+
+        counting_rpc_provider(address, self.celestia_args.rpc_counter.clone()).map_err(|e| {
+            error!(target: "host", "Failed to create the L1 provider for {address}: {e}");
+            SingleChainHostError::Other(host_errors::INVALID_L1_NODE)
+        })
+    }
+
+    /// Loads the [CommitmentIndex] from disk, starting from an empty index if none exists yet or
+    /// if it does not match the current state of the Blobstream contract.
+    async fn load_commitment_index(
+        &self,
+        path: &Path,
+        l1_provider: &RootProvider,
+    ) -> CommitmentIndex {
+        if !path.exists() {
+            return CommitmentIndex::new();
+        }
+
+        let mut index = match CommitmentIndex::load(path) {
+            Ok(index) => index,
+            Err(e) => {
+                warn!(target: "host", "Failed to load commitment index, starting empty: {e}");
+                return CommitmentIndex::new();
+            }
+        };
+
+        let blobstream_address = match l1_provider.get_chain_id().await {
+            Ok(chain_id) => resolve_blobstream_address(chain_id, &self.blobstream_addresses()),
+            Err(e) => {
+                warn!(target: "host", "Failed to fetch L1 chain id, starting empty: {e}");
+                return CommitmentIndex::new();
+            }
+        };
+        let Some(blobstream_address) = blobstream_address else {
+            return CommitmentIndex::new();
+        };
+
+        match index.validate(blobstream_address, l1_provider).await {
+            Ok(()) => {
+                info!(target: "host", "Loaded commitment index with {} commitments", index.len());
+                index
+            }
+            Err(e) => {
+                warn!(target: "host", "Discarding stale commitment index: {e}");
+                CommitmentIndex::new()
+            }
+        }
+    }
+}
+
+/// Parses a `<chain id>=<address>` entry of `--blobstream-addresses`.
+fn parse_blobstream_address_entry(s: &str) -> Result<(u64, Address), String> {
+    let (chain_id, address) = s.split_once('=').ok_or_else(|| {
+        format!("blobstream address `{s}` is not of the form <chain id>=<address>")
+    })?;
+    let chain_id = chain_id
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid chain id in `{s}`: {e}"))?;
+    let address = address
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid address in `{s}`: {e}"))?;
+    if let Some(canonical) = blobstream_address(chain_id) {
+        return Err(format!(
+            "chain id {chain_id} has the canonical Blobstream deployment {canonical}, it can't be \
+             replaced"
+        ));
+    }
+    Ok((chain_id, address))
+}
+
+/// Ensures the Celestia `network` is the one relayed by the canonical Blobstream deployment on
+/// the L1 chain `chain_id`.
+fn check_celestia_network(
+    network: CelestiaNetwork,
+    chain_id: u64,
+) -> Result<(), SingleChainHostError> {
+    let relayed = blobstream_celestia_network(chain_id);
+    if relayed != Some(network) {
+        error!(
+            target: "host",
+            "Celestia network {network} is not relayed by the Blobstream deployment on L1 chain {chain_id} (relays {relayed:?})"
+        );
+        return Err(SingleChainHostError::Other(
+            host_errors::CELESTIA_NETWORK_MISMATCH,
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves the name of an L2 chain with a rollup config in the superchain registry to its chain
+/// id.
+fn registry_chain_id(name: &str) -> Result<u64> {
+    let chain = NamedChain::from_str(name).map_err(|_| anyhow!("Unknown L2 chain `{name}`"))?;
+    let chain_id = chain as u64;
+    if !ROLLUP_CONFIGS.contains_key(&chain_id) {
+        return Err(anyhow!(
+            "L2 chain `{name}` has no rollup config in the registry, use --rollup-config-path"
+        ));
+    }
+    Ok(chain_id)
+}
+
+/// Waits for both tasks to complete, for at most `limit` if set.
+///
+/// Returns `None` if `limit` elapsed first, after cancelling both tasks and waiting for them to
+/// stop, so that no work is left running.
+async fn join_within<A, B>(
+    limit: Option<Duration>,
+    first: &mut JoinHandle<A>,
+    second: &mut JoinHandle<B>,
+) -> Option<Result<(A, B), JoinError>> {
+    let joined = async { tokio::try_join!(&mut *first, &mut *second) };
+    let joined = match limit {
+        Some(limit) => timeout(limit, joined).await.ok(),
+        None => Some(joined.await),
+    };
+    if joined.is_none() {
+        first.abort();
+        second.abort();
+        // Both were aborted, their cancellation is the expected outcome.
+        let _ = tokio::join!(first, second);
+    }
+    joined
+}
+
+/// A Celestia namespace given on the command line, before the `--namespace-version` is applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum NamespaceArg {
+    /// The id of a namespace, built into a namespace of the configured version.
+    Id(Vec<u8>),
+    /// A full namespace, carrying its version.
+    Full(Namespace),
+}
+
+impl NamespaceArg {
+    /// Returns the namespace, building an id with `version` (or [DEFAULT_NAMESPACE_VERSION] if
+    /// unset), and checking a full namespace has `version` if set.
+    pub fn namespace(&self, version: Option<u8>) -> Result<Namespace, String> {
+        match self {
+            Self::Id(id) => {
+                let version = version.unwrap_or(DEFAULT_NAMESPACE_VERSION);
+                Namespace::new(version, id).map_err(|e| {
+                    format!(
+                        "invalid version {version} namespace id `{}`: {e}",
+                        hex::encode(id)
+                    )
+                })
+            }
+            Self::Full(namespace) => match version {
+                Some(version) if namespace.version() != version => Err(format!(
+                    "namespace `{}` has version {}, expected {version}",
+                    hex::encode(namespace.as_bytes()),
+                    namespace.version()
+                )),
+                _ => Ok(*namespace),
+            },
+        }
+    }
+}
+
+/// Parses a Celestia namespace from the command line.
+///
+/// Accepts the id of a namespace or a full 29 byte namespace, hex encoded, optionally prefixed
+/// with `hex:`, or base64 encoded and prefixed with `base64:`. The encoding is never guessed, as
+/// many base64 strings are valid hex too.
+fn parse_namespace_arg(s: &str) -> Result<NamespaceArg, String> {
+    let bytes = match s.split_once(':') {
+        Some(("base64", encoded)) => BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("namespace `{s}` is not valid base64: {e}"))?,
+        Some(("hex", encoded)) => {
+            hex::decode(encoded).map_err(|e| format!("namespace `{s}` is not valid hex: {e}"))?
+        }
+        Some((prefix, _)) => {
+            return Err(format!(
+                "unknown namespace encoding `{prefix}`, expected `hex` or `base64`"
+            ))
+        }
+        None => hex::decode(s).map_err(|e| format!("namespace `{s}` is not valid hex: {e}"))?,
+    };
+
+    match bytes.len() {
+        NS_SIZE => Namespace::from_raw(&bytes)
+            .map(NamespaceArg::Full)
+            .map_err(|e| format!("invalid namespace `{s}`: {e}")),
+        0 => Err("namespace is empty".to_string()),
+        len if len <= NS_ID_SIZE => Ok(NamespaceArg::Id(bytes)),
+        len => Err(format!(
+            "namespace `{s}` has {len} bytes, expected an id of at most {NS_ID_SIZE} bytes or a {NS_SIZE} byte namespace"
+        )),
+    }
 }
 
 impl OnlineHostBackendCfg for CelestiaChainHost {
@@ -239,3 +1038,256 @@ impl OnlineHostBackendCfg for CelestiaChainHost {
     // TODO: Modify so that is uses "CelestiaChainProviders"
     type Providers = CelestiaChainProviders;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn parse_namespace(args: &[&str]) -> Result<Namespace, String> {
+        let cfg = CelestiaCfg::try_parse_from([&["hana-host"], args].concat())
+            .map_err(|e| e.to_string())?;
+        cfg.namespace.unwrap().namespace(cfg.namespace_version)
+    }
+
+    #[test]
+    fn test_namespace_is_parsed_with_an_explicit_encoding() {
+        let v0 = Namespace::new_v0(&[0xaa, 0xbb]).unwrap();
+        assert_eq!(parse_namespace(&["--namespace", "aabb"]), Ok(v0));
+        assert_eq!(parse_namespace(&["--namespace", "hex:aabb"]), Ok(v0));
+        assert_eq!(parse_namespace(&["--namespace", "base64:qrs="]), Ok(v0));
+
+        let encoded = hex::encode(v0.as_bytes());
+        assert_eq!(parse_namespace(&["--namespace", &encoded]), Ok(v0));
+    }
+
+    #[test]
+    fn test_namespace_id_is_built_with_the_version() {
+        let v0 = Namespace::new_v0(&[0xaa, 0xbb]).unwrap();
+        assert_eq!(parse_namespace(&["--namespace", "aabb"]), Ok(v0));
+        assert_eq!(parse_namespace(&["--namespace", "hex:aabb"]), Ok(v0));
+        assert_eq!(parse_namespace(&["--namespace", "base64:qrs="]), Ok(v0));
+        assert_eq!(
+            parse_namespace(&["--namespace", "aabb", "--namespace-version", "0"]),
+            Ok(v0)
+        );
+        assert!(parse_namespace(&["--namespace", "aabb", "--namespace-version", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_full_namespace_must_carry_the_version() {
+        let namespace = Namespace::new_v0(&[0xaa, 0xbb]).unwrap();
+        let encoded = hex::encode(namespace.as_bytes());
+
+        assert_eq!(parse_namespace(&["--namespace", &encoded]), Ok(namespace));
+        assert_eq!(
+            parse_namespace(&[
+                "--namespace",
+                &format!("base64:{}", BASE64_STANDARD.encode(namespace.as_bytes()))
+            ]),
+            Ok(namespace)
+        );
+        assert!(parse_namespace(&["--namespace", &encoded, "--namespace-version", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_namespaces_fail_at_argument_parsing() {
+        for namespace in ["", "zz", "qrs=", "b64:qrs=", "base64:!!", &"aa".repeat(30)] {
+            let err =
+                CelestiaCfg::try_parse_from(["hana-host", "--namespace", namespace]).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                clap::error::ErrorKind::ValueValidation,
+                "{namespace}"
+            );
+        }
+    }
+
+    /// Sets its flag when dropped, i.e. when the task holding it completes or is cancelled.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_join_within_cancels_both_tasks_on_timeout() {
+        let dropped = [
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        ];
+        let [mut first, mut second] = dropped.clone().map(|dropped| {
+            let flag = DropFlag(dropped);
+            task::spawn(async move {
+                let _flag = flag;
+                tokio::time::sleep(Duration::from_secs(3600)).await;
+            })
+        });
+
+        let joined = join_within(Some(Duration::from_millis(10)), &mut first, &mut second).await;
+
+        assert!(joined.is_none());
+        // The tasks were stopped before returning, not left running.
+        assert!(dropped
+            .iter()
+            .all(|dropped| dropped.load(Ordering::Acquire)));
+    }
+
+    #[tokio::test]
+    async fn test_join_within_returns_the_results_in_time() {
+        let mut first = task::spawn(async { 1 });
+        let mut second = task::spawn(async { "done" });
+
+        let joined = join_within(Some(Duration::from_secs(60)), &mut first, &mut second).await;
+        assert_eq!(joined.unwrap().unwrap(), (1, "done"));
+    }
+
+    #[tokio::test]
+    async fn test_configuration_errors_exit_with_the_config_code() {
+        let config_error = ExitCode::from(HostExitCode::ConfigError);
+        for args in [
+            &["--namespace", "aabb", "--namespace-version", "7"][..],
+            &["--l2-chain", "no-such-chain"][..],
+        ] {
+            let cfg = CelestiaChainHost::try_parse_from([&["hana-host"], args].concat()).unwrap();
+            assert_eq!(cfg.run().await, config_error, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_celestia_network_must_match_the_l1_chain() {
+        check_celestia_network(CelestiaNetwork::Mainnet, 1).unwrap();
+        check_celestia_network(CelestiaNetwork::Mocha, 11155111).unwrap();
+
+        // Mocha data proven against the mainnet deployment, and a chain without a deployment.
+        for (network, chain_id) in [
+            (CelestiaNetwork::Mocha, 1),
+            (CelestiaNetwork::Mainnet, 11155111),
+            (CelestiaNetwork::Mainnet, 424242),
+        ] {
+            let err = check_celestia_network(network, chain_id).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    SingleChainHostError::Other(message)
+                        if message == host_errors::CELESTIA_NETWORK_MISMATCH
+                ),
+                "{network} on {chain_id}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rollup_config_is_read_from_the_registry() {
+        let mut cfg = CelestiaChainHost::default();
+        assert!(cfg.single_host.rollup_config_path.is_none());
+
+        cfg.celestia_args.l2_chain = Some("base".to_string());
+        assert_eq!(cfg.read_rollup_config().unwrap(), ROLLUP_CONFIGS[&8453]);
+
+        cfg.celestia_args.l2_chain = Some("no-such-chain".to_string());
+        let err = cfg.read_rollup_config().unwrap_err();
+        assert_eq!(err.to_string(), "Unknown L2 chain `no-such-chain`");
+    }
+
+    /// A custom preimage backend, recording the keys written to it.
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        preimages: std::collections::HashMap<B256, Vec<u8>>,
+        written: Arc<std::sync::Mutex<Vec<B256>>>,
+    }
+
+    impl KeyValueStore for RecordingStore {
+        fn get(&self, key: B256) -> Option<Vec<u8>> {
+            self.preimages.get(&key).cloned()
+        }
+
+        fn set(&mut self, key: B256, value: Vec<u8>) -> Result<()> {
+            self.written.lock().unwrap().push(key);
+            self.preimages.insert(key, value);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preimages_are_kept_in_a_custom_backend() {
+        let backend = RecordingStore::default();
+        let written = backend.written.clone();
+        let kv = CelestiaChainHost::default().key_value_store_with(backend);
+
+        let key = B256::repeat_byte(0x02);
+        kv.write().await.set(key, b"preimage".to_vec()).unwrap();
+
+        assert_eq!(*written.lock().unwrap(), [key]);
+        assert_eq!(kv.read().await.get(key).unwrap(), b"preimage");
+    }
+
+    #[test]
+    fn test_concurrency_limits_reject_zero() {
+        let err =
+            CelestiaChainHost::try_parse_from(["hana-host", "--l1-concurrency", "0"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_default_matches_the_cli_defaults() {
+        let parsed = CelestiaCfg::try_parse_from(["hana-host"]).unwrap();
+        let default = CelestiaCfg::default();
+        assert!(default.verify_on_generate);
+
+        assert_eq!(
+            serde_json::to_value(&default).unwrap(),
+            serde_json::to_value(&parsed).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_l1_confirmations_default_to_two_epochs() {
+        let cfg = CelestiaCfg::try_parse_from(["hana-host"]).unwrap();
+        assert_eq!(cfg.l1_confirmations, DEFAULT_L1_CONFIRMATIONS);
+        assert_eq!(cfg.l1_confirmations, 64);
+        // Hosts built in code require the same confirmations as from the CLI.
+        assert_eq!(
+            CelestiaCfg::default().l1_confirmations,
+            DEFAULT_L1_CONFIRMATIONS
+        );
+        assert_eq!(
+            CelestiaChainHost::default()
+                .proof_options()
+                .min_confirmations,
+            DEFAULT_L1_CONFIRMATIONS
+        );
+
+        // The check can still be disabled, e.g. against a devnet.
+        let cfg = CelestiaCfg::try_parse_from(["hana-host", "--l1-confirmations", "0"]).unwrap();
+        assert_eq!(cfg.l1_confirmations, 0);
+    }
+
+    #[test]
+    fn test_proof_options_share_header_cache() {
+        let cfg = CelestiaChainHost::default();
+        let header = alloy_rpc_types_eth::Header::new(alloy_consensus::Header::default());
+        let hash = header.hash;
+
+        cfg.proof_options().header_cache.insert(header);
+        assert!(cfg.clone().proof_options().header_cache.get(hash).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_proof_options_share_concurrency_limits() {
+        let mut cfg = CelestiaChainHost::default();
+        cfg.celestia_args.l1_concurrency = NonZeroUsize::new(1);
+        let first = cfg.proof_options();
+        let second = cfg.clone().proof_options();
+
+        let _permit = first.concurrency.l1_permit().await;
+        // The only L1 slot is held through the first options.
+        assert!(
+            timeout(Duration::from_millis(10), second.concurrency.l1_permit())
+                .await
+                .is_err()
+        );
+    }
+}
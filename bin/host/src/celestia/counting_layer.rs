@@ -0,0 +1,125 @@
+//! A transport layer counting the RPC calls made to the L1 node by method.
+
+use alloy_json_rpc::{RequestPacket, ResponsePacket};
+use alloy_provider::RootProvider;
+use alloy_rpc_client::ClientBuilder;
+use alloy_transport::{TransportError, TransportFut};
+use hana_proofs::rpc_counter::RpcCallCounter;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// A [Layer] recording every request sent through it in an [RpcCallCounter].
+#[derive(Debug, Clone)]
+pub struct CountingLayer {
+    counter: RpcCallCounter,
+}
+
+impl CountingLayer {
+    /// Creates a layer recording requests in `counter`.
+    pub const fn new(counter: RpcCallCounter) -> Self {
+        Self { counter }
+    }
+}
+
+impl<S> Layer<S> for CountingLayer {
+    type Service = CountingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountingService {
+            inner,
+            counter: self.counter.clone(),
+        }
+    }
+}
+
+/// The [Service] created by [CountingLayer].
+#[derive(Debug, Clone)]
+pub struct CountingService<S> {
+    inner: S,
+    counter: RpcCallCounter,
+}
+
+impl<S> Service<RequestPacket> for CountingService<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Send
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        match request {
+            RequestPacket::Single(ref request) => self.counter.record(request.method()),
+            RequestPacket::Batch(ref requests) => {
+                for request in requests {
+                    self.counter.record(request.method());
+                }
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+/// Creates an HTTP provider for the node at `url`, recording every call in `counter`.
+pub fn counting_rpc_provider(url: &str, counter: RpcCallCounter) -> anyhow::Result<RootProvider> {
+    let client = ClientBuilder::default()
+        .layer(CountingLayer::new(counter))
+        .http(url.parse()?);
+    Ok(RootProvider::new(client))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U64;
+    use alloy_provider::Provider;
+    use alloy_transport::mock::{Asserter, MockTransport};
+
+    #[tokio::test]
+    async fn test_counts_calls_by_method() {
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(10));
+        asserter.push_success(&U64::from(11));
+        asserter.push_success(&U64::from(1));
+
+        let counter = RpcCallCounter::new();
+        let client = ClientBuilder::default()
+            .layer(CountingLayer::new(counter.clone()))
+            .transport(MockTransport::new(asserter), true);
+        let provider = RootProvider::new(client);
+
+        assert_eq!(provider.get_block_number().await.unwrap(), 10);
+        assert_eq!(provider.get_block_number().await.unwrap(), 11);
+        assert_eq!(provider.get_chain_id().await.unwrap(), 1);
+
+        let counts = counter.counts();
+        assert_eq!(counts.get("eth_blockNumber"), Some(&2));
+        assert_eq!(counts.get("eth_chainId"), Some(&1));
+        assert_eq!(counter.total(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_calls_are_counted() {
+        let asserter = Asserter::new();
+        asserter.push_failure_msg("rate limited");
+
+        let counter = RpcCallCounter::new();
+        let client = ClientBuilder::default()
+            .layer(CountingLayer::new(counter.clone()))
+            .transport(MockTransport::new(asserter), true);
+        let provider = RootProvider::new(client);
+
+        provider.get_block_number().await.unwrap_err();
+        assert_eq!(counter.total(), 1);
+    }
+}
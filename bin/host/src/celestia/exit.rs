@@ -0,0 +1,272 @@
+//! Exit codes reported by the host when running in native mode. In server mode, the errors of
+//! the host itself are reported with the same codes.
+//!
+//! | Code | Meaning                                                             |
+//! |------|---------------------------------------------------------------------|
+//! | 0    | The claim was validated successfully.                               |
+//! | 1    | The claim is invalid. Retrying will not change the outcome.         |
+//! | 2    | A provider, RPC or preimage server error occurred. Safe to retry.   |
+//! | 3    | A Celestia blob failed proof verification.                          |
+//! | 4    | The host configuration is invalid.                                  |
+//! | 5    | Any other failure, e.g. a panicked task or a derivation error.      |
+//! | 6    | The run exceeded the configured maximum runtime.                    |
+//!
+//! A run timing out while the preimage server retries a failing Celestia hint exits with the code
+//! of the last proof generation error instead of `6`, see [ProofFailure].
+
+use hana_oracle::metrics::VerificationMetrics;
+use hana_proofs::errors::ProofError;
+use kona_client::single::FaultProofProgramError;
+use kona_host::single::SingleChainHostError;
+use std::{
+    process::ExitCode,
+    sync::{Arc, Mutex},
+};
+
+/// The exit code of the host in native mode, derived from the result of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum HostExitCode {
+    /// The claim was validated successfully.
+    Success = 0,
+    /// The claim is invalid.
+    InvalidClaim = 1,
+    /// A provider, RPC or preimage server error occurred.
+    ProviderError = 2,
+    /// A Celestia blob failed proof verification.
+    VerificationFailure = 3,
+    /// The host configuration is invalid.
+    ConfigError = 4,
+    /// Any other failure.
+    Other = 5,
+    /// The run exceeded the configured maximum runtime.
+    Timeout = 6,
+}
+
+impl HostExitCode {
+    /// Returns the exit code for an error returned by the client program, whose oracle recorded
+    /// the blobs it rejected in `metrics`.
+    pub fn from_client_error(err: &FaultProofProgramError, metrics: &VerificationMetrics) -> Self {
+        // A rejected blob stops the derivation with a critical error, which the pipeline only
+        // carries as a message, so rejections are told apart by the oracle's metrics.
+        if metrics.rejected_blobs() > 0 {
+            return Self::VerificationFailure;
+        }
+
+        match err {
+            FaultProofProgramError::InvalidClaim(..) => Self::InvalidClaim,
+            FaultProofProgramError::OracleProviderError(_) => Self::ProviderError,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns the exit code for an error returned by the host itself.
+    pub fn from_host_error(err: &SingleChainHostError) -> Self {
+        match err {
+            SingleChainHostError::Other(message) if host_errors::CONFIG.contains(message) => {
+                Self::ConfigError
+            }
+            SingleChainHostError::Other(message) if host_errors::PROVIDER.contains(message) => {
+                Self::ProviderError
+            }
+            SingleChainHostError::PreimageServerError(_) | SingleChainHostError::IOError(_) => {
+                Self::ProviderError
+            }
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns the exit code for an error generating the proof of a Celestia blob.
+    ///
+    /// A [ProofError] that may go away later, e.g. a commitment not relayed to Blobstream yet, is
+    /// safe to retry, as are the RPC errors of the nodes. Other [ProofError]s won't change when
+    /// retrying.
+    pub fn from_proof_error(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<ProofError>() {
+            Some(err) if !err.is_temporary() => Self::Other,
+            _ => Self::ProviderError,
+        }
+    }
+
+    /// Returns the exit code as an [i32].
+    pub const fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+impl From<HostExitCode> for ExitCode {
+    fn from(code: HostExitCode) -> Self {
+        Self::from(code as u8)
+    }
+}
+
+/// The messages of the [SingleChainHostError::Other] errors the host returns, by exit code. Any
+/// other message, e.g. one of kona's, exits with [HostExitCode::Other].
+pub(crate) mod host_errors {
+    pub(crate) const UNKNOWN_L2_CHAIN: &str = "Unknown L2 chain";
+    pub(crate) const MISSING_DATA_DIR: &str = "The disk kv backend requires a data directory";
+    pub(crate) const MISSING_L1_NODE: &str = "Provider must be set";
+    pub(crate) const CELESTIA_NETWORK_MISMATCH: &str =
+        "Celestia network does not match the L1 chain's Blobstream deployment";
+    pub(crate) const MISSING_BEACON_API: &str = "Beacon API URL must be set";
+    pub(crate) const MISSING_L2_NODE: &str = "L2 node address must be set";
+    pub(crate) const MISSING_CELESTIA_CONNECTION: &str = "Celestia connection must be set";
+    pub(crate) const MISSING_NAMESPACE: &str = "Celestia Namespace must be set";
+    pub(crate) const INVALID_NAMESPACE: &str = "Invalid Celestia namespace";
+    pub(crate) const INVALID_L1_NODE: &str = "Invalid L1 node address";
+    pub(crate) const L1_CHAIN_ID_UNAVAILABLE: &str = "Failed to fetch the L1 chain id";
+    pub(crate) const BEACON_VERIFIER_UNAVAILABLE: &str =
+        "Failed to create the beacon header verifier";
+
+    /// The messages of an invalid host configuration.
+    pub(crate) const CONFIG: &[&str] = &[
+        UNKNOWN_L2_CHAIN,
+        MISSING_DATA_DIR,
+        MISSING_L1_NODE,
+        CELESTIA_NETWORK_MISMATCH,
+        MISSING_BEACON_API,
+        MISSING_L2_NODE,
+        MISSING_CELESTIA_CONNECTION,
+        MISSING_NAMESPACE,
+        INVALID_NAMESPACE,
+        INVALID_L1_NODE,
+    ];
+
+    /// The messages of a node that failed to answer.
+    pub(crate) const PROVIDER: &[&str] = &[L1_CHAIN_ID_UNAVAILABLE, BEACON_VERIFIER_UNAVAILABLE];
+}
+
+/// The exit code of the last failed Celestia proof generation, shared by every clone of the host
+/// config.
+///
+/// The preimage server retries a failing hint until it succeeds, so a run can only end on a
+/// proof error by timing out. The run then exits with this code rather than
+/// [HostExitCode::Timeout], telling whether retrying the run later may succeed.
+#[derive(Debug, Clone, Default)]
+pub struct ProofFailure(Arc<Mutex<Option<HostExitCode>>>);
+
+impl ProofFailure {
+    /// Records `err` as the last proof generation error.
+    pub fn record(&self, err: &anyhow::Error) {
+        *self.0.lock().expect("proof failure lock poisoned") =
+            Some(HostExitCode::from_proof_error(err));
+    }
+
+    /// Clears the last proof generation error once a proof was generated.
+    pub fn clear(&self) {
+        *self.0.lock().expect("proof failure lock poisoned") = None;
+    }
+
+    /// Returns the exit code of the last proof generation error, if the last proof failed.
+    pub fn exit_code(&self) -> Option<HostExitCode> {
+        *self.0.lock().expect("proof failure lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::B256;
+    use hana_blobstream::blobstream::VerificationStage;
+
+    #[test]
+    fn test_exit_code_matches_the_table() {
+        assert_eq!(ExitCode::from(HostExitCode::Success), ExitCode::SUCCESS);
+        assert_eq!(ExitCode::from(HostExitCode::Timeout), ExitCode::from(6));
+    }
+
+    #[test]
+    fn test_rejected_blobs_are_verification_failures() {
+        let metrics = VerificationMetrics::default();
+        metrics.record_rejected_blob();
+        metrics.record_failure(VerificationStage::ShareProof);
+
+        let err = FaultProofProgramError::InvalidClaim(B256::ZERO, B256::repeat_byte(1));
+        assert_eq!(
+            HostExitCode::from_client_error(&err, &metrics),
+            HostExitCode::VerificationFailure
+        );
+    }
+
+    #[test]
+    fn test_client_errors_without_rejected_blobs() {
+        let metrics = VerificationMetrics::default();
+
+        let err = FaultProofProgramError::InvalidClaim(B256::ZERO, B256::repeat_byte(1));
+        assert_eq!(
+            HostExitCode::from_client_error(&err, &metrics),
+            HostExitCode::InvalidClaim
+        );
+    }
+
+    #[test]
+    fn test_host_errors_are_classified_by_message() {
+        for message in host_errors::CONFIG {
+            assert_eq!(
+                HostExitCode::from_host_error(&SingleChainHostError::Other(message)),
+                HostExitCode::ConfigError,
+                "{message}"
+            );
+        }
+        for message in host_errors::PROVIDER {
+            assert_eq!(
+                HostExitCode::from_host_error(&SingleChainHostError::Other(message)),
+                HostExitCode::ProviderError,
+                "{message}"
+            );
+        }
+        assert_eq!(
+            HostExitCode::from_host_error(&SingleChainHostError::Other("Unexpected failure")),
+            HostExitCode::Other
+        );
+        assert_eq!(
+            HostExitCode::from_host_error(&std::io::Error::other("broken pipe").into()),
+            HostExitCode::ProviderError
+        );
+    }
+
+    #[test]
+    fn test_temporary_proof_errors_are_safe_to_retry() {
+        let err = anyhow::Error::from(ProofError::CommitmentNotYetRelayed {
+            height: 10,
+            latest_block: 5,
+        });
+        assert_eq!(
+            HostExitCode::from_proof_error(&err),
+            HostExitCode::ProviderError
+        );
+    }
+
+    #[test]
+    fn test_permanent_proof_errors_are_not_retried() {
+        let err = anyhow::Error::from(ProofError::CommitmentNotFound(10));
+        assert_eq!(HostExitCode::from_proof_error(&err), HostExitCode::Other);
+    }
+
+    #[test]
+    fn test_rpc_errors_are_safe_to_retry() {
+        let err = anyhow::anyhow!("connection refused");
+        assert_eq!(
+            HostExitCode::from_proof_error(&err),
+            HostExitCode::ProviderError
+        );
+    }
+
+    #[test]
+    fn test_proof_failure_tracks_the_last_error() {
+        let failure = ProofFailure::default();
+        assert_eq!(failure.exit_code(), None);
+
+        failure.record(&anyhow::Error::from(ProofError::CommitmentNotFound(10)));
+        assert_eq!(failure.clone().exit_code(), Some(HostExitCode::Other));
+
+        failure.record(&anyhow::Error::from(ProofError::L1NodeBehind {
+            requested: 110,
+            node_head: 100,
+        }));
+        assert_eq!(failure.exit_code(), Some(HostExitCode::ProviderError));
+
+        failure.clear();
+        assert_eq!(failure.exit_code(), None);
+    }
+}
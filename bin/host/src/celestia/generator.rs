@@ -0,0 +1,175 @@
+//! Generation of the [OraclePayload]s served for Celestia hints.
+
+use alloy_primitives::{Address, Bytes, B256};
+use anyhow::Result;
+use async_trait::async_trait;
+use celestia_types::{nmt::Namespace, Commitment};
+use core::fmt::Debug;
+use hana_blobstream::blobstream::BlobstreamProof;
+use hana_oracle::payload::OraclePayload;
+use hana_proofs::blobstream_inclusion::get_blobstream_proof;
+
+use super::{CelestiaChainHost, CelestiaChainProviders};
+
+/// A strategy producing the [OraclePayload] of a Celestia blob, proving its inclusion to the
+/// client.
+#[async_trait]
+pub trait ProofGenerator: Debug + Send + Sync {
+    /// Fetches the blob with `commitment` at `height` in `namespace` and generates its payload.
+    async fn generate(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: Commitment,
+        cfg: &CelestiaChainHost,
+        providers: &CelestiaChainProviders,
+    ) -> Result<OraclePayload>;
+}
+
+/// The [ProofGenerator] proving blobs through the Blobstream data commitments relayed to L1.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlobstreamProofGenerator;
+
+#[async_trait]
+impl ProofGenerator for BlobstreamProofGenerator {
+    async fn generate(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: Commitment,
+        cfg: &CelestiaChainHost,
+        providers: &CelestiaChainProviders,
+    ) -> Result<OraclePayload> {
+        let blob = providers
+            .celestia
+            .blob_get(height, Some(namespace), commitment)
+            .await?;
+
+        let data = blob.data.clone();
+
+        let blobstream_proof = get_blobstream_proof(
+            providers.celestia.client.as_ref(),
+            providers.l1(),
+            cfg.single_host.l1_head,
+            height,
+            blob,
+            providers.commitment_index.as_deref(),
+            providers.l1_archive.as_ref(),
+            &cfg.proof_options(),
+        )
+        .await?;
+
+        if let (Some(index), Some(path)) = (
+            &providers.commitment_index,
+            &cfg.celestia_args.commitment_index,
+        ) {
+            index
+                .lock()
+                .await
+                .save(path)
+                .map_err(|e| anyhow::anyhow!("failed to save commitment index: {e}"))?;
+        }
+
+        if let Some(ref verifier) = providers.beacon_verifier {
+            verifier
+                .verify_header(&blobstream_proof.block_header)
+                .await?;
+        }
+
+        // Catch broken proofs here rather than during derivation.
+        if cfg.celestia_args.verify_on_generate {
+            let blobstream_address = cfg.expected_blobstream_address(providers.l1()).await?;
+            verify_generated_proof(
+                &blobstream_proof,
+                height,
+                cfg.single_host.l1_head,
+                blobstream_address,
+            )?;
+        }
+
+        Ok(OraclePayload::new(Bytes::from(data), blobstream_proof))
+    }
+}
+
+/// Verifies a generated proof of a blob at `height` like the client will, against the Blobstream
+/// deployment the client expects rather than the address recorded in the proof.
+pub(crate) fn verify_generated_proof(
+    proof: &BlobstreamProof,
+    height: u64,
+    l1_head: B256,
+    blobstream_address: Address,
+) -> Result<()> {
+    proof
+        .validate(height, l1_head, blobstream_address)
+        .map_err(|e| anyhow::anyhow!("generated blobstream proof failed to verify: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::{
+        test_utils::{offline_providers, synthetic_hint, SyntheticProofGenerator},
+        CelestiaChainHintHandler,
+    };
+    use alloy_primitives::keccak256;
+    use hana_blobstream::test_utils::synthetic_proof;
+    use hana_oracle::hint::HintWrapper;
+    use kona_host::{HintHandler, MemoryKeyValueStore};
+    use kona_preimage::{PreimageKey, PreimageKeyType};
+    use kona_proof::Hint;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_hints_are_served_by_the_configured_generator() {
+        let generator = Arc::new(SyntheticProofGenerator::default());
+        let providers = offline_providers(generator.clone()).await;
+        let cfg = CelestiaChainHost::default();
+        let kv = cfg.key_value_store_with(MemoryKeyValueStore::new());
+
+        // The nodes of the providers are unreachable, the payload can only come from the
+        // alternate generator.
+        let hint = synthetic_hint().encode();
+        CelestiaChainHintHandler::fetch_hint(
+            Hint::new(HintWrapper::CelestiaDA, hint.clone()),
+            &cfg,
+            &providers,
+            kv.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(generator.generated(), 1);
+
+        let key = PreimageKey::new(*keccak256(&hint), PreimageKeyType::GlobalGeneric);
+        let payload = kv.read().await.get(key.into()).unwrap();
+        let payload = OraclePayload::from_bytes(&payload).unwrap();
+        assert_eq!(payload.blob.as_ref(), b"hana-blob");
+    }
+
+    #[test]
+    fn test_verify_generated_proof_accepts_the_expected_address() {
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+
+        verify_generated_proof(
+            &fixture.proof,
+            fixture.height,
+            fixture.l1_head,
+            fixture.blobstream_address,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verify_generated_proof_rejects_another_address() {
+        // The proof is internally consistent, only the expected deployment differs.
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+
+        let err = verify_generated_proof(
+            &fixture.proof,
+            fixture.height,
+            fixture.l1_head,
+            Address::repeat_byte(0xcc),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("expected"), "{err}");
+    }
+}
@@ -1,12 +1,9 @@
 //! [HintHandler] for the [CelestiaaChainHost].
 
-use alloy_primitives::{keccak256, Bytes};
-use anyhow::{ensure, Result};
+use alloy_primitives::{hex, keccak256};
+use anyhow::Result;
 use async_trait::async_trait;
-use celestia_rpc::BlobClient;
-use celestia_types::Commitment;
-use hana_oracle::{hint::HintWrapper, payload::OraclePayload};
-use hana_proofs::blobstream_inclusion::get_blobstream_proof;
+use hana_oracle::hint::{CelestiaDAHint, HintWrapper};
 use kona_host::{
     single::SingleChainHintHandler, HintHandler, OnlineHostBackendCfg, SharedKeyValueStore,
 };
@@ -31,6 +28,8 @@ impl HintHandler for CelestiaChainHintHandler {
     ) -> Result<()> {
         match hint.ty {
             HintWrapper::Standard(standard_hint) => {
+                providers.ensure_serves(&standard_hint)?;
+
                 let inner_hint = Hint {
                     ty: standard_hint,
                     data: hint.data,
@@ -49,39 +48,43 @@ impl HintHandler for CelestiaChainHintHandler {
                 }
             }
             HintWrapper::CelestiaDA => {
-                ensure!(hint.data.len() == 40, "Invalid hint data length");
-
-                let height = u64::from_le_bytes(hint.data[0..8].try_into().unwrap());
+                let CelestiaDAHint {
+                    height,
+                    commitment,
+                    namespace,
+                } = CelestiaDAHint::decode(&hint.data)
+                    .map_err(|e| anyhow::anyhow!("Invalid celestia-da hint: {}", e.0))?;
 
-                let hash_array: [u8; 32] =
-                    hint.data[8..40].try_into().expect("Slice must be 32 bytes");
-                let commitment = Commitment::new(hash_array);
+                // Hints for namespaced pointers carry the namespace after the commitment, others
+                // are fetched from the provider's namespace.
+                let namespace = namespace.unwrap_or(providers.celestia.namespace);
 
-                let blob = match providers
-                    .celestia
-                    .client
-                    .blob_get(height, providers.celestia.namespace, commitment)
+                let payload = match providers
+                    .proof_generator
+                    .generate(height, namespace, commitment, cfg, providers)
                     .await
                 {
-                    Ok(blob) => blob,
-                    Err(e) => anyhow::bail!("celestia blob not found: {:#}", e),
+                    Ok(payload) => {
+                        cfg.celestia_args.proof_failure.clear();
+                        payload
+                    }
+                    Err(err) => {
+                        cfg.celestia_args.proof_failure.record(&err);
+                        return Err(err);
+                    }
                 };
 
-                let data = blob.data.clone();
-
-                let blobstream_proof = get_blobstream_proof(
-                    providers.celestia.client.as_ref(),
-                    providers.l1(),
-                    cfg.single_host.l1_head,
-                    height,
-                    blob,
-                )
-                .await?;
-
-                let payload = OraclePayload::new(Bytes::from(data), blobstream_proof)
+                let payload = payload
                     .to_bytes()
                     .expect("failed to serialize celestia oracle payload");
 
+                if let Some(ref dir) = cfg.celestia_args.dump_payloads {
+                    let path = dir.join(format!("{height}-{}.bin", hex::encode(commitment.hash())));
+                    std::fs::write(&path, &payload).map_err(|e| {
+                        anyhow::anyhow!("failed to dump payload to {}: {e}", path.display())
+                    })?;
+                }
+
                 let mut kv_lock = kv.write().await;
 
                 let celestia_commitment_hash = keccak256(&hint.data);
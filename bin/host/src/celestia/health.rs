@@ -0,0 +1,190 @@
+//! A minimal HTTP server reporting the health and readiness of the host.
+//!
+//! - `GET /healthz` returns `200` while the Celestia and L1 nodes respond, `503` otherwise. Before
+//!   the providers are created, only the liveness of the process is reported.
+//! - `GET /readyz` returns `200` once the providers are created and the preimage server runs.
+//! - `GET /metrics` returns the number of Blobstream proofs that failed verification, by stage,
+//!   in the Prometheus text format. Proofs are only verified by the host when the client runs
+//!   natively in process.
+
+use crate::celestia::CelestiaChainProviders;
+use alloy_provider::Provider;
+use celestia_rpc::HeaderClient;
+use hana_oracle::metrics::VerificationMetrics;
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{info, warn};
+
+/// The state reported by the health server.
+#[derive(Debug, Default)]
+pub struct HealthState {
+    /// Whether the preimage server is running.
+    ready: AtomicBool,
+    /// The providers whose connections are checked, once created.
+    providers: OnceLock<CelestiaChainProviders>,
+    /// The verification failure counters exported on `/metrics`.
+    metrics: Arc<VerificationMetrics>,
+}
+
+impl HealthState {
+    /// Creates the state of a host whose client records verification failures in `metrics`.
+    pub fn new(metrics: Arc<VerificationMetrics>) -> Self {
+        Self {
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    /// Marks the host as ready, checking the connections of `providers` from now on if given.
+    pub fn set_ready(&self, providers: Option<CelestiaChainProviders>) {
+        if let Some(providers) = providers {
+            let _ = self.providers.set(providers);
+        }
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the host is ready to serve preimages.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the Celestia and L1 nodes respond.
+    async fn is_healthy(&self) -> bool {
+        let Some(providers) = self.providers.get() else {
+            return true;
+        };
+
+        if let Err(e) = providers.l1().get_block_number().await {
+            warn!(target: "health", "L1 node is unreachable: {e}");
+            return false;
+        }
+        if let Err(e) = providers.celestia.client.header_network_head().await {
+            warn!(target: "health", "Celestia node is unreachable: {e}");
+            return false;
+        }
+        true
+    }
+}
+
+/// Serves the health endpoints on `addr` until the listener fails.
+pub async fn serve_health(addr: SocketAddr, state: Arc<HealthState>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!(target: "health", "Serving health endpoints on {addr}");
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &state).await {
+                warn!(target: "health", "Failed to answer health request: {e}");
+            }
+        });
+    }
+}
+
+/// Answers a single request on `stream`.
+async fn handle_connection(mut stream: TcpStream, state: &HealthState) -> io::Result<()> {
+    let mut buf = [0u8; 1024];
+    let read = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    if path == "/metrics" {
+        let body = render_metrics(&state.metrics);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        return stream.write_all(response.as_bytes()).await;
+    }
+
+    let ok = match path {
+        "/healthz" => Some(state.is_healthy().await),
+        "/readyz" => Some(state.is_ready()),
+        _ => None,
+    };
+    let status = match ok {
+        Some(true) => "200 OK",
+        Some(false) => "503 Service Unavailable",
+        None => "404 Not Found",
+    };
+
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    stream.write_all(response.as_bytes()).await
+}
+
+/// Renders the verification failure counters in the Prometheus text format.
+fn render_metrics(metrics: &VerificationMetrics) -> String {
+    let mut body = String::from(
+        "# HELP hana_verification_failures_total Blobstream proofs that failed verification, by stage.\n\
+         # TYPE hana_verification_failures_total counter\n",
+    );
+    for (stage, count) in metrics.failures() {
+        body.push_str(&format!(
+            "hana_verification_failures_total{{stage=\"{stage}\"}} {count}\n"
+        ));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hana_blobstream::blobstream::VerificationStage;
+
+    /// Sends a `GET` request for `path` to a connection handled with `state`, and returns the
+    /// status line of the response.
+    async fn status_of(state: &HealthState, path: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        client
+            .write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        handle_connection(server, state).await.unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_readiness_switches_once_the_host_is_ready() {
+        let state = HealthState::default();
+        assert_eq!(
+            status_of(&state, "/readyz").await,
+            "HTTP/1.1 503 Service Unavailable"
+        );
+        // Only the liveness of the process is reported before the providers are created.
+        assert_eq!(status_of(&state, "/healthz").await, "HTTP/1.1 200 OK");
+
+        state.set_ready(None);
+        assert_eq!(status_of(&state, "/readyz").await, "HTTP/1.1 200 OK");
+        assert_eq!(status_of(&state, "/healthz").await, "HTTP/1.1 200 OK");
+        assert_eq!(status_of(&state, "/other").await, "HTTP/1.1 404 Not Found");
+    }
+
+    #[test]
+    fn test_render_metrics_reports_the_recorded_failures() {
+        let metrics = VerificationMetrics::default();
+        metrics.record_failure(VerificationStage::StorageProof);
+
+        let body = render_metrics(&metrics);
+        assert!(body.contains("hana_verification_failures_total{stage=\"storage_proof\"} 1\n"));
+        assert!(body.contains("hana_verification_failures_total{stage=\"share_proof\"} 0\n"));
+    }
+}
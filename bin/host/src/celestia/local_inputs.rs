@@ -0,0 +1,219 @@
+//! The local inputs the host serves to the client: the boot info of kona's single chain host, and
+//! the Celestia specific inputs committed to like it.
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use hana_blobstream::blobstream::{encode_blobstream_addresses, BlobstreamAddresses};
+use hana_oracle::provider::BLOBSTREAM_ADDRESSES_KEY;
+use kona_host::{single::SingleChainLocalInputs, KeyValueStore};
+use kona_preimage::PreimageKey;
+
+/// The local inputs of a run: the [SingleChainLocalInputs] of the boot info, and the table of
+/// custom Blobstream deployments at [BLOBSTREAM_ADDRESSES_KEY].
+#[derive(Debug)]
+pub struct CelestiaLocalInputs {
+    /// The boot info inputs.
+    boot: SingleChainLocalInputs,
+    /// The encoded table of custom Blobstream deployments.
+    blobstream_addresses: Vec<u8>,
+}
+
+impl CelestiaLocalInputs {
+    /// Serves `blobstream_addresses` next to the boot info inputs of `boot`.
+    pub fn new(boot: SingleChainLocalInputs, blobstream_addresses: &BlobstreamAddresses) -> Self {
+        Self {
+            boot,
+            blobstream_addresses: encode_blobstream_addresses(blobstream_addresses),
+        }
+    }
+}
+
+impl KeyValueStore for CelestiaLocalInputs {
+    fn get(&self, key: B256) -> Option<Vec<u8>> {
+        if key == preimage_key_hash(PreimageKey::new_local(BLOBSTREAM_ADDRESSES_KEY)) {
+            return Some(self.blobstream_addresses.clone());
+        }
+        self.boot.get(key)
+    }
+
+    fn set(&mut self, key: B256, value: Vec<u8>) -> Result<()> {
+        self.boot.set(key, value)
+    }
+}
+
+/// The key-value store key of a preimage key.
+fn preimage_key_hash(key: PreimageKey) -> B256 {
+    B256::from(<[u8; 32]>::from(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::celestia::CelestiaChainHost;
+    use alloy_primitives::{keccak256, Address, Bytes};
+    use async_trait::async_trait;
+    use clap::Parser;
+    use hana_blobstream::{
+        payload::OraclePayload,
+        test_utils::{synthetic_namespace, synthetic_proof},
+    };
+    use hana_celestia::CelestiaProvider;
+    use hana_oracle::{
+        hint::CelestiaDAHint,
+        provider::{load_blobstream_addresses, OracleCelestiaProvider, VerificationContext},
+    };
+    use kona_host::{MemoryKeyValueStore, SharedKeyValueStore};
+    use kona_preimage::{
+        errors::{PreimageOracleError, PreimageOracleResult},
+        HintWriterClient, PreimageKey, PreimageKeyType, PreimageOracleClient,
+    };
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// The L1 chain id of the runs, Ethereum mainnet which has a canonical Blobstream deployment.
+    const L1_CHAIN_ID: u64 = 1;
+
+    /// A chain without a canonical Blobstream deployment.
+    const CUSTOM_CHAIN_ID: u64 = 424242;
+
+    /// A client oracle reading the key-value store of the host directly, in place of the preimage
+    /// server.
+    #[derive(Debug, Clone)]
+    struct StoreOracle(SharedKeyValueStore);
+
+    #[async_trait]
+    impl PreimageOracleClient for StoreOracle {
+        async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+            self.0
+                .read()
+                .await
+                .get(preimage_key_hash(key))
+                .ok_or(PreimageOracleError::KeyNotFound)
+        }
+
+        async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
+            let preimage = self.get(key).await?;
+            if preimage.len() != buf.len() {
+                return Err(PreimageOracleError::BufferLengthMismatch(
+                    buf.len(),
+                    preimage.len(),
+                ));
+            }
+            buf.copy_from_slice(&preimage);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HintWriterClient for StoreOracle {
+        async fn write(&self, _hint: &str) -> PreimageOracleResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Parses the host arguments of a run on OP mainnet, whose L1 is [L1_CHAIN_ID].
+    fn host(l1_head: B256, extra: &[&str]) -> CelestiaChainHost {
+        try_host(l1_head, extra).unwrap()
+    }
+
+    /// Same as [host], returning the parse error.
+    fn try_host(l1_head: B256, extra: &[&str]) -> Result<CelestiaChainHost, clap::Error> {
+        let zero = B256::ZERO.to_string();
+        let l1_head = l1_head.to_string();
+        let args = [
+            "hana-host",
+            "--l1-head",
+            &l1_head,
+            "--agreed-l2-head-hash",
+            &zero,
+            "--agreed-l2-output-root",
+            &zero,
+            "--claimed-l2-output-root",
+            &zero,
+            "--claimed-l2-block-number",
+            "1",
+            "--l2-chain-id",
+            "10",
+        ];
+        CelestiaChainHost::try_parse_from([&args[..], extra].concat())
+    }
+
+    #[tokio::test]
+    async fn test_local_inputs_serve_the_blobstream_addresses() {
+        let addresses = BlobstreamAddresses::from([(CUSTOM_CHAIN_ID, Address::repeat_byte(0xbb))]);
+        let entry = format!("{CUSTOM_CHAIN_ID}={}", Address::repeat_byte(0xbb));
+        let cfg = host(B256::ZERO, &["--blobstream-addresses", &entry]);
+        let oracle = StoreOracle(cfg.key_value_store_with(MemoryKeyValueStore::new()));
+
+        assert_eq!(load_blobstream_addresses(&oracle).await.unwrap(), addresses);
+
+        // Without custom deployments the host serves an empty table.
+        let cfg = host(B256::ZERO, &[]);
+        let oracle = StoreOracle(cfg.key_value_store_with(MemoryKeyValueStore::new()));
+        assert!(load_blobstream_addresses(&oracle).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_rejects_overrides_of_the_canonical_deployment() {
+        let address = Address::repeat_byte(0xbb);
+        let fixture = synthetic_proof(address, 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), fixture.proof);
+        let commitment = payload.proven_commitment().unwrap();
+        let hint = CelestiaDAHint {
+            height: fixture.height,
+            commitment,
+            namespace: Some(synthetic_namespace()),
+        };
+
+        // The host refuses to serve a mainnet override.
+        let entry = format!("{L1_CHAIN_ID}={address}");
+        let err = try_host(fixture.l1_head, &["--blobstream-addresses", &entry]).unwrap_err();
+        assert!(err.to_string().contains("can't be replaced"), "{err}");
+
+        // A host serving it anyway, bypassing the flag's check.
+        let mut cfg = host(fixture.l1_head, &[]);
+        cfg.celestia_args.blobstream_addresses = vec![(L1_CHAIN_ID, address)];
+        let kv = cfg.key_value_store_with(MemoryKeyValueStore::new());
+        kv.write()
+            .await
+            .set(
+                preimage_key_hash(PreimageKey::new(
+                    *keccak256(hint.encode()),
+                    PreimageKeyType::GlobalGeneric,
+                )),
+                payload.to_bytes().unwrap(),
+            )
+            .unwrap();
+        let oracle = Arc::new(StoreOracle(kv));
+
+        // The client doesn't read the table, and verifies against the canonical deployment.
+        let err = OracleCelestiaProvider::new(oracle.clone())
+            .blob_get_namespaced(fixture.height, synthetic_namespace(), commitment)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("Proof was generated for Blobstream at {address}")),
+            "{err}"
+        );
+
+        // A client loading the table rejects it, as does one given the override directly.
+        assert!(load_blobstream_addresses(oracle.as_ref()).await.is_err());
+        let context = VerificationContext {
+            l1_head: fixture.l1_head,
+            l1_chain_id: L1_CHAIN_ID,
+            anchored_storage_root: None,
+            blobstream_address: Some(address),
+            expected_code_hash: None,
+        };
+        let err = OracleCelestiaProvider::new(oracle)
+            .with_verification_context(context)
+            .blob_get_namespaced(fixture.height, synthetic_namespace(), commitment)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("can't replace the canonical one"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! The manifest of the Celestia data served to the client during a run, for proving pipelines
+//! that must know exactly which DA a derivation consumed.
+
+use alloy_primitives::{hex, keccak256, B256};
+use anyhow::Result;
+use async_trait::async_trait;
+use celestia_types::nmt::Namespace;
+use hana_oracle::{
+    hint::{CelestiaDAHint, HintWrapper},
+    payload::OraclePayload,
+};
+use kona_preimage::{
+    errors::PreimageOracleResult, HintRouter, PreimageFetcher, PreimageKey, PreimageKeyType,
+};
+use kona_proof::Hint;
+use serde::Serialize;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+/// A Celestia blob served to the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsumedBlob {
+    /// The Celestia height of the blob.
+    pub height: u64,
+    /// The blob commitment.
+    pub commitment: B256,
+    /// The hex encoded namespace the blob was fetched from.
+    pub namespace: String,
+    /// The serialized size of the blob's Blobstream proof, in bytes.
+    pub proof_size: usize,
+}
+
+/// The manifest written at the end of a successful run.
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivationManifest {
+    /// The claimed L2 output root.
+    pub l2_output_root: B256,
+    /// The L1 head the derivation ran against.
+    pub l1_head: B256,
+    /// The chain id of the L2, from its rollup config.
+    pub l2_chain_id: u64,
+    /// The Celestia blobs served to the client, in the order they were fetched.
+    pub blobs: Vec<ConsumedBlob>,
+}
+
+impl DerivationManifest {
+    /// Writes the manifest to `path` as JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Records the blobs served during a run. Clones share the same records.
+#[derive(Debug, Clone, Default)]
+pub struct ManifestRecorder {
+    blobs: Arc<Mutex<Vec<ConsumedBlob>>>,
+}
+
+impl ManifestRecorder {
+    /// Records a blob served to the client.
+    pub fn record(&self, height: u64, commitment: B256, namespace: &Namespace, proof_size: usize) {
+        self.blobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ConsumedBlob {
+                height,
+                commitment,
+                namespace: hex::encode(namespace.as_bytes()),
+                proof_size,
+            });
+    }
+
+    /// Returns the blobs recorded so far.
+    pub fn blobs(&self) -> Vec<ConsumedBlob> {
+        self.blobs.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// A preimage server backend recording the Celestia blobs it serves in a [ManifestRecorder].
+///
+/// The blobs are recorded when their payload is served to the client, whether `inner` fetched it
+/// or found it in a key-value store filled by an earlier run.
+#[derive(Debug)]
+pub struct ManifestBackend<B> {
+    inner: B,
+    recorder: Option<ManifestRecorder>,
+    /// The last Celestia hint routed to the backend, with the key of the payload it hints at.
+    last_hint: Mutex<Option<(PreimageKey, CelestiaDAHint)>>,
+}
+
+impl<B> ManifestBackend<B> {
+    /// Wraps `inner`, recording the blobs it serves in `recorder` if given.
+    pub const fn new(inner: B, recorder: Option<ManifestRecorder>) -> Self {
+        Self {
+            inner,
+            recorder,
+            last_hint: Mutex::new(None),
+        }
+    }
+
+    /// Records the blob of `payload` if it is the payload of the last Celestia hint.
+    fn record(&self, recorder: &ManifestRecorder, key: PreimageKey, payload: &[u8]) {
+        let last_hint = *self.last_hint.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((hint_key, hint)) = last_hint else {
+            return;
+        };
+        if hint_key != key {
+            return;
+        }
+
+        // The namespace the shares were proven in is the one the blob was served from, even for
+        // hints without a namespace.
+        let proof = match OraclePayload::from_bytes(payload) {
+            Ok(payload) => payload.blobstream_proof,
+            Err(e) => {
+                warn!(
+                    target: "host",
+                    "Not listing the undecodable payload of the blob at height {} in the manifest: {e}",
+                    hint.height
+                );
+                return;
+            }
+        };
+        let proof_size = match proof.estimated_serialized_size() {
+            Ok(size) => size,
+            Err(e) => {
+                warn!(
+                    target: "host",
+                    "Not listing the blob at height {} in the manifest, its proof size is unknown: {e}",
+                    hint.height
+                );
+                return;
+            }
+        };
+        recorder.record(
+            hint.height,
+            B256::from(*hint.commitment.hash()),
+            &proof.share_proof.namespace(),
+            proof_size,
+        );
+    }
+}
+
+#[async_trait]
+impl<B> HintRouter for ManifestBackend<B>
+where
+    B: HintRouter + Send + Sync,
+{
+    async fn route_hint(&self, hint: String) -> PreimageOracleResult<()> {
+        if self.recorder.is_some() {
+            let celestia_hint = hint
+                .parse::<Hint<HintWrapper>>()
+                .ok()
+                .filter(|hint| hint.ty == HintWrapper::CelestiaDA)
+                .and_then(|hint| {
+                    let key =
+                        PreimageKey::new(*keccak256(&hint.data), PreimageKeyType::GlobalGeneric);
+                    Some((key, CelestiaDAHint::decode(&hint.data).ok()?))
+                });
+            *self.last_hint.lock().unwrap_or_else(|e| e.into_inner()) = celestia_hint;
+        }
+
+        self.inner.route_hint(hint).await
+    }
+}
+
+#[async_trait]
+impl<B> PreimageFetcher for ManifestBackend<B>
+where
+    B: PreimageFetcher + Send + Sync,
+{
+    async fn get_preimage(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+        let preimage = self.inner.get_preimage(key).await?;
+        if let Some(ref recorder) = self.recorder {
+            self.record(recorder, key, &preimage);
+        }
+        Ok(preimage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bytes};
+    use celestia_types::Commitment;
+    use hana_blobstream::test_utils::{synthetic_namespace, synthetic_proof};
+    use kona_preimage::errors::PreimageOracleError;
+    use std::collections::HashMap;
+
+    /// A backend serving the preimages of a key-value store filled by an earlier run, ignoring
+    /// hints.
+    #[derive(Debug, Default)]
+    struct StoreBackend(HashMap<PreimageKey, Vec<u8>>);
+
+    #[async_trait]
+    impl HintRouter for StoreBackend {
+        async fn route_hint(&self, _hint: String) -> PreimageOracleResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl PreimageFetcher for StoreBackend {
+        async fn get_preimage(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+            self.0
+                .get(&key)
+                .cloned()
+                .ok_or(PreimageOracleError::KeyNotFound)
+        }
+    }
+
+    /// Returns the hint of a blob at `height` and its payload, stored in `store`.
+    fn store_blob(store: &mut StoreBackend, height: u64) -> (String, PreimageKey) {
+        let hint = CelestiaDAHint {
+            height,
+            commitment: Commitment::new([height as u8; 32]),
+            namespace: None,
+        };
+        let data = hint.encode();
+        let key = PreimageKey::new(*keccak256(&data), PreimageKeyType::GlobalGeneric);
+        let payload = OraclePayload::new(
+            Bytes::from_static(b"hana-blob"),
+            synthetic_proof(Address::repeat_byte(0xbb), height).proof,
+        );
+        store.0.insert(key, payload.to_bytes().unwrap());
+
+        (Hint::new(HintWrapper::CelestiaDA, data).encode(), key)
+    }
+
+    #[tokio::test]
+    async fn test_records_blobs_served_from_the_store() {
+        let mut store = StoreBackend::default();
+        let (first_hint, first_key) = store_blob(&mut store, 10);
+        let (second_hint, second_key) = store_blob(&mut store, 11);
+
+        let recorder = ManifestRecorder::default();
+        let backend = ManifestBackend::new(store, Some(recorder.clone()));
+        for (hint, key) in [(first_hint, first_key), (second_hint, second_key)] {
+            backend.route_hint(hint).await.unwrap();
+            backend.get_preimage(key).await.unwrap();
+        }
+
+        let blobs = recorder.blobs();
+        assert_eq!(
+            blobs.iter().map(|blob| blob.height).collect::<Vec<_>>(),
+            [10, 11]
+        );
+        assert_eq!(blobs[0].commitment, B256::repeat_byte(10));
+        assert_eq!(
+            blobs[0].namespace,
+            hex::encode(synthetic_namespace().as_bytes())
+        );
+        assert!(blobs[0].proof_size > 0);
+    }
+
+    #[tokio::test]
+    async fn test_other_preimages_are_not_recorded() {
+        let mut store = StoreBackend::default();
+        let (hint, _) = store_blob(&mut store, 10);
+        let other = PreimageKey::new(*keccak256(b"other"), PreimageKeyType::Keccak256);
+        store.0.insert(other, b"other".to_vec());
+
+        let recorder = ManifestRecorder::default();
+        let backend = ManifestBackend::new(store, Some(recorder.clone()));
+        backend.route_hint(hint).await.unwrap();
+        backend.get_preimage(other).await.unwrap();
+
+        assert!(recorder.blobs().is_empty());
+    }
+}
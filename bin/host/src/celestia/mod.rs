@@ -1,12 +1,48 @@
 //! This module contains the celestia-single-chain mode for the host.
 mod cfg;
-pub use cfg::{CelestiaCfg, CelestiaChainHost};
+pub use cfg::{
+    CelestiaCfg, CelestiaChainHost, NamespaceArg, DEFAULT_L1_CONFIRMATIONS,
+    DEFAULT_NAMESPACE_VERSION,
+};
 
 mod handler;
 pub use handler::CelestiaChainHintHandler;
 
 mod providers;
-pub use providers::CelestiaChainProviders;
+pub use providers::{CelestiaChainProviders, NoBeaconApi};
 
 mod online_provider;
-pub use online_provider::OnlineCelestiaProvider;
+pub use online_provider::{BlobPoll, OnlineCelestiaProvider};
+
+mod exit;
+pub use exit::{HostExitCode, ProofFailure};
+
+mod subprocess;
+pub use subprocess::{spawn_client, ClientProcess};
+
+mod health;
+pub use health::{serve_health, HealthState};
+
+mod beacon;
+pub use beacon::BeaconHeaderVerifier;
+
+mod warm;
+pub use warm::WarmCache;
+
+mod counting_layer;
+pub use counting_layer::{counting_rpc_provider, CountingLayer, CountingService};
+
+mod manifest;
+pub use manifest::{ConsumedBlob, DerivationManifest, ManifestBackend, ManifestRecorder};
+
+mod generator;
+pub use generator::{BlobstreamProofGenerator, ProofGenerator};
+
+mod local_inputs;
+pub use local_inputs::CelestiaLocalInputs;
+
+mod shared;
+pub use shared::{SharedHostResources, SharedPreimageStore};
+
+#[cfg(test)]
+mod test_utils;
@@ -1,23 +1,144 @@
-use celestia_rpc::Client;
-use celestia_types::nmt::Namespace;
-use std::sync::Arc;
+use anyhow::Result;
+use celestia_rpc::{BlobClient, Client};
+use celestia_types::{nmt::Namespace, Blob, Commitment};
+use hana_proofs::{
+    errors::{is_celestia_api_error, BLOB_NOT_FOUND_ERROR},
+    rpc_counter::RpcCallCounter,
+};
+use jsonrpsee::core::ClientError;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::time::{sleep, Instant};
+use tracing::debug;
+
+/// How long to keep polling for a blob the node reports as not found, e.g. because it was only
+/// just posted at the tip of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobPoll {
+    /// The time after which a missing blob is reported as absent.
+    pub window: Duration,
+    /// The delay before the first retry, doubled after every retry.
+    pub interval: Duration,
+}
 
 /// Online client to fetch data from a Celestia network
 #[derive(Clone)]
 pub struct OnlineCelestiaProvider {
     /// The node client
     pub client: Arc<Client>,
-    /// The namespace to fetch data from
+    /// The namespace to fetch data from when none is given
     pub namespace: Namespace,
+    /// The counter calls to the node are recorded in, if any
+    pub rpc_counter: Option<RpcCallCounter>,
+    /// The polling of blobs not found yet, if enabled
+    pub blob_poll: Option<BlobPoll>,
 }
 
 impl OnlineCelestiaProvider {
     pub fn new(client: Client, namespace: Namespace) -> Self {
+        Self::from_arc(Arc::new(client), namespace)
+    }
+
+    /// Creates a provider sharing `client` with the rest of the application.
+    pub fn from_arc(client: Arc<Client>, namespace: Namespace) -> Self {
         OnlineCelestiaProvider {
-            client: Arc::new(client),
+            client,
             namespace,
+            rpc_counter: None,
+            blob_poll: None,
+        }
+    }
+
+    /// Polls for blobs the node doesn't have yet as configured by `poll`.
+    pub fn with_blob_poll(mut self, poll: BlobPoll) -> Self {
+        self.blob_poll = Some(poll);
+        self
+    }
+
+    /// Records every call made to the node in `counter`.
+    pub fn with_rpc_counter(mut self, counter: RpcCallCounter) -> Self {
+        self.rpc_counter = Some(counter);
+        self
+    }
+
+    /// Records a call to `method` in the rpc counter.
+    fn record_call(&self, method: &str) {
+        if let Some(ref counter) = self.rpc_counter {
+            counter.record(method);
         }
     }
+
+    /// Fetches the blob with the given commitment at `height` from `namespace`, or from the
+    /// provider's namespace if none is given.
+    ///
+    /// With [BlobPoll] enabled, a blob the node reports as not found is requested again with
+    /// backoff until the poll window elapses.
+    pub async fn blob_get(
+        &self,
+        height: u64,
+        namespace: Option<Namespace>,
+        commitment: Commitment,
+    ) -> Result<Blob> {
+        let namespace = namespace.unwrap_or(self.namespace);
+        poll_blob(self.blob_poll, height, || {
+            self.record_call("blob.Get");
+            self.client.blob_get(height, namespace, commitment)
+        })
+        .await
+    }
+
+    /// Fetches every blob posted to the provider's namespace at `height`.
+    pub async fn blob_get_all(&self, height: u64) -> Result<Vec<Blob>> {
+        self.record_call("blob.GetAll");
+        let blobs = self
+            .client
+            .blob_get_all(height, &[self.namespace])
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to fetch celestia blobs: {:#}", e))?;
+        Ok(blobs.unwrap_or_default())
+    }
+}
+
+/// Fetches the blob at `height` with `fetch`, polling as configured by `poll` while the node
+/// reports it as not found, see [OnlineCelestiaProvider::blob_get].
+async fn poll_blob<F, Fut>(poll: Option<BlobPoll>, height: u64, mut fetch: F) -> Result<Blob>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Blob, ClientError>>,
+{
+    let deadline = poll.map(|poll| (Instant::now() + poll.window, poll));
+    let mut interval = poll.map(|poll| poll.interval).unwrap_or_default();
+
+    loop {
+        let err = match fetch().await {
+            Ok(blob) => return Ok(blob),
+            Err(err) => err,
+        };
+
+        match deadline {
+            Some((deadline, _)) if is_blob_not_found(&err) && Instant::now() < deadline => {
+                debug!(
+                    target: "host",
+                    "Celestia blob at height {height} not found yet, retrying in {interval:?}"
+                );
+                sleep(interval.min(deadline.saturating_duration_since(Instant::now()))).await;
+                interval *= 2;
+            }
+            Some((_, poll)) if is_blob_not_found(&err) => {
+                anyhow::bail!(
+                    "celestia blob not found after polling for {:?}: {:#}",
+                    poll.window,
+                    err
+                )
+            }
+            _ => anyhow::bail!("celestia blob not found: {:#}", err),
+        }
+    }
+}
+
+/// Returns `true` if the node's error means it doesn't have the blob, rather than failing to
+/// serve it.
+fn is_blob_not_found(err: &ClientError) -> bool {
+    is_celestia_api_error(err, &[BLOB_NOT_FOUND_ERROR])
 }
 
 impl core::fmt::Debug for OnlineCelestiaProvider {
@@ -27,3 +148,108 @@ impl core::fmt::Debug for OnlineCelestiaProvider {
             .finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::AppVersion;
+    use hana_blobstream::test_utils::synthetic_namespace;
+    use hana_proofs::errors::CELESTIA_API_ERROR_CODE;
+    use jsonrpsee::types::ErrorObject;
+    use std::{cell::Cell, future::ready};
+
+    const POLL: BlobPoll = BlobPoll {
+        window: Duration::from_secs(60),
+        interval: Duration::from_millis(1),
+    };
+
+    fn blob() -> Blob {
+        Blob::new(
+            synthetic_namespace(),
+            b"hana-blob".to_vec(),
+            None,
+            AppVersion::latest(),
+        )
+        .unwrap()
+    }
+
+    fn not_found() -> ClientError {
+        ClientError::Call(ErrorObject::owned(
+            CELESTIA_API_ERROR_CODE,
+            BLOB_NOT_FOUND_ERROR,
+            None::<()>,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_from_arc_shares_the_client() {
+        // Building an HTTP client doesn't connect to the node.
+        let client = Arc::new(Client::new("http://127.0.0.1:1", None).await.unwrap());
+
+        let provider = OnlineCelestiaProvider::from_arc(client.clone(), synthetic_namespace());
+        assert!(Arc::ptr_eq(&provider.client, &client));
+        assert_eq!(Arc::strong_count(&client), 2);
+
+        drop(provider);
+        assert_eq!(Arc::strong_count(&client), 1);
+    }
+
+    #[tokio::test]
+    async fn test_blob_appears_on_third_poll() {
+        let calls = Cell::new(0);
+        let blob = poll_blob(Some(POLL), 10, || {
+            calls.set(calls.get() + 1);
+            ready(if calls.get() < 3 {
+                Err(not_found())
+            } else {
+                Ok(blob())
+            })
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls.get(), 3);
+        assert_eq!(blob.data, b"hana-blob");
+    }
+
+    #[tokio::test]
+    async fn test_missing_blob_is_not_polled_without_poll() {
+        let calls = Cell::new(0);
+        poll_blob(None, 10, || {
+            calls.set(calls.get() + 1);
+            ready(Err(not_found()))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_polled() {
+        let calls = Cell::new(0);
+        poll_blob(Some(POLL), 10, || {
+            calls.set(calls.get() + 1);
+            // The blob's text with another code, e.g. from a proxy, isn't the node's error.
+            ready(Err(ClientError::Call(ErrorObject::owned(
+                -32000,
+                BLOB_NOT_FOUND_ERROR,
+                None::<()>,
+            ))))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_blob_is_reported_after_the_window() {
+        let poll = BlobPoll {
+            window: Duration::from_millis(20),
+            ..POLL
+        };
+        let err = poll_blob(Some(poll), 10, || ready(Err(not_found())))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after polling for 20ms"));
+    }
+}
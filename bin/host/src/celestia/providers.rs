@@ -1,8 +1,29 @@
-use crate::celestia::OnlineCelestiaProvider;
+use crate::celestia::{
+    BeaconHeaderVerifier, BlobstreamProofGenerator, OnlineCelestiaProvider, ProofGenerator,
+};
 use alloy_provider::RootProvider;
+use hana_proofs::commitment_index::CommitmentIndex;
 use kona_host::single::SingleChainProviders;
+use kona_proof::HintType;
 use kona_providers_alloy::{OnlineBeaconClient, OnlineBlobProvider};
 use op_alloy_network::Optimism;
+use std::{fmt, sync::Arc};
+use tokio::sync::Mutex;
+
+/// The error returned for the L1 blobs requested from a host running without a beacon API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoBeaconApi;
+
+impl fmt::Display for NoBeaconApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "no beacon API configured, cannot fetch L1 blobs: set `--l1-beacon-address` to \
+             derive rollups posting blobs to L1",
+        )
+    }
+}
+
+impl std::error::Error for NoBeaconApi {}
 
 /// The combined providers for Celestia and single chain operations
 #[derive(Debug, Clone)]
@@ -11,6 +32,17 @@ pub struct CelestiaChainProviders {
     pub inner_providers: SingleChainProviders,
     /// The Celestia provider
     pub celestia: OnlineCelestiaProvider,
+    /// The index of Blobstream data commitments, if one is kept
+    pub commitment_index: Option<Arc<Mutex<CommitmentIndex>>>,
+    /// The archive L1 provider storage proofs fall back to, if one is configured
+    pub l1_archive: Option<RootProvider>,
+    /// The verifier checking proof headers against the beacon chain, if enabled
+    pub beacon_verifier: Option<BeaconHeaderVerifier>,
+    /// The strategy generating the oracle payloads of Celestia blobs
+    pub proof_generator: Arc<dyn ProofGenerator>,
+    /// Whether the blob provider of the inner providers is backed by a beacon API. Without one,
+    /// the blob provider is a placeholder that must never be queried
+    pub has_beacon_api: bool,
 }
 
 impl CelestiaChainProviders {
@@ -19,17 +51,42 @@ impl CelestiaChainProviders {
         Self {
             inner_providers,
             celestia,
+            commitment_index: None,
+            l1_archive: None,
+            beacon_verifier: None,
+            proof_generator: Arc::new(BlobstreamProofGenerator),
+            has_beacon_api: true,
         }
     }
 
+    /// Marks the blob provider of `inner_providers` as a placeholder, rejecting L1 blob requests
+    /// with [NoBeaconApi]
+    pub fn without_beacon_api(mut self) -> Self {
+        self.has_beacon_api = false;
+        self
+    }
+
+    /// Generate the oracle payloads with `proof_generator` instead of through Blobstream
+    pub fn with_proof_generator(mut self, proof_generator: Arc<dyn ProofGenerator>) -> Self {
+        self.proof_generator = proof_generator;
+        self
+    }
+
     /// Access the L1 provider from the inner providers
     pub fn l1(&self) -> &RootProvider {
         &self.inner_providers.l1
     }
 
-    /// Access the blob provider from the inner providers
-    pub fn blobs(&self) -> &OnlineBlobProvider<OnlineBeaconClient> {
-        &self.inner_providers.blobs
+    /// Access the blob provider from the inner providers, failing if there is no beacon API
+    pub fn blobs(&self) -> Result<&OnlineBlobProvider<OnlineBeaconClient>, NoBeaconApi> {
+        self.has_beacon_api
+            .then_some(&self.inner_providers.blobs)
+            .ok_or(NoBeaconApi)
+    }
+
+    /// Fails with [NoBeaconApi] if a hint of type `hint` needs a beacon API that isn't configured
+    pub fn ensure_serves(&self, hint: &HintType) -> Result<(), NoBeaconApi> {
+        ensure_serves(hint, self.has_beacon_api)
     }
 
     /// Access the L2 provider from the inner providers
@@ -38,8 +95,43 @@ impl CelestiaChainProviders {
     }
 }
 
+/// Fails with [NoBeaconApi] if `hint` is an L1 blob hint and there is no beacon API.
+fn ensure_serves(hint: &HintType, has_beacon_api: bool) -> Result<(), NoBeaconApi> {
+    match hint {
+        HintType::L1Blob if !has_beacon_api => Err(NoBeaconApi),
+        _ => Ok(()),
+    }
+}
+
 impl From<CelestiaChainProviders> for SingleChainProviders {
     fn from(providers: CelestiaChainProviders) -> Self {
         providers.inner_providers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l1_blob_hints_require_a_beacon_api() {
+        assert_eq!(ensure_serves(&HintType::L1Blob, false), Err(NoBeaconApi));
+        assert_eq!(ensure_serves(&HintType::L1Blob, true), Ok(()));
+    }
+
+    #[test]
+    fn test_other_hints_are_served_without_a_beacon_api() {
+        for hint in [
+            HintType::L1BlockHeader,
+            HintType::L1Receipts,
+            HintType::L2BlockHeader,
+        ] {
+            assert_eq!(ensure_serves(&hint, false), Ok(()), "{hint:?}");
+        }
+    }
+
+    #[test]
+    fn test_no_beacon_api_message_names_the_flag() {
+        assert!(NoBeaconApi.to_string().contains("--l1-beacon-address"));
+    }
+}
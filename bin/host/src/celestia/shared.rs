@@ -0,0 +1,121 @@
+//! Resources a long-lived host shares between the runs it serves, so that the provider
+//! connections and the preimage store are set up once per process rather than once per run.
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use kona_host::KeyValueStore;
+use std::sync::{Arc, RwLock};
+
+use super::CelestiaChainProviders;
+
+/// A [KeyValueStore] shared by the runs of a host. Clones refer to the same store.
+///
+/// Every run puts its own local inputs in front of the shared store, see
+/// [CelestiaChainHost::key_value_store_with](super::CelestiaChainHost::key_value_store_with).
+#[derive(Clone)]
+pub struct SharedPreimageStore {
+    inner: Arc<RwLock<dyn KeyValueStore + Send + Sync>>,
+}
+
+impl SharedPreimageStore {
+    /// Shares `store` between runs.
+    pub fn new<B>(store: B) -> Self
+    where
+        B: KeyValueStore + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(RwLock::new(store)),
+        }
+    }
+}
+
+impl KeyValueStore for SharedPreimageStore {
+    fn get(&self, key: B256) -> Option<Vec<u8>> {
+        self.inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+    }
+
+    fn set(&mut self, key: B256, value: Vec<u8>) -> Result<()> {
+        self.inner
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .set(key, value)
+    }
+}
+
+impl core::fmt::Debug for SharedPreimageStore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SharedPreimageStore")
+            .finish_non_exhaustive()
+    }
+}
+
+/// The providers and preimage store created once and reused by every run of a host.
+#[derive(Debug, Clone)]
+pub struct SharedHostResources {
+    /// The preimages fetched by any run.
+    pub store: SharedPreimageStore,
+    /// The providers, `None` in offline mode.
+    pub providers: Option<CelestiaChainProviders>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::{
+        test_utils::{offline_providers, synthetic_hint, SyntheticProofGenerator},
+        CelestiaChainHost,
+    };
+    use alloy_primitives::keccak256;
+    use hana_oracle::{hint::HintWrapper, payload::OraclePayload};
+    use kona_host::MemoryKeyValueStore;
+    use kona_preimage::{
+        BidirectionalChannel, HintWriter, OracleReader, PreimageKey, PreimageKeyType,
+        PreimageOracleClient,
+    };
+    use kona_proof::Hint;
+
+    /// Runs a client requesting the synthetic blob against a server started by `cfg` with
+    /// `resources`, and returns the blob.
+    async fn request_blob(cfg: &CelestiaChainHost, resources: &SharedHostResources) -> Vec<u8> {
+        let hint = BidirectionalChannel::new().unwrap();
+        let preimage = BidirectionalChannel::new().unwrap();
+        let server = cfg.start_server_with(hint.host, preimage.host, resources);
+
+        let encoded = synthetic_hint().encode();
+        Hint::new(HintWrapper::CelestiaDA, encoded.clone())
+            .send(&HintWriter::new(hint.client))
+            .await
+            .unwrap();
+        let payload = OracleReader::new(preimage.client)
+            .get(PreimageKey::new(
+                *keccak256(&encoded),
+                PreimageKeyType::GlobalGeneric,
+            ))
+            .await
+            .unwrap();
+
+        server.abort();
+        OraclePayload::from_bytes(&payload).unwrap().blob.to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_sequential_runs_share_the_providers_and_preimages() {
+        let generator = Arc::new(SyntheticProofGenerator::default());
+        let resources = SharedHostResources {
+            store: SharedPreimageStore::new(MemoryKeyValueStore::new()),
+            providers: Some(offline_providers(generator.clone()).await),
+        };
+
+        let mut cfg = CelestiaChainHost::default();
+        assert_eq!(request_blob(&cfg, &resources).await, b"hana-blob");
+        assert_eq!(generator.generated(), 1);
+
+        // Another run, with its own local inputs, is served the payload fetched by the first.
+        cfg.single_host.l1_head = B256::repeat_byte(0x01);
+        assert_eq!(request_blob(&cfg, &resources).await, b"hana-blob");
+        assert_eq!(generator.generated(), 1);
+    }
+}
@@ -0,0 +1,143 @@
+//! Launches the client program as a child process, wired to the host over the same file
+//! descriptors an FPVM would provide.
+
+use kona_std_fpvm::{FileChannel, FileDescriptor};
+use std::{
+    io,
+    os::{
+        fd::{AsRawFd, IntoRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    path::Path,
+};
+use tokio::process::{Child, Command};
+
+/// The host side of the channels connected to a client child process.
+#[derive(Debug)]
+pub struct ClientProcess {
+    /// The hint channel, read by the host.
+    pub hint: FileChannel,
+    /// The preimage channel, written by the host.
+    pub preimage: FileChannel,
+    /// The child process running the client program.
+    pub child: Child,
+}
+
+/// Spawns the client program at `client_bin`, with its hint and preimage pipes mapped onto the
+/// [FileDescriptor]s used by [FileChannel] in the client.
+pub fn spawn_client(client_bin: &Path) -> io::Result<ClientProcess> {
+    let (hint_host_read, hint_client_write) = io::pipe()?;
+    let (hint_client_read, hint_host_write) = io::pipe()?;
+    let (preimage_host_read, preimage_client_write) = io::pipe()?;
+    let (preimage_client_read, preimage_host_write) = io::pipe()?;
+
+    let client_fds: [(OwnedFd, FileDescriptor); 4] = [
+        (hint_client_read.into(), FileDescriptor::HintRead),
+        (hint_client_write.into(), FileDescriptor::HintWrite),
+        (preimage_client_read.into(), FileDescriptor::PreimageRead),
+        (preimage_client_write.into(), FileDescriptor::PreimageWrite),
+    ];
+    let mappings: Vec<(i32, i32)> = client_fds
+        .iter()
+        .map(|(fd, target)| (fd.as_raw_fd(), usize::from(*target) as i32))
+        .collect();
+
+    let mut command = Command::new(client_bin);
+    // SAFETY: only async-signal-safe libc calls are made between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            // Move every descriptor out of the target range first, so that mapping one doesn't
+            // clobber another that happens to already sit on a target descriptor. The moved
+            // copies are closed on exec, only the targets `dup2` creates without the flag are
+            // inherited by the client.
+            let mut moved = [0i32; 4];
+            for (i, (fd, _)) in mappings.iter().enumerate() {
+                moved[i] = libc::fcntl(*fd, libc::F_DUPFD_CLOEXEC, 10);
+                if moved[i] < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            for (i, (_, target)) in mappings.iter().enumerate() {
+                if libc::dup2(moved[i], *target) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    let child = command.kill_on_drop(true).spawn()?;
+
+    // The child holds its own copies of the client ends now.
+    drop(client_fds);
+
+    let hint = FileChannel::new(
+        FileDescriptor::Wildcard(OwnedFd::from(hint_host_read).into_raw_fd() as usize),
+        FileDescriptor::Wildcard(OwnedFd::from(hint_host_write).into_raw_fd() as usize),
+    );
+    let preimage = FileChannel::new(
+        FileDescriptor::Wildcard(OwnedFd::from(preimage_host_read).into_raw_fd() as usize),
+        FileDescriptor::Wildcard(OwnedFd::from(preimage_host_write).into_raw_fd() as usize),
+    );
+
+    Ok(ClientProcess {
+        hint,
+        preimage,
+        child,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    /// Returns the descriptors of this process a child inherits, i.e. those not closed on exec.
+    fn inheritable_fds() -> Vec<i32> {
+        fs::read_dir("/proc/self/fd")
+            .unwrap()
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            // SAFETY: F_GETFD only reads the flags of the descriptor.
+            .filter(|fd| unsafe { libc::fcntl(*fd, libc::F_GETFD) } & libc::FD_CLOEXEC == 0)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_client_only_inherits_the_channel_descriptors() {
+        let dir = std::env::temp_dir().join(format!("hana-subprocess-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let listing = dir.join("fds");
+        let client = dir.join("client.sh");
+        fs::write(
+            &client,
+            format!("#!/bin/sh\nexec ls /proc/self/fd > {}\n", listing.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&client, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let inherited = inheritable_fds();
+        let mut process = spawn_client(&client).unwrap();
+        assert!(process.child.wait().await.unwrap().success());
+
+        let fds: Vec<i32> = fs::read_to_string(&listing)
+            .unwrap()
+            .lines()
+            .map(|fd| fd.parse().unwrap())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        for target in [
+            FileDescriptor::HintRead,
+            FileDescriptor::HintWrite,
+            FileDescriptor::PreimageRead,
+            FileDescriptor::PreimageWrite,
+        ] {
+            assert!(fds.contains(&(usize::from(target) as i32)), "{fds:?}");
+        }
+        // Besides the standard streams, the channels and the directory `ls` reads, the client
+        // only holds what the test process itself passes on.
+        assert!(
+            fds.iter().all(|fd| *fd < 10 || inherited.contains(fd)),
+            "{fds:?}"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Helpers shared by the tests of the host.
+
+use alloy_primitives::{Address, Bytes};
+use alloy_provider::RootProvider;
+use anyhow::Result;
+use async_trait::async_trait;
+use celestia_types::{nmt::Namespace, Commitment};
+use hana_blobstream::test_utils::{synthetic_namespace, synthetic_proof};
+use hana_oracle::{hint::CelestiaDAHint, payload::OraclePayload};
+use kona_host::single::SingleChainProviders;
+use kona_providers_alloy::{OnlineBeaconClient, OnlineBlobProvider};
+use op_alloy_network::Optimism;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use super::{CelestiaChainHost, CelestiaChainProviders, OnlineCelestiaProvider, ProofGenerator};
+
+/// The address of nodes that are never reached.
+const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+/// The Celestia height of the synthetic blob.
+pub(crate) const SYNTHETIC_HEIGHT: u64 = 100;
+
+/// Returns providers whose nodes are never reached, generating the Celestia payloads with
+/// `proof_generator`.
+pub(crate) async fn offline_providers(
+    proof_generator: Arc<dyn ProofGenerator>,
+) -> CelestiaChainProviders {
+    let inner_providers = SingleChainProviders {
+        l1: RootProvider::new_http(UNREACHABLE.parse().unwrap()),
+        blobs: OnlineBlobProvider::new(OnlineBeaconClient::new_http(String::new()), 0, 12),
+        l2: RootProvider::<Optimism>::new_http(UNREACHABLE.parse().unwrap()),
+    };
+    let client = celestia_rpc::Client::new(UNREACHABLE, None).await.unwrap();
+
+    CelestiaChainProviders::new(
+        inner_providers,
+        OnlineCelestiaProvider::new(client, synthetic_namespace()),
+    )
+    .without_beacon_api()
+    .with_proof_generator(proof_generator)
+}
+
+/// Returns the payload of the synthetic blob at [SYNTHETIC_HEIGHT].
+pub(crate) fn synthetic_payload() -> OraclePayload {
+    let fixture = synthetic_proof(Address::repeat_byte(0xbb), SYNTHETIC_HEIGHT);
+    OraclePayload::new(Bytes::from_static(b"hana-blob"), fixture.proof)
+}
+
+/// Returns the hint requesting the synthetic blob.
+pub(crate) fn synthetic_hint() -> CelestiaDAHint {
+    CelestiaDAHint {
+        height: SYNTHETIC_HEIGHT,
+        commitment: synthetic_payload().proven_commitment().unwrap(),
+        namespace: Some(synthetic_namespace()),
+    }
+}
+
+/// A [ProofGenerator] serving [synthetic_payload] without querying the nodes, counting the
+/// payloads it generated.
+#[derive(Debug, Default)]
+pub(crate) struct SyntheticProofGenerator {
+    /// The number of payloads generated.
+    generated: AtomicUsize,
+}
+
+impl SyntheticProofGenerator {
+    /// Returns the number of payloads generated.
+    pub(crate) fn generated(&self) -> usize {
+        self.generated.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ProofGenerator for SyntheticProofGenerator {
+    async fn generate(
+        &self,
+        height: u64,
+        _namespace: Namespace,
+        commitment: Commitment,
+        _cfg: &CelestiaChainHost,
+        _providers: &CelestiaChainProviders,
+    ) -> Result<OraclePayload> {
+        let hint = synthetic_hint();
+        anyhow::ensure!(
+            height == hint.height && commitment == hint.commitment,
+            "no blob with commitment {commitment:?} at height {height}"
+        );
+
+        self.generated.fetch_add(1, Ordering::SeqCst);
+        Ok(synthetic_payload())
+    }
+}
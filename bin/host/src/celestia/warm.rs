@@ -0,0 +1,333 @@
+//! Pre-fetches the Celestia oracle payloads for a range of heights into the key-value store, so
+//! a later proving run finds every blob preimage already cached.
+
+use alloy_primitives::{keccak256, Bytes, B256};
+use anyhow::{anyhow, ensure, Result};
+use celestia_types::Blob;
+use clap::Parser;
+use hana_blobstream::blobstream::BlobstreamProof;
+use hana_oracle::payload::OraclePayload;
+use hana_proofs::blobstream_inclusion::get_blobstream_proofs;
+use kona_cli::cli_styles;
+use kona_host::SharedKeyValueStore;
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+
+use super::{generator::verify_generated_proof, CelestiaChainHost, CelestiaChainProviders};
+
+/// The arguments of the `warm-cache` subcommand.
+#[derive(Parser, Serialize, Clone, Debug)]
+#[command(styles = cli_styles())]
+pub struct WarmCache {
+    /// First Celestia height to fetch the blobs of
+    #[clap(long)]
+    pub from_height: u64,
+    /// Last Celestia height to fetch the blobs of (inclusive)
+    #[clap(long)]
+    pub to_height: u64,
+    #[clap(flatten)]
+    pub host: CelestiaChainHost,
+}
+
+impl WarmCache {
+    /// Generates and stores the oracle payload of every blob posted to the configured namespace
+    /// between `from_height` and `to_height`, skipping the ones already in the key-value store.
+    ///
+    /// Every payload is stored as soon as it is generated, and every height whose blobs were all
+    /// proven is recorded as completed in the data directory, see [WarmProgress]. Running it
+    /// again after an interruption skips the completed heights without querying the nodes and
+    /// resumes where it stopped.
+    pub async fn start(self) -> Result<()> {
+        ensure!(
+            self.from_height <= self.to_height,
+            "--from-height must not be above --to-height"
+        );
+        ensure!(
+            !self.host.is_offline(),
+            "warming the cache requires the L1, L2 and beacon nodes"
+        );
+
+        let kv = self.host.create_key_value_store()?;
+        let providers = self.host.create_providers().await?;
+        let mut progress = WarmProgress::load(self.host.single_host.data_dir.as_deref());
+
+        warm_range(self.from_height..=self.to_height, &mut progress, |height| {
+            self.warm_height(height, &providers, &kv)
+        })
+        .await
+    }
+
+    /// Stores the payloads of the uncached blobs at `height`, returning how many were stored and
+    /// how many failed to be proven.
+    async fn warm_height(
+        &self,
+        height: u64,
+        providers: &CelestiaChainProviders,
+        kv: &SharedKeyValueStore,
+    ) -> Result<(usize, usize)> {
+        let mut blobs: Vec<(B256, Blob)> = Vec::new();
+        {
+            let kv = kv.read().await;
+            for blob in providers.celestia.blob_get_all(height).await? {
+                let key = payload_key(height, &blob);
+                if kv.get(key).is_none() {
+                    blobs.push((key, blob));
+                }
+            }
+        }
+        if blobs.is_empty() {
+            return Ok((0, 0));
+        }
+
+        let (keys, blobs): (Vec<_>, Vec<_>) = blobs
+            .into_iter()
+            .map(|(key, blob)| (key, (height, blob)))
+            .unzip();
+        let data: Vec<_> = blobs.iter().map(|(_, blob)| blob.data.clone()).collect();
+
+        let l1_head = self.host.single_host.l1_head;
+        let proofs = get_blobstream_proofs(
+            providers.celestia.client.as_ref(),
+            providers.l1(),
+            l1_head,
+            blobs,
+            providers.l1_archive.as_ref(),
+            &self.host.proof_options(),
+        )
+        .await;
+
+        let blobstream_address = if self.host.celestia_args.verify_on_generate {
+            Some(
+                self.host
+                    .expected_blobstream_address(providers.l1())
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        let (mut stored, mut failed) = (0, 0);
+        for ((key, data), proof) in keys.into_iter().zip(data).zip(proofs) {
+            let proof = match proof {
+                Ok(proof) => proof,
+                Err(e) => {
+                    warn!(target: "host", "Failed to prove a blob at height {height}: {e}");
+                    failed += 1;
+                    continue;
+                }
+            };
+
+            if let Some(ref verifier) = providers.beacon_verifier {
+                verifier.verify_header(&proof.block_header).await?;
+            }
+            if let Some(blobstream_address) = blobstream_address {
+                verify_generated_proof(&proof, height, l1_head, blobstream_address)?;
+            }
+
+            store_payload(kv, key, Bytes::from(data), proof).await?;
+            stored += 1;
+        }
+
+        Ok((stored, failed))
+    }
+}
+
+/// Warms every height in `heights` that no previous run completed, with `warm_height` returning
+/// how many payloads it stored for a height and how many of its blobs failed to be proven.
+async fn warm_range<F, Fut>(
+    heights: RangeInclusive<u64>,
+    progress: &mut WarmProgress,
+    mut warm_height: F,
+) -> Result<()>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<(usize, usize)>>,
+{
+    let total = heights.end() - heights.start() + 1;
+    let mut stored = 0;
+    for (i, height) in heights.enumerate() {
+        if progress.is_completed(height) {
+            info!(
+                target: "host",
+                "Skipping height {height} ({}/{total}), completed by a previous run",
+                i + 1
+            );
+            continue;
+        }
+
+        let (height_stored, failed) = warm_height(height).await?;
+        stored += height_stored;
+        if failed == 0 {
+            progress.complete(height)?;
+        }
+        info!(
+            target: "host",
+            "Warmed height {height} ({}/{total}), {stored} payloads stored",
+            i + 1
+        );
+    }
+
+    Ok(())
+}
+
+/// Stores the oracle payload of the blob `data` proven by `proof` under `key`.
+async fn store_payload(
+    kv: &SharedKeyValueStore,
+    key: B256,
+    data: Bytes,
+    proof: BlobstreamProof,
+) -> Result<()> {
+    let payload = OraclePayload::new(data, proof)
+        .to_bytes()
+        .map_err(|e| anyhow!("failed to serialize celestia oracle payload: {e}"))?;
+    kv.write().await.set(key, payload)
+}
+
+/// The key-value store key of the oracle payload of `blob`, as requested by the client for a
+/// pointer without a namespace.
+fn payload_key(height: u64, blob: &Blob) -> B256 {
+    let mut hint_data = height.to_le_bytes().to_vec();
+    hint_data.extend_from_slice(blob.commitment.hash());
+    PreimageKey::new(*keccak256(&hint_data), PreimageKeyType::GlobalGeneric).into()
+}
+
+/// The Celestia heights whose blobs were all stored by previous runs.
+///
+/// The progress is kept in [WarmProgress::FILE_NAME] in the data directory rather than in the
+/// key-value store, so that the host's bookkeeping never shares a key with a preimage. Without a
+/// data directory the payloads aren't persisted either, and the progress only lasts for the run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WarmProgress {
+    /// The completed heights.
+    completed: BTreeSet<u64>,
+    /// The file the progress is saved to, if any.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl WarmProgress {
+    /// The name of the progress file in the data directory.
+    const FILE_NAME: &str = "warm-cache-progress.json";
+
+    /// Loads the progress saved in `data_dir`, starting over if there is none or it is
+    /// unreadable.
+    fn load(data_dir: Option<&Path>) -> Self {
+        let Some(path) = data_dir.map(|dir| dir.join(Self::FILE_NAME)) else {
+            return Self::default();
+        };
+
+        let mut progress = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                warn!(target: "host", "Failed to read {}, starting over: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        progress.path = Some(path);
+        progress
+    }
+
+    /// Returns `true` if `height` was completed.
+    fn is_completed(&self, height: u64) -> bool {
+        self.completed.contains(&height)
+    }
+
+    /// Records `height` as completed, saving the progress if it is kept in a file.
+    fn complete(&mut self, height: u64) -> Result<()> {
+        self.completed.insert(height);
+        if let Some(ref path) = self.path {
+            std::fs::write(path, serde_json::to_string(self)?)
+                .map_err(|e| anyhow!("failed to save {}: {e}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+    use celestia_types::AppVersion;
+    use hana_blobstream::test_utils::{synthetic_namespace, synthetic_proof};
+    use kona_host::MemoryKeyValueStore;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_warming_a_range_fills_the_key_value_store() {
+        let kv: SharedKeyValueStore = Arc::new(RwLock::new(MemoryKeyValueStore::new()));
+        let mut progress = WarmProgress::load(None);
+        let blob = Blob::new(
+            synthetic_namespace(),
+            b"hana-blob".to_vec(),
+            None,
+            AppVersion::latest(),
+        )
+        .unwrap();
+
+        // Stands in for the nodes, proving the same blob at every height.
+        warm_range(100..=102, &mut progress, |height| {
+            let (kv, blob) = (&kv, &blob);
+            async move {
+                let proof = synthetic_proof(Address::repeat_byte(0xbb), height).proof;
+                let key = payload_key(height, blob);
+                store_payload(kv, key, Bytes::from(blob.data.clone()), proof).await?;
+                Ok((1, 0))
+            }
+        })
+        .await
+        .unwrap();
+
+        for height in 100..=102 {
+            let payload = kv.read().await.get(payload_key(height, &blob)).unwrap();
+            let payload = OraclePayload::from_bytes(&payload).unwrap();
+            assert_eq!(payload.blob.as_ref(), b"hana-blob");
+            assert!(progress.is_completed(height));
+        }
+
+        // A height with a blob that failed to be proven is retried by the next run.
+        warm_range(103..=103, &mut progress, |_| async { Ok((0, 1)) })
+            .await
+            .unwrap();
+        assert!(!progress.is_completed(103));
+
+        // The completed heights are skipped.
+        warm_range(100..=102, &mut progress, |height| async move {
+            panic!("height {height} is warmed again")
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_progress_is_resumed_from_the_data_dir() {
+        let dir = std::env::temp_dir().join(format!("hana-warm-progress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut progress = WarmProgress::load(Some(&dir));
+        assert!(!progress.is_completed(7));
+        progress.complete(7).unwrap();
+
+        let resumed = WarmProgress::load(Some(&dir));
+        assert!(resumed.is_completed(7));
+        assert!(!resumed.is_completed(8));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_progress_without_data_dir_lasts_for_the_run() {
+        let mut progress = WarmProgress::load(None);
+        progress.complete(7).unwrap();
+
+        assert!(progress.is_completed(7));
+        assert!(!WarmProgress::load(None).is_completed(7));
+    }
+}
@@ -2,7 +2,6 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
-use tracing as _;
 use tracing_subscriber as _;
 
 pub mod celestia;
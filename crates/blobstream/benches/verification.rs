@@ -0,0 +1,132 @@
+//! Benchmarks for the in-program Blobstream verification steps.
+//!
+//! The input is the payload recorded in `testdata/mainnet_payload.bin`, an [OraclePayload] served
+//! by the host for a mainnet blob, encoded with [OraclePayload::to_bytes]. The values it verifies
+//! against are in `testdata/mainnet_payload.json`:
+//!
+//! ```json
+//! { "height": 4000000, "l1_head": "0x..", "blobstream_address": "0x.." }
+//! ```
+//!
+//! The recording is produced by running the host with `--dump-payloads` against a mainnet blob.
+//! Until both files are in `testdata`, the benchmarks are skipped with a note naming the missing
+//! file. There is deliberately no fallback to a synthetic proof, whose single share and single
+//! leaf tries say little about the cost of a real proof.
+//!
+//! Run with `cargo bench -p hana-blobstream`.
+
+use alloy_primitives::{Address, B256};
+use criterion::{criterion_group, criterion_main, Criterion};
+use hana_blobstream::{
+    blobstream::{verify_data_commitment, verify_data_root_tuple, BlobstreamProof},
+    payload::OraclePayload,
+};
+use serde::Deserialize;
+use std::{
+    hint::black_box,
+    path::{Path, PathBuf},
+};
+
+/// The values a recorded payload verifies against.
+#[derive(Debug, Deserialize)]
+struct Recording {
+    height: u64,
+    l1_head: B256,
+    blobstream_address: Address,
+}
+
+/// A proof and the values it verifies against.
+struct Fixture {
+    proof: BlobstreamProof,
+    recording: Recording,
+}
+
+/// Loads the recorded mainnet payload, or returns the path of the first missing file.
+fn fixture() -> Result<Fixture, PathBuf> {
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+    let read = |name: &str| {
+        let path = testdata.join(name);
+        std::fs::read(&path).map_err(|_| path)
+    };
+
+    let payload = read("mainnet_payload.bin")?;
+    let recording = read("mainnet_payload.json")?;
+    Ok(Fixture {
+        proof: OraclePayload::from_bytes(&payload)
+            .expect("valid recorded payload")
+            .blobstream_proof,
+        recording: serde_json::from_slice(&recording).expect("valid recording"),
+    })
+}
+
+fn verification(c: &mut Criterion) {
+    let Fixture { proof, recording } = match fixture() {
+        Ok(fixture) => fixture,
+        Err(missing) => {
+            eprintln!(
+                "skipping the verification benchmarks, the recorded payload {} is missing, see the \
+                 benchmark docs",
+                missing.display()
+            );
+            return;
+        }
+    };
+    let proof = &proof;
+    // The block the storage proof is anchored at, the l1 head or an ancestor of it.
+    let anchor_hash = proof.block_header.hash_slow();
+
+    c.bench_function("verify_data_commitment", |b| {
+        b.iter(|| {
+            verify_data_commitment(
+                proof.storage_root,
+                &proof.storage_proof,
+                &proof.account_proof,
+                proof.proof_nonce,
+                proof.data_commitment,
+                recording.blobstream_address,
+                proof.blobstream_balance,
+                proof.blobstream_nonce,
+                proof.blobstream_code_hash,
+                &proof.block_header,
+                black_box(anchor_hash),
+            )
+            .unwrap()
+        })
+    });
+
+    c.bench_function("share_proof_verify", |b| {
+        b.iter(|| {
+            proof
+                .share_proof
+                .verify(black_box(proof.data_root))
+                .unwrap()
+        })
+    });
+
+    c.bench_function("data_root_tuple_proof_verify", |b| {
+        b.iter(|| {
+            verify_data_root_tuple(
+                black_box(recording.height),
+                &proof.data_root,
+                &proof.data_root_tuple_proof,
+                proof.data_commitment,
+            )
+            .unwrap()
+        })
+    });
+
+    c.bench_function("blobstream_proof_validate", |b| {
+        b.iter(|| {
+            proof
+                .validate(
+                    black_box(recording.height),
+                    recording.l1_head,
+                    recording.blobstream_address,
+                )
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, verification);
+criterion_main!(benches);
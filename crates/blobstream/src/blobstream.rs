@@ -1,43 +1,54 @@
-use std::boxed::Box;
-
-use alloc::vec::Vec;
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
 use alloy_chains::NamedChain;
 use alloy_consensus::Header;
 use alloy_primitives::{address, keccak256, Address, Bytes, FixedBytes, B256, U256};
+use alloy_rlp::Decodable;
 use alloy_sol_types::sol;
-use alloy_trie::{proof::verify_proof, Nibbles, TrieAccount};
+use alloy_trie::{nodes::TrieNode, proof::verify_proof, Nibbles, TrieAccount};
 use anyhow::{anyhow, Result};
 use celestia_types::{hash::Hash, MerkleProof, ShareProof};
 use serde::{Deserialize, Serialize};
 
 /////// Contract ///////
 
-sol! {
-    #[allow(missing_docs)]
-    #[sol(rpc)]
-    contract SP1Blobstream {
-        bool public frozen;
-        uint64 public latestBlock;
-        uint256 public state_proofNonce;
-        mapping(uint64 => bytes32) public blockHeightToHeaderHash;
-        mapping(uint256 => bytes32) public state_dataCommitments;
-        uint64 public constant DATA_COMMITMENT_MAX = 10000;
-        bytes32 public blobstreamProgramVkey;
-        address public verifier;
-
-        event DataCommitmentStored(
-            uint256 proofNonce,
-            uint64 indexed startBlock,
-            uint64 indexed endBlock,
-            bytes32 indexed dataCommitment
-        );
+/// Declares the SP1Blobstream bindings with the given `sol` attributes, so that the RPC bindings
+/// are only generated with the `rpc` feature.
+macro_rules! sp1_blobstream {
+    ($($attr:tt)*) => {
+        sol! {
+            #[allow(missing_docs)]
+            $($attr)*
+            contract SP1Blobstream {
+                bool public frozen;
+                uint64 public latestBlock;
+                uint256 public state_proofNonce;
+                mapping(uint64 => bytes32) public blockHeightToHeaderHash;
+                mapping(uint256 => bytes32) public state_dataCommitments;
+                uint64 public constant DATA_COMMITMENT_MAX = 10000;
+                bytes32 public blobstreamProgramVkey;
+                address public verifier;
 
-        function commitHeaderRange(bytes calldata proof, bytes calldata publicValues) external;
-    }
+                event DataCommitmentStored(
+                    uint256 proofNonce,
+                    uint64 indexed startBlock,
+                    uint64 indexed endBlock,
+                    bytes32 indexed dataCommitment
+                );
+
+                function commitHeaderRange(bytes calldata proof, bytes calldata publicValues) external;
+            }
+        }
+    };
 }
 
+#[cfg(feature = "rpc")]
+sp1_blobstream!(#[sol(rpc)]);
+
+#[cfg(not(feature = "rpc"))]
+sp1_blobstream!();
+
 /// Represents the stored data commitment event from Blobstream
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SP1BlobstreamDataCommitmentStored {
     pub proof_nonce: U256,
     pub start_block: u64,
@@ -45,8 +56,25 @@ pub struct SP1BlobstreamDataCommitmentStored {
     pub data_commitment: B256,
 }
 
-impl std::fmt::Display for SP1BlobstreamDataCommitmentStored {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl SP1BlobstreamDataCommitmentStored {
+    /// Returns `true` if the commitment covers a non-empty range of at most
+    /// [DATA_COMMITMENT_MAX] Celestia blocks, as enforced by the contract.
+    ///
+    /// Events failing this check were not emitted by Blobstream and must not be trusted.
+    pub const fn has_valid_range(&self) -> bool {
+        self.start_block < self.end_block
+            && self.end_block - self.start_block <= DATA_COMMITMENT_MAX
+    }
+
+    /// Returns `true` if the commitment covers the Celestia block `height`. The range is
+    /// half-open: `end_block` is covered by the next commitment.
+    pub const fn covers(&self, height: u64) -> bool {
+        self.start_block <= height && height < self.end_block
+    }
+}
+
+impl core::fmt::Display for SP1BlobstreamDataCommitmentStored {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "SP1BlobstreamDataCommitmentStored {{ proof_nonce: {}, start_block: {}, end_block: {}, data_commitment: {} }}",
             self.proof_nonce, self.start_block, self.end_block, self.data_commitment)
     }
@@ -54,9 +82,15 @@ impl std::fmt::Display for SP1BlobstreamDataCommitmentStored {
 
 pub const DATA_COMMITMENTS_SLOT: u32 = 254;
 
+/// The maximum number of Celestia blocks a single data commitment covers, the contract's
+/// `DATA_COMMITMENT_MAX`.
+pub const DATA_COMMITMENT_MAX: u64 = 10000;
+
 /// A structure containing a Celestia Blob and its corresponding proofs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlobstreamProof {
+    /// The Blobstream address the proof was generated for
+    pub blobstream_address: Address,
     /// The data root to verify the proof against
     pub data_root: Hash,
     /// The data commitment from Blobstream to verify against
@@ -81,11 +115,15 @@ pub struct BlobstreamProof {
     pub blobstream_code_hash: B256,
     /// The block header to verify against the l1 head
     pub block_header: Header,
+    /// The headers linking the l1 head to `block_header`, newest first. Empty when the proof is
+    /// anchored at the l1 head itself.
+    pub header_chain: Vec<Header>,
 }
 
 impl BlobstreamProof {
     /// Create a new OraclePayload instance
     pub fn new(
+        blobstream_address: Address,
         data_root: Hash,
         data_commitment: FixedBytes<32>,
         data_root_tuple_proof: MerkleProof,
@@ -98,8 +136,10 @@ impl BlobstreamProof {
         blobstream_nonce: u64,
         blobstream_code_hash: B256,
         block_header: Header,
+        header_chain: Vec<Header>,
     ) -> Self {
         Self {
+            blobstream_address,
             data_root,
             data_commitment,
             data_root_tuple_proof,
@@ -112,23 +152,544 @@ impl BlobstreamProof {
             blobstream_nonce,
             blobstream_code_hash,
             block_header,
+            header_chain,
         }
     }
 
     /// Serialize the struct to bytes using serde with a binary format
-    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn core::error::Error>> {
         let bytes = bincode::serialize(self)?;
         Ok(bytes)
     }
 
     /// Deserialize from bytes back into the struct
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn core::error::Error>> {
         let deserialized = bincode::deserialize(bytes)?;
         Ok(deserialized)
     }
+
+    /// Returns the size of the proof once serialized with [BlobstreamProof::to_bytes], without
+    /// serializing it.
+    pub fn estimated_serialized_size(&self) -> Result<usize> {
+        Ok(bincode::serialized_size(self)? as usize)
+    }
+
+    /// Cheaply checks that the proof is well formed before any trie verification, to reject
+    /// malformed or truncated payloads with a precise error.
+    pub fn check_invariants(&self) -> Result<()> {
+        self.check_storage_invariants()?;
+        if self.account_proof.is_empty() {
+            return Err(anyhow!("Account proof is empty"));
+        }
+        if self.block_header.state_root.is_zero() {
+            return Err(anyhow!("Block header state root is zero"));
+        }
+
+        Ok(())
+    }
+
+    /// The checks of [BlobstreamProof::check_invariants] on the fields proving the data
+    /// commitment against the storage root, the only ones [BlobstreamProof::validate_anchored]
+    /// and [BlobstreamProof::validate_at_state_root] rely on.
+    fn check_storage_invariants(&self) -> Result<()> {
+        if self.storage_proof.is_empty() {
+            return Err(anyhow!("Storage proof is empty"));
+        }
+        // Blobstream proof nonces start at 1.
+        if self.proof_nonce.is_zero() {
+            return Err(anyhow!("Proof nonce is zero"));
+        }
+        // Blobstream never stores a zero commitment for a relayed range, a zero commitment can
+        // only stand for an empty slot.
+        if self.data_commitment.is_zero() {
+            return Err(anyhow!("Data commitment is zero"));
+        }
+        if self.storage_root.is_zero() {
+            return Err(anyhow!("Storage root is zero"));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the Blobstream account runs the code hashing to `expected_code_hash`, e.g. to
+    /// reject proofs against an unexpectedly upgraded contract. The code hash is proven by the
+    /// account proof checked in [BlobstreamProof::validate].
+    ///
+    /// Security Note: `expected_code_hash` must come from a secure source, like the l1 head.
+    pub fn check_code_hash(&self, expected_code_hash: B256) -> Result<()> {
+        if self.blobstream_code_hash != expected_code_hash {
+            return Err(at_stage(VerificationStage::AccountProof)(anyhow!(
+                "Blobstream code hash {} does not match the expected code hash {}",
+                self.blobstream_code_hash,
+                expected_code_hash
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the storage and account proofs and the header chain stay within `limits`.
+    ///
+    /// State proofs have a bounded practical depth, so exceeding the limits indicates a malicious
+    /// or buggy provider.
+    pub fn check_limits(&self, limits: &ProofLimits) -> Result<()> {
+        if self.header_chain.len() > limits.max_header_chain {
+            return Err(anyhow!(
+                "Header chain has {} headers, the limit is {}",
+                self.header_chain.len(),
+                limits.max_header_chain
+            ));
+        }
+
+        for (name, nodes) in [
+            ("storage", &self.storage_proof),
+            ("account", &self.account_proof),
+        ] {
+            if nodes.len() > limits.max_nodes {
+                return Err(anyhow!(
+                    "{} proof has {} nodes, the limit is {}",
+                    name,
+                    nodes.len(),
+                    limits.max_nodes
+                ));
+            }
+            if let Some(node) = nodes.iter().find(|node| node.len() > limits.max_node_size) {
+                return Err(anyhow!(
+                    "{} proof has a node of {} bytes, the limit is {}",
+                    name,
+                    node.len(),
+                    limits.max_node_size
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs every check needed to trust the blob behind this proof for the given Celestia height:
+    ///
+    /// 1. The data commitment is stored in Blobstream at the L1 block committed to by `l1_head`, or
+    ///    at an ancestor of it linked by the header chain.
+    /// 2. The share proof is valid against the data root.
+    /// 3. The data root tuple for `height` is included in the data commitment.
+    ///
+    /// Security Note: `l1_head` and `expected_blobstream_address` must come from a secure source.
+    pub fn validate(
+        &self,
+        height: u64,
+        l1_head: B256,
+        expected_blobstream_address: Address,
+    ) -> Result<()> {
+        if self.blobstream_address != expected_blobstream_address {
+            return Err(anyhow!(
+                "Proof was generated for Blobstream at {}, expected {}",
+                self.blobstream_address,
+                expected_blobstream_address
+            ));
+        }
+
+        self.check_invariants()?;
+
+        // The storage proof may be anchored at an ancestor of the l1 head.
+        let anchor_hash = verify_header_chain(&self.header_chain, l1_head)
+            .map_err(at_stage(VerificationStage::BlockHash))?;
+        verify_block_hash(&self.block_header, anchor_hash)
+            .map_err(at_stage(VerificationStage::BlockHash))?;
+
+        // The same checks as verify_data_commitment, run one by one to tag the failing stage.
+        verify_account_fields(
+            self.block_header.state_root,
+            self.storage_root,
+            &self.account_proof,
+            expected_blobstream_address,
+            self.blobstream_balance,
+            self.blobstream_nonce,
+            self.blobstream_code_hash,
+        )
+        .map_err(at_stage(VerificationStage::AccountProof))?;
+        verify_commitment_in_storage(
+            self.storage_root,
+            &self.storage_proof,
+            self.proof_nonce,
+            self.data_commitment,
+        )
+        .map_err(at_stage(VerificationStage::StorageProof))?;
+
+        self.verify_blob_inclusion(height)
+    }
+
+    /// Same as [BlobstreamProof::validate], for setups where the Blobstream storage root at the
+    /// l1 head was verified against L1 out of band: the header chain and the account proof are
+    /// not checked, the data commitment is only proven against `anchored_storage_root`.
+    ///
+    /// Security Note: `anchored_storage_root` must come from a secure source that committed to
+    /// it, e.g. a public input of the proof, as it replaces every check linking the proof to L1.
+    pub fn validate_anchored(
+        &self,
+        height: u64,
+        anchored_storage_root: B256,
+        expected_blobstream_address: Address,
+    ) -> Result<()> {
+        if self.blobstream_address != expected_blobstream_address {
+            return Err(anyhow!(
+                "Proof was generated for Blobstream at {}, expected {}",
+                self.blobstream_address,
+                expected_blobstream_address
+            ));
+        }
+
+        // The account proof and the block header aren't checked, they may be left empty.
+        self.check_storage_invariants()?;
+
+        if self.storage_root != anchored_storage_root {
+            return Err(at_stage(VerificationStage::StorageProof)(anyhow!(
+                "Storage root {} does not match the anchored storage root {}",
+                self.storage_root,
+                anchored_storage_root
+            )));
+        }
+
+        verify_commitment_in_storage(
+            anchored_storage_root,
+            &self.storage_proof,
+            self.proof_nonce,
+            self.data_commitment,
+        )
+        .map_err(at_stage(VerificationStage::StorageProof))?;
+
+        self.verify_blob_inclusion(height)
+    }
+
+    /// Same as [BlobstreamProof::validate], for setups where the state root of the L1 block the
+    /// proof is anchored at was established out of band, e.g. from a beacon verified header: the
+    /// header chain and the block header are not checked, the account proof is verified against
+    /// `state_root`.
+    ///
+    /// Security Note: the caller is responsible for the provenance of `state_root`, it must come
+    /// from a secure source as it replaces every check linking the proof to the l1 head.
+    pub fn validate_at_state_root(
+        &self,
+        height: u64,
+        state_root: B256,
+        expected_blobstream_address: Address,
+    ) -> Result<()> {
+        if self.blobstream_address != expected_blobstream_address {
+            return Err(anyhow!(
+                "Proof was generated for Blobstream at {}, expected {}",
+                self.blobstream_address,
+                expected_blobstream_address
+            ));
+        }
+
+        // The block header isn't used, so its state root may be left unset.
+        self.check_storage_invariants()?;
+
+        verify_account_fields(
+            state_root,
+            self.storage_root,
+            &self.account_proof,
+            expected_blobstream_address,
+            self.blobstream_balance,
+            self.blobstream_nonce,
+            self.blobstream_code_hash,
+        )
+        .map_err(at_stage(VerificationStage::AccountProof))?;
+        verify_commitment_in_storage(
+            self.storage_root,
+            &self.storage_proof,
+            self.proof_nonce,
+            self.data_commitment,
+        )
+        .map_err(at_stage(VerificationStage::StorageProof))?;
+
+        self.verify_blob_inclusion(height)
+    }
+
+    /// Verifies the share proof against the data root and the data root tuple for `height`
+    /// against the data commitment.
+    fn verify_blob_inclusion(&self, height: u64) -> Result<()> {
+        check_share_proof_rows(&self.share_proof)
+            .map_err(at_stage(VerificationStage::ShareProof))?;
+        self.share_proof
+            .verify(self.data_root)
+            .map_err(|e| anyhow!("Share proof verification failed: {}", e))
+            .map_err(at_stage(VerificationStage::ShareProof))?;
+
+        verify_data_root_tuple(
+            height,
+            &self.data_root,
+            &self.data_root_tuple_proof,
+            self.data_commitment,
+        )
+        .map_err(at_stage(VerificationStage::DataRootTuple))
+    }
+
+    /// Recomputes the chain of values linking `height` to the stored data commitment, for
+    /// displaying what a verified proof proves. No proof is checked here, see
+    /// [BlobstreamProof::validate]. Fails if the data root is empty.
+    pub fn derivation_chain(&self, height: u64) -> Result<DerivationChain> {
+        Ok(DerivationChain {
+            height,
+            data_root: self.data_root,
+            data_root_tuple: encode_data_root_tuple(height, &self.data_root)?,
+            data_commitment: self.data_commitment,
+            proof_nonce: self.proof_nonce,
+            storage_slot: calculate_mapping_slot(DATA_COMMITMENTS_SLOT, self.proof_nonce),
+        })
+    }
+}
+
+/// The check of [BlobstreamProof::validate] a proof failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VerificationStage {
+    /// The anchor block header or the header chain doesn't hash to the l1 head.
+    BlockHash,
+    /// The Blobstream account proof doesn't match the block's state root.
+    AccountProof,
+    /// The data commitment isn't proven by the storage proof.
+    StorageProof,
+    /// The blob's shares aren't proven to be in the data root.
+    ShareProof,
+    /// The data root tuple isn't proven to be in the data commitment.
+    DataRootTuple,
+}
+
+impl VerificationStage {
+    /// Every stage, in the order the checks run.
+    pub const ALL: [Self; 5] = [
+        Self::BlockHash,
+        Self::AccountProof,
+        Self::StorageProof,
+        Self::ShareProof,
+        Self::DataRootTuple,
+    ];
+
+    /// Returns the name of the stage.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::BlockHash => "block_hash",
+            Self::AccountProof => "account_proof",
+            Self::StorageProof => "storage_proof",
+            Self::ShareProof => "share_proof",
+            Self::DataRootTuple => "data_root_tuple",
+        }
+    }
+
+    /// Returns the stage `err`, returned by [BlobstreamProof::validate], failed at. `None` if
+    /// the proof was rejected before any stage ran, e.g. for the wrong Blobstream address.
+    pub fn of(err: &anyhow::Error) -> Option<Self> {
+        err.downcast_ref::<StageError>().map(|err| err.stage)
+    }
+}
+
+impl core::fmt::Display for VerificationStage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error of [BlobstreamProof::validate] tagged with the stage that failed. Displayed as the
+/// underlying error.
+#[derive(Debug)]
+struct StageError {
+    stage: VerificationStage,
+    source: anyhow::Error,
+}
+
+impl core::fmt::Display for StageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&self.source, f)
+    }
+}
+
+impl core::error::Error for StageError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.source.source()
+    }
+}
+
+/// Checks that `share_proof` proves one contiguous range of shares: one namespace proof per row
+/// root, every row after the first proven from its first share, every row before the last proven
+/// up to the same end, and exactly the shares of the proven ranges.
+///
+/// The row roots are proven against the data root by [ShareProof::verify]. Binding the range to
+/// the index of the blob in the data square needs the data availability header, and is done when
+/// generating the proof.
+pub fn check_share_proof_rows(share_proof: &ShareProof) -> Result<()> {
+    let share_proofs = share_proof.share_proofs();
+    let row_roots = share_proof.row_proof().row_roots();
+    if share_proofs.is_empty() || share_proofs.len() != row_roots.len() {
+        return Err(anyhow!(
+            "Share proof has {} namespace proofs for {} rows",
+            share_proofs.len(),
+            row_roots.len()
+        ));
+    }
+
+    if share_proofs
+        .iter()
+        .skip(1)
+        .any(|proof| proof.start_idx() != 0)
+    {
+        return Err(anyhow!(
+            "Share proof leaves a gap at the start of a row after the first"
+        ));
+    }
+    if let Some((last, rows)) = share_proofs.split_last() {
+        let row_end = rows.first().map(|proof| proof.end_idx());
+        if rows.iter().any(|proof| Some(proof.end_idx()) != row_end)
+            || row_end.is_some_and(|end| last.end_idx() > end)
+        {
+            return Err(anyhow!(
+                "Share proof leaves a gap at the end of a row before the last"
+            ));
+        }
+    }
+
+    let proven: usize = share_proofs
+        .iter()
+        .map(|proof| proof.end_idx().saturating_sub(proof.start_idx()) as usize)
+        .sum();
+    if proven != share_proof.shares().len() {
+        return Err(anyhow!(
+            "Share proof has {} shares for {} proven shares",
+            share_proof.shares().len(),
+            proven
+        ));
+    }
+
+    Ok(())
+}
+
+/// Tags an error with the stage it occurred at, see [VerificationStage::of].
+pub(crate) fn at_stage(stage: VerificationStage) -> impl FnOnce(anyhow::Error) -> anyhow::Error {
+    move |source| anyhow::Error::new(StageError { stage, source })
 }
 
-pub fn encode_data_root_tuple(height: u64, data_root: &Hash) -> Vec<u8> {
+/// The values a [BlobstreamProof] links together, from the Celestia height down to the
+/// Blobstream storage slot holding the data commitment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationChain {
+    /// The Celestia height of the blob.
+    pub height: u64,
+    /// The data root of the Celestia block at `height`.
+    pub data_root: Hash,
+    /// The data root tuple included in the data commitment.
+    pub data_root_tuple: Vec<u8>,
+    /// The data commitment stored in Blobstream.
+    pub data_commitment: FixedBytes<32>,
+    /// The proof nonce the data commitment is stored under.
+    pub proof_nonce: U256,
+    /// The storage slot of the data commitment in Blobstream.
+    pub storage_slot: B256,
+}
+
+impl core::fmt::Display for DerivationChain {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "height {} -> data_root {} -> tuple 0x{} -> commitment {} at nonce {} in slot {}",
+            self.height,
+            self.data_root,
+            alloy_primitives::hex::encode(&self.data_root_tuple),
+            self.data_commitment,
+            self.proof_nonce,
+            self.storage_slot
+        )
+    }
+}
+
+/// Limits on the size of the storage and account proofs and of the header chain in a
+/// [BlobstreamProof], see [BlobstreamProof::check_limits].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofLimits {
+    /// The maximum number of nodes in each proof.
+    pub max_nodes: usize,
+    /// The maximum size of a single proof node, in bytes.
+    pub max_node_size: usize,
+    /// The maximum number of headers linking the anchor block to the l1 head.
+    pub max_header_chain: usize,
+}
+
+impl Default for ProofLimits {
+    fn default() -> Self {
+        // A trie path is at most 64 nibbles long, and no node exceeds a full branch node. Full
+        // nodes keep the state of the last 128 blocks, anchoring further back is never needed.
+        Self {
+            max_nodes: 64,
+            max_node_size: 2 * MAX_TRIE_NODE_SIZE,
+            max_header_chain: 256,
+        }
+    }
+}
+
+/// The size of a Celestia share.
+const SHARE_SIZE: usize = 512;
+
+/// Upper bound on the encoded size of a Merkle Patricia trie node (a full branch node).
+const MAX_TRIE_NODE_SIZE: usize = 532;
+
+/// Rough size of the parts of a [BlobstreamProof] that don't scale with the inputs of
+/// [estimate_blobstream_proof_size]: the block header, the data root tuple proof and the fixed
+/// size fields.
+const BASE_PROOF_SIZE: usize = 2048;
+
+/// Rough per-share cost of the namespace and row proofs included in a share proof.
+const SHARE_PROOF_OVERHEAD: usize = 128;
+
+/// Rough size of a serialized L1 block header.
+const HEADER_SIZE: usize = 700;
+
+/// Estimates the serialized size of a [BlobstreamProof] before it is generated, from the number
+/// of trie nodes in the storage and account proofs, the number of shares in the blob and the
+/// number of headers linking the anchor block to the l1 head.
+///
+/// The estimate assumes every trie node is a full branch node, so it errs on the high side.
+pub fn estimate_blobstream_proof_size(
+    storage_proof_nodes: usize,
+    account_proof_nodes: usize,
+    share_count: usize,
+    header_chain_len: usize,
+) -> usize {
+    // Every vector element is prefixed with its 8 byte length.
+    let trie_nodes = (storage_proof_nodes + account_proof_nodes) * (MAX_TRIE_NODE_SIZE + 8);
+    let shares = share_count * (SHARE_SIZE + SHARE_PROOF_OVERHEAD);
+    let headers = header_chain_len * HEADER_SIZE;
+
+    BASE_PROOF_SIZE + trie_nodes + shares + headers
+}
+
+/// Verifies that the `(height, data_root)` tuple is included in `data_commitment`.
+///
+/// Security Note: This function assumes the data_commitment comes from a secure source, see
+/// [verify_data_commitment].
+pub fn verify_data_root_tuple(
+    height: u64,
+    data_root: &Hash,
+    proof: &MerkleProof,
+    data_commitment: B256,
+) -> Result<()> {
+    proof
+        .verify(
+            encode_data_root_tuple(height, data_root)?,
+            data_commitment.0,
+        )
+        .map_err(|e| anyhow!("Data root tuple verification failed: {}", e))
+}
+
+/// Encodes the `(height, data_root)` tuple committed to by Blobstream data commitments: the
+/// height as a 32 byte big-endian word followed by the 32 byte data root.
+///
+/// The height is big-endian as in the ABI encoding used on-chain, unlike the little-endian height
+/// of the blob pointers and oracle hints.
+///
+/// The data root must be a SHA-256 hash, the encoding is then always 64 bytes long. An empty
+/// [Hash::None] root is rejected, no data commitment includes it.
+pub fn encode_data_root_tuple(height: u64, data_root: &Hash) -> Result<Vec<u8>> {
+    let Hash::Sha256(data_root) = data_root else {
+        return Err(anyhow!("Data root is empty, expected a 32 byte hash"));
+    };
+
     // Create the result vector with 64 bytes capacity
     let mut result = Vec::with_capacity(64);
 
@@ -142,9 +703,9 @@ pub fn encode_data_root_tuple(height: u64, data_root: &Hash) -> Vec<u8> {
     result.extend_from_slice(&height_bytes);
 
     // Add the 32-byte data root
-    result.extend_from_slice(data_root.as_bytes());
+    result.extend_from_slice(data_root);
 
-    result
+    Ok(result)
 }
 
 /// Verifies that a data commitment exists in the Ethereum state at the specified L1 block.
@@ -165,101 +726,1368 @@ pub fn encode_data_root_tuple(height: u64, data_root: &Hash) -> Vec<u8> {
 /// Security Note: This function assumes the l1_block_hash and expected_blobsstream_address come from a secure source.
 pub fn verify_data_commitment(
     storage_root: B256,
-    storage_proof: Vec<Bytes>,
-    account_proof: Vec<Bytes>,
+    storage_proof: &[Bytes],
+    account_proof: &[Bytes],
     commitment_nonce: U256,
     expected_commitment: B256,
     expected_blobstream_address: Address,
     blobstream_balance: U256,
     blobstream_nonce: u64,
     blobstream_code_hash: B256,
-    block_header: Header,
+    block_header: &Header,
+    l1_block_hash: B256,
+) -> Result<()> {
+    if expected_commitment.is_zero() {
+        return Err(anyhow!(
+            "Data commitment is zero, zero slots are absent from the trie"
+        ));
+    }
+
+    verify_blobstream_account(
+        storage_root,
+        account_proof,
+        expected_blobstream_address,
+        blobstream_balance,
+        blobstream_nonce,
+        blobstream_code_hash,
+        block_header,
+        l1_block_hash,
+    )?;
+
+    verify_commitment_in_storage(
+        storage_root,
+        storage_proof,
+        commitment_nonce,
+        expected_commitment,
+    )
+}
+
+/// Same as [verify_data_commitment], verifying the account proof against a `state_root`
+/// established out of band instead of the state root of a block header checked against the l1
+/// block hash.
+///
+/// Security Note: the caller is responsible for the provenance of `state_root`, it must come from
+/// a secure source, e.g. a beacon verified header, as no header hash is checked.
+pub fn verify_data_commitment_at_state_root(
+    state_root: B256,
+    storage_root: B256,
+    storage_proof: &[Bytes],
+    account_proof: &[Bytes],
+    commitment_nonce: U256,
+    expected_commitment: B256,
+    expected_blobstream_address: Address,
+    blobstream_balance: U256,
+    blobstream_nonce: u64,
+    blobstream_code_hash: B256,
+) -> Result<()> {
+    if expected_commitment.is_zero() {
+        return Err(anyhow!(
+            "Data commitment is zero, zero slots are absent from the trie"
+        ));
+    }
+
+    verify_account_fields(
+        state_root,
+        storage_root,
+        account_proof,
+        expected_blobstream_address,
+        blobstream_balance,
+        blobstream_nonce,
+        blobstream_code_hash,
+    )?;
+
+    verify_commitment_in_storage(
+        storage_root,
+        storage_proof,
+        commitment_nonce,
+        expected_commitment,
+    )
+}
+
+/// The storage proof of a single data commitment stored in Blobstream.
+#[derive(Debug, Clone, Copy)]
+pub struct CommitmentStorageProof<'a> {
+    /// The proof nonce the data commitment is stored under.
+    pub proof_nonce: U256,
+    /// The expected data commitment.
+    pub data_commitment: B256,
+    /// The storage proof for the state_dataCommitments mapping slot.
+    pub storage_proof: &'a [Bytes],
+}
+
+/// Verifies that several data commitments exist in the Ethereum state at the same L1 block.
+///
+/// Equivalent to calling [verify_data_commitment] for every commitment, but the block hash and
+/// the Blobstream account proof are only verified once, since they are shared by all of them.
+///
+/// Security Note: This function assumes the l1_block_hash and expected_blobsstream_address come from a secure source.
+pub fn verify_data_commitments_batch(
+    storage_root: B256,
+    commitments: &[CommitmentStorageProof<'_>],
+    account_proof: &[Bytes],
+    expected_blobstream_address: Address,
+    blobstream_balance: U256,
+    blobstream_nonce: u64,
+    blobstream_code_hash: B256,
+    block_header: &Header,
+    l1_block_hash: B256,
+) -> Result<()> {
+    if commitments
+        .iter()
+        .any(|commitment| commitment.data_commitment.is_zero())
+    {
+        return Err(anyhow!(
+            "Data commitment is zero, zero slots are absent from the trie"
+        ));
+    }
+
+    verify_blobstream_account(
+        storage_root,
+        account_proof,
+        expected_blobstream_address,
+        blobstream_balance,
+        blobstream_nonce,
+        blobstream_code_hash,
+        block_header,
+        l1_block_hash,
+    )?;
+
+    for commitment in commitments {
+        verify_commitment_in_storage(
+            storage_root,
+            commitment.storage_proof,
+            commitment.proof_nonce,
+            commitment.data_commitment,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Verifies that `block_header` is the l1 block and that the Blobstream account in its state
+/// has the given storage root, balance, nonce and code hash.
+///
+/// The account is decoded from the proven leaf and compared field by field, so a storage root
+/// that isn't the one bound by the account proof is reported as such.
+fn verify_blobstream_account(
+    storage_root: B256,
+    account_proof: &[Bytes],
+    expected_blobstream_address: Address,
+    blobstream_balance: U256,
+    blobstream_nonce: u64,
+    blobstream_code_hash: B256,
+    block_header: &Header,
     l1_block_hash: B256,
 ) -> Result<()> {
     // Verify the block header hash matches the l1 head.
-    let block_hash = block_header.hash_slow();
-    assert!(
-        block_hash == l1_block_hash,
-        "computed block hash must match host l1 head"
-    );
+    verify_block_hash(block_header, l1_block_hash)?;
 
-    let account = TrieAccount {
-        nonce: blobstream_nonce,
-        balance: blobstream_balance,
-        code_hash: blobstream_code_hash,
+    verify_account_fields(
+        block_header.state_root,
         storage_root,
-    };
+        account_proof,
+        expected_blobstream_address,
+        blobstream_balance,
+        blobstream_nonce,
+        blobstream_code_hash,
+    )
+}
 
-    let blobstream_address_nibbles = Nibbles::unpack(keccak256(expected_blobstream_address));
+/// Verifies that `block_header` hashes to `l1_block_hash`, naming the diverging header field
+/// when it can be identified, see [diagnose_header_hash].
+fn verify_block_hash(block_header: &Header, l1_block_hash: B256) -> Result<()> {
+    if block_header.hash_slow() == l1_block_hash {
+        return Ok(());
+    }
+
+    match diagnose_header_hash(block_header, l1_block_hash) {
+        Some(field) => Err(anyhow!(
+            "computed block hash must match host l1 head, the header has an unexpected {field}"
+        )),
+        None => Err(anyhow!("computed block hash must match host l1 head")),
+    }
+}
+
+/// Verifies that the Blobstream account proven against `state_root` has the given storage root,
+/// balance, nonce and code hash.
+fn verify_account_fields(
+    state_root: B256,
+    storage_root: B256,
+    account_proof: &[Bytes],
+    expected_blobstream_address: Address,
+    blobstream_balance: U256,
+    blobstream_nonce: u64,
+    blobstream_code_hash: B256,
+) -> Result<()> {
+    let account = proven_account(state_root, expected_blobstream_address, account_proof)?;
+
+    if account.storage_root != storage_root {
+        return Err(anyhow!(
+            "Storage root {} does not match the proven account's {}",
+            storage_root,
+            account.storage_root
+        ));
+    }
+    if account.balance != blobstream_balance
+        || account.nonce != blobstream_nonce
+        || account.code_hash != blobstream_code_hash
+    {
+        return Err(anyhow!(
+            "Blobstream balance, nonce or code hash does not match the proven account"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the account of `address` proven by `account_proof` against `state_root`.
+fn proven_account(
+    state_root: B256,
+    address: Address,
+    account_proof: &[Bytes],
+) -> Result<TrieAccount> {
+    // The account is the value of the leaf terminating the proof.
+    let leaf = account_proof
+        .last()
+        .ok_or_else(|| anyhow!("Account proof is empty"))?;
+    let value = match TrieNode::decode(&mut leaf.as_ref()) {
+        Ok(TrieNode::Leaf(leaf)) => leaf.value,
+        _ => return Err(anyhow!("Account proof does not end in a leaf")),
+    };
 
     verify_proof(
-        block_header.state_root,
-        blobstream_address_nibbles,
-        Some(alloy_rlp::encode(account)),
-        &account_proof,
+        state_root,
+        Nibbles::unpack(keccak256(address)),
+        Some(value.clone()),
+        account_proof,
     )
     .map_err(|e| anyhow!("Account proof verification failed: {}", e))?;
 
+    TrieAccount::decode(&mut value.as_slice())
+        .map_err(|e| anyhow!("Invalid account in account proof: {}", e))
+}
+
+/// Verifies that `expected_commitment` is stored under `commitment_nonce` in the
+/// state_dataCommitments mapping of the Blobstream storage trie.
+///
+/// Only the storage slot inclusion is checked, see [verify_data_commitment] to also prove the
+/// storage root against an L1 block. A zero commitment is rejected, as an exclusion proof of the
+/// empty slot would otherwise prove it.
+///
+/// Security Note: This function assumes the storage_root comes from a secure source.
+pub fn verify_commitment_in_storage<'a, I>(
+    storage_root: B256,
+    storage_proof: I,
+    commitment_nonce: U256,
+    expected_commitment: B256,
+) -> Result<()>
+where
+    I: IntoIterator<Item = &'a Bytes>,
+{
+    // Blobstream never stores a zero commitment for a relayed range, proving one would only prove
+    // the slot is empty.
+    let value = expected_storage_value(expected_commitment)
+        .ok_or_else(|| anyhow!("Data commitment is zero, zero slots are absent from the trie"))?;
+
     // Get the nibbles for the storage slot for state_dataCommitments[nonce]
     let data_commitment_slot_nibbles = Nibbles::unpack(keccak256(calculate_mapping_slot(
         DATA_COMMITMENTS_SLOT,
         commitment_nonce,
     )));
 
-    // Drop leading zeros before encoding
-    let commitment_bytes = expected_commitment.as_slice();
-    let canonical_commitment = match commitment_bytes.iter().position(|byte| *byte != 0) {
-        Some(idx) => &commitment_bytes[idx..],
-        None => &[],
-    };
-
-    // Use canonical RLP encoding
-    let expected_rlp = alloy_rlp::encode(canonical_commitment);
-
     // Verify storage proof with canonically encoded commitment
     verify_proof(
         storage_root,
         data_commitment_slot_nibbles,
-        Some(expected_rlp),
-        &storage_proof,
+        Some(value.into()),
+        storage_proof,
     )
-    .map_err(|e| anyhow!("Storage proof verification failed: {}", e))?;
+    .map_err(|e| anyhow!("Storage proof verification failed: {}", e))
+}
 
-    Ok(())
+/// Returns the value the Blobstream storage trie holds for a stored `bytes32` commitment: the
+/// canonical RLP encoding of the commitment with its leading zero bytes dropped.
+///
+/// Returns `None` for a zero commitment, as zero slots are absent from the storage trie.
+pub fn expected_storage_value(commitment: B256) -> Option<Bytes> {
+    let commitment_bytes = commitment.as_slice();
+    let idx = commitment_bytes.iter().position(|byte| *byte != 0)?;
+
+    Some(alloy_rlp::encode(&commitment_bytes[idx..]).into())
+}
+
+/// Verifies that `header_chain` links `l1_head` to one of its ancestors, returning the hash the
+/// ancestor block must have.
+///
+/// The chain is ordered newest first: the first header must hash to `l1_head`, and every header
+/// must be the parent of the one before it. The returned hash is the parent hash of the last
+/// header, or `l1_head` itself when the chain is empty.
+pub fn verify_header_chain(header_chain: &[Header], l1_head: B256) -> Result<B256> {
+    let mut expected_hash = l1_head;
+    for header in header_chain {
+        let hash = header.hash_slow();
+        if hash != expected_hash {
+            return Err(anyhow!(
+                "Header chain is broken at block {}: expected hash {}, got {}",
+                header.number,
+                expected_hash,
+                hash
+            ));
+        }
+        expected_hash = header.parent_hash;
+    }
+
+    Ok(expected_hash)
+}
+
+/// Returns the names of the fields that differ between two headers.
+///
+/// Useful when a header received over RPC doesn't hash to the expected block hash: diffing it
+/// against the consensus encoding of the same block pinpoints the field that diverged.
+pub fn header_field_diff(computed: &Header, expected: &Header) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    let mut check = |name: &'static str, differs: bool| {
+        if differs {
+            fields.push(name);
+        }
+    };
+
+    check("parent_hash", computed.parent_hash != expected.parent_hash);
+    check("ommers_hash", computed.ommers_hash != expected.ommers_hash);
+    check("beneficiary", computed.beneficiary != expected.beneficiary);
+    check("state_root", computed.state_root != expected.state_root);
+    check(
+        "transactions_root",
+        computed.transactions_root != expected.transactions_root,
+    );
+    check(
+        "receipts_root",
+        computed.receipts_root != expected.receipts_root,
+    );
+    check("logs_bloom", computed.logs_bloom != expected.logs_bloom);
+    check("difficulty", computed.difficulty != expected.difficulty);
+    check("number", computed.number != expected.number);
+    check("gas_limit", computed.gas_limit != expected.gas_limit);
+    check("gas_used", computed.gas_used != expected.gas_used);
+    check("timestamp", computed.timestamp != expected.timestamp);
+    check("extra_data", computed.extra_data != expected.extra_data);
+    check("mix_hash", computed.mix_hash != expected.mix_hash);
+    check("nonce", computed.nonce != expected.nonce);
+    check(
+        "base_fee_per_gas",
+        computed.base_fee_per_gas != expected.base_fee_per_gas,
+    );
+    check(
+        "withdrawals_root",
+        computed.withdrawals_root != expected.withdrawals_root,
+    );
+    check(
+        "blob_gas_used",
+        computed.blob_gas_used != expected.blob_gas_used,
+    );
+    check(
+        "excess_blob_gas",
+        computed.excess_blob_gas != expected.excess_blob_gas,
+    );
+    check(
+        "parent_beacon_block_root",
+        computed.parent_beacon_block_root != expected.parent_beacon_block_root,
+    );
+    check(
+        "requests_hash",
+        computed.requests_hash != expected.requests_hash,
+    );
+
+    fields
+}
+
+/// Looks for the fork field that makes `header` hash to something other than `expected_hash`.
+///
+/// Without the expected header, only one kind of divergence can be identified: an RPC header
+/// carrying optional fields the block doesn't have. The optional fields are cleared one by one,
+/// newest fork first, until the header hashes to `expected_hash`. The last field cleared is
+/// returned: the header shouldn't have it, nor any field of a newer fork.
+pub fn diagnose_header_hash(header: &Header, expected_hash: B256) -> Option<&'static str> {
+    let mut header = header.clone();
+    let clears: [(&'static str, fn(&mut Header) -> bool); 6] = [
+        ("requests_hash", |h| h.requests_hash.take().is_some()),
+        ("parent_beacon_block_root", |h| {
+            h.parent_beacon_block_root.take().is_some()
+        }),
+        ("excess_blob_gas", |h| h.excess_blob_gas.take().is_some()),
+        ("blob_gas_used", |h| h.blob_gas_used.take().is_some()),
+        ("withdrawals_root", |h| h.withdrawals_root.take().is_some()),
+        ("base_fee_per_gas", |h| h.base_fee_per_gas.take().is_some()),
+    ];
+
+    for (name, clear) in clears {
+        if clear(&mut header) && header.hash_slow() == expected_hash {
+            return Some(name);
+        }
+    }
+
+    None
 }
 
 /// Calculate the storage slot for a mapping with a uint256 key
 pub fn calculate_mapping_slot(mapping_slot: u32, key: U256) -> B256 {
-    let key_bytes = key.to_be_bytes::<32>();
+    mapping_slot_uint(mapping_slot, key)
+}
+
+/// Calculate the storage slot for a mapping with an unsigned integer key of any width.
+///
+/// Solidity left-pads `uintN` keys to 32 bytes, so e.g. the `uint64` keys of
+/// `blockHeightToHeaderHash` produce the same slot as the equal `uint256`.
+pub fn mapping_slot_uint(mapping_slot: u32, key: impl Into<U256>) -> B256 {
+    mapping_slot_for_word(mapping_slot, B256::from(key.into()))
+}
 
+/// Calculate the storage slot for a mapping with an `address` key.
+///
+/// The address is left-padded with zeros to 32 bytes.
+pub fn mapping_slot_address(mapping_slot: u32, key: Address) -> B256 {
+    mapping_slot_for_word(mapping_slot, key.into_word())
+}
+
+/// Calculate the storage slot for a mapping with a `bytes32` key.
+pub fn mapping_slot_bytes32(mapping_slot: u32, key: B256) -> B256 {
+    mapping_slot_for_word(mapping_slot, key)
+}
+
+/// Calculate `keccak256(key . slot)` for a key already encoded as a 32 byte word.
+fn mapping_slot_for_word(mapping_slot: u32, key: B256) -> B256 {
     let slot_bytes = U256::from(mapping_slot).to_be_bytes::<32>();
 
     let mut concatenated = [0u8; 64];
-    concatenated[0..32].copy_from_slice(&key_bytes);
+    concatenated[0..32].copy_from_slice(key.as_slice());
     concatenated[32..64].copy_from_slice(&slot_bytes);
 
     alloy_primitives::keccak256(concatenated)
 }
 
+/// The canonical Blobstream deployments, by L1 chain id.
+///
+/// Source: https://docs.celestia.org/how-to-guides/blobstream#deployed-contracts
+const CANONICAL_BLOBSTREAM_DEPLOYMENTS: [(u64, Address); 8] = [
+    (
+        NamedChain::Mainnet as u64,
+        address!("0x7Cf3876F681Dbb6EdA8f6FfC45D66B996Df08fAe"),
+    ),
+    (
+        NamedChain::Arbitrum as u64,
+        address!("0xA83ca7775Bc2889825BcDeDfFa5b758cf69e8794"),
+    ),
+    (
+        NamedChain::Base as u64,
+        address!("0xA83ca7775Bc2889825BcDeDfFa5b758cf69e8794"),
+    ),
+    (
+        NamedChain::Scroll as u64,
+        address!("0x5008fa5CC3397faEa90fcde71C35945db6822218"),
+    ),
+    (
+        NamedChain::Sepolia as u64,
+        address!("0xF0c6429ebAB2e7DC6e05DaFB61128bE21f13cb1e"),
+    ),
+    (
+        NamedChain::ArbitrumSepolia as u64,
+        address!("0xc3e209eb245Fd59c8586777b499d6A665DF3ABD2"),
+    ),
+    (
+        NamedChain::BaseSepolia as u64,
+        address!("0xc3e209eb245Fd59c8586777b499d6A665DF3ABD2"),
+    ),
+    (
+        NamedChain::Holesky as u64,
+        address!("0x315A044cb95e4d44bBf6253585FbEbcdB6fb41ef"),
+    ),
+];
+
+/// The chain ids of [CANONICAL_BLOBSTREAM_DEPLOYMENTS], in the same order.
+const CANONICAL_BLOBSTREAM_CHAIN_IDS: [u64; CANONICAL_BLOBSTREAM_DEPLOYMENTS.len()] = {
+    let mut chain_ids = [0; CANONICAL_BLOBSTREAM_DEPLOYMENTS.len()];
+    let mut i = 0;
+    while i < chain_ids.len() {
+        chain_ids[i] = CANONICAL_BLOBSTREAM_DEPLOYMENTS[i].0;
+        i += 1;
+    }
+    chain_ids
+};
+
 /// The canonical Blobstream address for the given chain id.
 ///
 /// Source: https://docs.celestia.org/how-to-guides/blobstream#deployed-contracts
 pub fn blobstream_address(chain_id: u64) -> Option<Address> {
-    if let Ok(chain) = NamedChain::try_from(chain_id) {
-        match chain {
-            NamedChain::Mainnet => Some(address!("0x7Cf3876F681Dbb6EdA8f6FfC45D66B996Df08fAe")),
-            NamedChain::Arbitrum => Some(address!("0xA83ca7775Bc2889825BcDeDfFa5b758cf69e8794")),
-            NamedChain::Base => Some(address!("0xA83ca7775Bc2889825BcDeDfFa5b758cf69e8794")),
-            NamedChain::Scroll => Some(address!("0x5008fa5CC3397faEa90fcde71C35945db6822218")),
-            NamedChain::Sepolia => Some(address!("0xF0c6429ebAB2e7DC6e05DaFB61128bE21f13cb1e")),
-            NamedChain::ArbitrumSepolia => {
-                Some(address!("0xc3e209eb245Fd59c8586777b499d6A665DF3ABD2"))
-            }
-            NamedChain::BaseSepolia => Some(address!("0xc3e209eb245Fd59c8586777b499d6A665DF3ABD2")),
-            NamedChain::Holesky => Some(address!("0x315A044cb95e4d44bBf6253585FbEbcdB6fb41ef")),
-            _ => None,
+    CANONICAL_BLOBSTREAM_DEPLOYMENTS
+        .iter()
+        .find(|(id, _)| *id == chain_id)
+        .map(|(_, address)| *address)
+}
+
+/// The L1 chain ids with a canonical Blobstream deployment, see [blobstream_address].
+///
+/// Chains without one can still be used by configuring their deployment, see
+/// [resolve_blobstream_address].
+pub fn supported_blobstream_chain_ids() -> &'static [u64] {
+    &CANONICAL_BLOBSTREAM_CHAIN_IDS
+}
+
+/// The canonical Blobstream deployments as `(chain id, address)` pairs, see
+/// [blobstream_address].
+pub fn supported_blobstream_chains() -> &'static [(u64, Address)] {
+    &CANONICAL_BLOBSTREAM_DEPLOYMENTS
+}
+
+/// Blobstream deployments by L1 chain id, for chains without a canonical deployment.
+pub type BlobstreamAddresses = BTreeMap<u64, Address>;
+
+/// The Blobstream address for the given chain id: its canonical deployment, see
+/// [blobstream_address], or its entry in `custom` for chains without one. A custom entry never
+/// replaces a canonical deployment, see [check_blobstream_addresses].
+///
+/// Security Note: In the client, `custom` must be committed to like the l1 head, as it decides
+/// which contract the proofs are verified against.
+pub fn resolve_blobstream_address(chain_id: u64, custom: &BlobstreamAddresses) -> Option<Address> {
+    blobstream_address(chain_id).or_else(|| custom.get(&chain_id).copied())
+}
+
+/// Ensures no entry of `custom` is for a chain with a canonical Blobstream deployment.
+pub fn check_blobstream_addresses(custom: &BlobstreamAddresses) -> Result<()> {
+    match custom
+        .iter()
+        .find(|(chain_id, _)| blobstream_address(**chain_id).is_some())
+    {
+        Some((chain_id, address)) => Err(anyhow!(
+            "custom Blobstream address {} can't replace the canonical one of chain id {}",
+            address,
+            chain_id
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The size of an entry of an encoded [BlobstreamAddresses] table: the chain id, 8 bytes
+/// big-endian, followed by the 20 byte address.
+const BLOBSTREAM_ADDRESS_ENTRY_SIZE: usize = 8 + 20;
+
+/// Encodes `addresses` as the concatenation of their entries in chain id order, see
+/// [decode_blobstream_addresses]. An empty table encodes to no bytes.
+pub fn encode_blobstream_addresses(addresses: &BlobstreamAddresses) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(addresses.len() * BLOBSTREAM_ADDRESS_ENTRY_SIZE);
+    for (chain_id, address) in addresses {
+        encoded.extend_from_slice(&chain_id.to_be_bytes());
+        encoded.extend_from_slice(address.as_slice());
+    }
+    encoded
+}
+
+/// Decodes a table encoded with [encode_blobstream_addresses], rejecting truncated entries and
+/// entries out of chain id order, so that a table has a single encoding, and entries for chains
+/// with a canonical deployment, see [check_blobstream_addresses].
+pub fn decode_blobstream_addresses(encoded: &[u8]) -> Result<BlobstreamAddresses> {
+    if encoded.len() % BLOBSTREAM_ADDRESS_ENTRY_SIZE != 0 {
+        return Err(anyhow!(
+            "Blobstream address table of {} bytes is not made of {} byte entries",
+            encoded.len(),
+            BLOBSTREAM_ADDRESS_ENTRY_SIZE
+        ));
+    }
+
+    let mut addresses = BlobstreamAddresses::new();
+    for entry in encoded.chunks_exact(BLOBSTREAM_ADDRESS_ENTRY_SIZE) {
+        let chain_id = u64::from_be_bytes(entry[..8].try_into().expect("8 byte chain id"));
+        if addresses
+            .last_key_value()
+            .is_some_and(|(last, _)| *last >= chain_id)
+        {
+            return Err(anyhow!(
+                "Blobstream address table is not sorted by chain id at chain {}",
+                chain_id
+            ));
+        }
+        addresses.insert(chain_id, Address::from_slice(&entry[8..]));
+    }
+    check_blobstream_addresses(&addresses)?;
+    Ok(addresses)
+}
+
+/// The Celestia networks relayed to Blobstream deployments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CelestiaNetwork {
+    /// Celestia mainnet.
+    Mainnet,
+    /// The Mocha testnet.
+    Mocha,
+    /// The Arabica devnet.
+    Arabica,
+}
+
+impl core::fmt::Display for CelestiaNetwork {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Mainnet => write!(f, "mainnet"),
+            Self::Mocha => write!(f, "mocha"),
+            Self::Arabica => write!(f, "arabica"),
+        }
+    }
+}
+
+impl core::str::FromStr for CelestiaNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        match s {
+            "mainnet" | "celestia" => Ok(Self::Mainnet),
+            "mocha" | "mocha-4" => Ok(Self::Mocha),
+            "arabica" | "arabica-11" => Ok(Self::Arabica),
+            _ => Err(format!("unknown celestia network {s}")),
+        }
+    }
+}
+
+/// Returns the Celestia network relayed by the canonical Blobstream deployment on the given
+/// chain, see [blobstream_address].
+pub fn blobstream_celestia_network(chain_id: u64) -> Option<CelestiaNetwork> {
+    match NamedChain::try_from(chain_id).ok()? {
+        NamedChain::Mainnet | NamedChain::Arbitrum | NamedChain::Base | NamedChain::Scroll => {
+            Some(CelestiaNetwork::Mainnet)
+        }
+        NamedChain::Sepolia
+        | NamedChain::ArbitrumSepolia
+        | NamedChain::BaseSepolia
+        | NamedChain::Holesky => Some(CelestiaNetwork::Mocha),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_check_code_hash() {
+        let proof = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100).proof;
+        proof.check_code_hash(keccak256(b"SP1Blobstream")).unwrap();
+
+        let err = proof.check_code_hash(B256::repeat_byte(1)).unwrap_err();
+        assert_eq!(
+            VerificationStage::of(&err),
+            Some(VerificationStage::AccountProof)
+        );
+    }
+
+    /// Returns the share proof of the synthetic proof with its JSON encoding edited by `edit`.
+    fn edited_share_proof(edit: impl FnOnce(&mut serde_json::Value)) -> ShareProof {
+        let proof = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100).proof;
+        let mut json = serde_json::to_value(&proof.share_proof).unwrap();
+        edit(&mut json);
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_check_share_proof_rows_accepts_a_contiguous_range() {
+        let proof = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100).proof;
+        check_share_proof_rows(&proof.share_proof).unwrap();
+    }
+
+    #[test]
+    fn test_check_share_proof_rows_rejects_missing_row_proofs() {
+        let share_proof = edited_share_proof(|json| {
+            let row = json["share_proofs"][0].clone();
+            json["share_proofs"].as_array_mut().unwrap().push(row);
+        });
+
+        let err = check_share_proof_rows(&share_proof).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof has 2 namespace proofs for 1 rows"
+        );
+    }
+
+    #[test]
+    fn test_check_share_proof_rows_rejects_gaps_between_rows() {
+        let share_proof = edited_share_proof(|json| {
+            let mut row = json["share_proofs"][0].clone();
+            row["start"] = 1.into();
+            row["end"] = 2.into();
+            json["share_proofs"].as_array_mut().unwrap().push(row);
+            let root = json["row_proof"]["row_roots"][0].clone();
+            json["row_proof"]["row_roots"]
+                .as_array_mut()
+                .unwrap()
+                .push(root);
+            let share = json["data"][0].clone();
+            json["data"].as_array_mut().unwrap().push(share);
+        });
+
+        let err = check_share_proof_rows(&share_proof).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof leaves a gap at the start of a row after the first"
+        );
+    }
+
+    #[test]
+    fn test_check_share_proof_rows_rejects_unproven_shares() {
+        let share_proof = edited_share_proof(|json| {
+            let share = json["data"][0].clone();
+            json["data"].as_array_mut().unwrap().push(share);
+        });
+
+        let err = check_share_proof_rows(&share_proof).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof has 2 shares for 1 proven shares"
+        );
+    }
+
+    #[test]
+    fn test_check_limits_caps_the_header_chain() {
+        let mut proof = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100).proof;
+        let limits = ProofLimits {
+            max_header_chain: 2,
+            ..Default::default()
+        };
+
+        proof.header_chain = vec![Header::default(); 2];
+        proof.check_limits(&limits).unwrap();
+
+        proof.header_chain.push(Header::default());
+        let err = proof.check_limits(&limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Header chain has 3 headers, the limit is 2"
+        );
+    }
+
+    #[test]
+    fn test_expected_storage_value() {
+        let commitment = B256::repeat_byte(0x11);
+        let mut expected = vec![0xa0];
+        expected.extend_from_slice(commitment.as_slice());
+        assert_eq!(
+            expected_storage_value(commitment).unwrap(),
+            Bytes::from(expected)
+        );
+    }
+
+    #[test]
+    fn test_expected_storage_value_drops_leading_zeros() {
+        let mut commitment = B256::ZERO;
+        commitment[30] = 0x12;
+        commitment[31] = 0x34;
+        assert_eq!(
+            expected_storage_value(commitment).unwrap(),
+            Bytes::from_static(&[0x82, 0x12, 0x34])
+        );
+
+        // A single byte below 0x80 is its own encoding.
+        let commitment = B256::with_last_byte(0x01);
+        assert_eq!(
+            expected_storage_value(commitment).unwrap(),
+            Bytes::from_static(&[0x01])
+        );
+    }
+
+    #[test]
+    fn test_expected_storage_value_of_zero_is_absent() {
+        assert_eq!(expected_storage_value(B256::ZERO), None);
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        fixture.proof.check_invariants().unwrap();
+
+        let cases: [(fn(&mut BlobstreamProof), &str); 6] = [
+            (
+                |proof| proof.storage_proof.clear(),
+                "Storage proof is empty",
+            ),
+            (
+                |proof| proof.account_proof.clear(),
+                "Account proof is empty",
+            ),
+            (
+                |proof| proof.proof_nonce = U256::ZERO,
+                "Proof nonce is zero",
+            ),
+            (
+                |proof| proof.data_commitment = B256::ZERO,
+                "Data commitment is zero",
+            ),
+            (
+                |proof| proof.storage_root = B256::ZERO,
+                "Storage root is zero",
+            ),
+            (
+                |proof| proof.block_header.state_root = B256::ZERO,
+                "Block header state root is zero",
+            ),
+        ];
+        for (edit, expected) in cases {
+            let mut proof = fixture.proof.clone();
+            edit(&mut proof);
+            assert_eq!(proof.check_invariants().unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_validate_checks_invariants() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+
+        let mut proof = fixture.proof.clone();
+        proof.data_commitment = B256::ZERO;
+        let err = proof
+            .validate(fixture.height, fixture.l1_head, fixture.blobstream_address)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Data commitment is zero");
+
+        let mut proof = fixture.proof;
+        proof.storage_proof.clear();
+        let err = proof
+            .validate_anchored(
+                fixture.height,
+                proof.storage_root,
+                fixture.blobstream_address,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Storage proof is empty");
+
+        let err = proof
+            .validate_at_state_root(
+                fixture.height,
+                proof.block_header.state_root,
+                fixture.blobstream_address,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Storage proof is empty");
+    }
+
+    #[test]
+    fn test_tampered_storage_proof_fails_at_the_storage_stage() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+
+        for index in 0..fixture.proof.storage_proof.len() {
+            let mut proof = fixture.proof.clone();
+            let mut node = proof.storage_proof[index].to_vec();
+            let middle = node.len() / 2;
+            node[middle] ^= 0x01;
+            proof.storage_proof[index] = node.into();
+
+            verify_commitment_in_storage(
+                proof.storage_root,
+                &proof.storage_proof,
+                proof.proof_nonce,
+                proof.data_commitment,
+            )
+            .unwrap_err();
+            let err = proof
+                .validate(fixture.height, fixture.l1_head, fixture.blobstream_address)
+                .unwrap_err();
+            assert_eq!(
+                VerificationStage::of(&err),
+                Some(VerificationStage::StorageProof),
+                "node {index}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_storage_root_must_be_the_proven_accounts() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &fixture.proof;
+
+        let account = proven_account(
+            proof.block_header.state_root,
+            fixture.blobstream_address,
+            &proof.account_proof,
+        )
+        .unwrap();
+        assert_eq!(account.storage_root, proof.storage_root);
+
+        // The account proof is valid, but binds another storage root than the one passed.
+        let err = verify_account_fields(
+            proof.block_header.state_root,
+            B256::repeat_byte(0x11),
+            &proof.account_proof,
+            fixture.blobstream_address,
+            proof.blobstream_balance,
+            proof.blobstream_nonce,
+            proof.blobstream_code_hash,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not match the proven account's"),
+            "{err}"
+        );
+
+        let mut mismatched = proof.clone();
+        mismatched.storage_root = B256::repeat_byte(0x11);
+        let err = mismatched
+            .validate(fixture.height, fixture.l1_head, fixture.blobstream_address)
+            .unwrap_err();
+        assert_eq!(
+            VerificationStage::of(&err),
+            Some(VerificationStage::AccountProof)
+        );
+    }
+
+    #[test]
+    fn test_validate_anchored_ignores_the_account_proof() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+
+        // Only the storage proof is needed against a storage root fixed out of band.
+        let mut proof = fixture.proof;
+        let storage_root = proof.storage_root;
+        proof.account_proof.clear();
+        proof.block_header.state_root = B256::ZERO;
+        proof
+            .validate_anchored(fixture.height, storage_root, fixture.blobstream_address)
+            .unwrap();
+
+        let err = proof
+            .validate_anchored(
+                fixture.height,
+                B256::repeat_byte(0x11),
+                fixture.blobstream_address,
+            )
+            .unwrap_err();
+        assert_eq!(
+            VerificationStage::of(&err),
+            Some(VerificationStage::StorageProof)
+        );
+    }
+
+    #[test]
+    fn test_zero_commitment_is_rejected_by_every_verifier() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &fixture.proof;
+        let expected = "Data commitment is zero, zero slots are absent from the trie";
+
+        let err = verify_commitment_in_storage(
+            proof.storage_root,
+            &proof.storage_proof,
+            proof.proof_nonce,
+            B256::ZERO,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+
+        let err = verify_data_commitment(
+            proof.storage_root,
+            &proof.storage_proof,
+            &proof.account_proof,
+            proof.proof_nonce,
+            B256::ZERO,
+            fixture.blobstream_address,
+            proof.blobstream_balance,
+            proof.blobstream_nonce,
+            proof.blobstream_code_hash,
+            &proof.block_header,
+            fixture.l1_head,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+
+        let err = verify_data_commitment_at_state_root(
+            proof.block_header.state_root,
+            proof.storage_root,
+            &proof.storage_proof,
+            &proof.account_proof,
+            proof.proof_nonce,
+            B256::ZERO,
+            fixture.blobstream_address,
+            proof.blobstream_balance,
+            proof.blobstream_nonce,
+            proof.blobstream_code_hash,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+
+        let commitments = [CommitmentStorageProof {
+            proof_nonce: proof.proof_nonce,
+            data_commitment: B256::ZERO,
+            storage_proof: &proof.storage_proof,
+        }];
+        let err = verify_data_commitments_batch(
+            proof.storage_root,
+            &commitments,
+            &proof.account_proof,
+            fixture.blobstream_address,
+            proof.blobstream_balance,
+            proof.blobstream_nonce,
+            proof.blobstream_code_hash,
+            &proof.block_header,
+            fixture.l1_head,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[test]
+    fn test_validate_at_state_root() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &fixture.proof;
+        let state_root = proof.block_header.state_root;
+
+        // The header isn't checked, a proof with a header not hashing to any l1 head still
+        // verifies against the supplied root.
+        let mut unanchored = proof.clone();
+        unanchored.block_header.number += 1;
+        unanchored
+            .validate_at_state_root(fixture.height, state_root, fixture.blobstream_address)
+            .unwrap();
+
+        let err = proof
+            .validate_at_state_root(
+                fixture.height,
+                B256::repeat_byte(0x11),
+                fixture.blobstream_address,
+            )
+            .unwrap_err();
+        assert_eq!(
+            VerificationStage::of(&err),
+            Some(VerificationStage::AccountProof)
+        );
+    }
+
+    #[test]
+    fn test_validate_at_state_root_ignores_the_block_header() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let state_root = fixture.proof.block_header.state_root;
+
+        // A proof built without a block header, whose state root is zero, verifies against the
+        // supplied root.
+        let mut proof = fixture.proof.clone();
+        proof.block_header = Default::default();
+        assert!(proof.check_invariants().is_err());
+        proof
+            .validate_at_state_root(fixture.height, state_root, fixture.blobstream_address)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_verify_data_commitment_at_state_root() {
+        let fixture = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &fixture.proof;
+        let verify = |state_root| {
+            verify_data_commitment_at_state_root(
+                state_root,
+                proof.storage_root,
+                &proof.storage_proof,
+                &proof.account_proof,
+                proof.proof_nonce,
+                proof.data_commitment,
+                fixture.blobstream_address,
+                proof.blobstream_balance,
+                proof.blobstream_nonce,
+                proof.blobstream_code_hash,
+            )
+        };
+
+        verify(proof.block_header.state_root).unwrap();
+        let err = verify(B256::repeat_byte(0x11)).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Account proof verification failed"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_encode_data_root_tuple() {
+        let tuple = encode_data_root_tuple(0x0102, &Hash::Sha256([0xaa; 32])).unwrap();
+
+        assert_eq!(tuple.len(), 64);
+        assert_eq!(tuple[..30], [0; 30]);
+        assert_eq!(tuple[30..32], [0x01, 0x02]);
+        assert_eq!(tuple[32..], [0xaa; 32]);
+    }
+
+    #[test]
+    fn test_encode_data_root_tuple_rejects_empty_root() {
+        assert!(encode_data_root_tuple(1, &Hash::None).is_err());
+    }
+
+    /// Returns the estimate of [estimate_blobstream_proof_size] for `proof` and its actual size.
+    fn estimate_and_size(proof: &BlobstreamProof) -> (usize, usize) {
+        let estimate = estimate_blobstream_proof_size(
+            proof.storage_proof.len(),
+            proof.account_proof.len(),
+            proof.share_proof.shares().len(),
+            proof.header_chain.len(),
+        );
+        (estimate, proof.to_bytes().unwrap().len())
+    }
+
+    #[test]
+    fn test_estimate_blobstream_proof_size_bounds_the_proof() {
+        let mut proof = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100).proof;
+        let (estimate, size) = estimate_and_size(&proof);
+        assert!(size <= estimate, "{size} > {estimate}");
+        assert!(estimate - size <= BASE_PROOF_SIZE, "{estimate} for {size}");
+
+        // Full branch nodes cost exactly what the estimate assumes.
+        let margin = estimate - size;
+        proof
+            .storage_proof
+            .extend(vec![Bytes::from(vec![0xaa; MAX_TRIE_NODE_SIZE]); 8]);
+        proof
+            .account_proof
+            .extend(vec![Bytes::from(vec![0xaa; MAX_TRIE_NODE_SIZE]); 8]);
+        let (estimate, size) = estimate_and_size(&proof);
+        assert_eq!(estimate - size, margin);
+
+        // So does the header chain, within the estimate.
+        proof.header_chain = vec![proof.block_header.clone(); 4];
+        let (estimate, size) = estimate_and_size(&proof);
+        assert!(size <= estimate, "{size} > {estimate}");
+        assert!(estimate - size <= BASE_PROOF_SIZE, "{estimate} for {size}");
+    }
+
+    #[test]
+    fn test_verify_blobstream_account_rejects_wrong_block_hash() {
+        let header = Header::default();
+        let wrong_hash = B256::repeat_byte(0x11);
+        assert_ne!(header.hash_slow(), wrong_hash);
+
+        let err = verify_blobstream_account(
+            B256::ZERO,
+            &[],
+            Address::ZERO,
+            U256::ZERO,
+            0,
+            B256::ZERO,
+            &header,
+            wrong_hash,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("computed block hash must match host l1 head"));
+    }
+
+    #[test]
+    fn test_header_field_diff_names_the_diverging_field() {
+        let expected = Header {
+            number: 7,
+            withdrawals_root: Some(B256::repeat_byte(1)),
+            ..Default::default()
+        };
+        let mut computed = expected.clone();
+        computed.withdrawals_root = Some(B256::repeat_byte(2));
+
+        assert_eq!(
+            header_field_diff(&computed, &expected),
+            vec!["withdrawals_root"]
+        );
+        assert!(header_field_diff(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn test_diagnose_header_hash_finds_the_extra_fork_field() {
+        let block = Header {
+            base_fee_per_gas: Some(7),
+            withdrawals_root: Some(B256::repeat_byte(1)),
+            ..Default::default()
+        };
+        let mut rpc_header = block.clone();
+        rpc_header.blob_gas_used = Some(0);
+        rpc_header.excess_blob_gas = Some(0);
+
+        assert_eq!(
+            diagnose_header_hash(&rpc_header, block.hash_slow()),
+            Some("blob_gas_used")
+        );
+        assert_eq!(
+            diagnose_header_hash(&rpc_header, B256::repeat_byte(9)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_block_hash_mismatch_error_names_the_field() {
+        let block = Header::default();
+        let mut rpc_header = block.clone();
+        rpc_header.requests_hash = Some(B256::ZERO);
+
+        let err = verify_block_hash(&rpc_header, block.hash_slow()).unwrap_err();
+        assert!(err.to_string().contains("unexpected requests_hash"));
+    }
+
+    #[test]
+    fn test_verify_data_commitment_rejects_wrong_block_hash() {
+        let header = Header::default();
+        let result = verify_data_commitments_batch(
+            B256::ZERO,
+            &[],
+            &[],
+            Address::ZERO,
+            U256::ZERO,
+            0,
+            B256::ZERO,
+            &header,
+            B256::repeat_byte(0x22),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_derivation_chain_matches_the_proof() {
+        let synthetic = crate::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &synthetic.proof;
+        let chain = proof.derivation_chain(synthetic.height).unwrap();
+
+        assert_eq!(chain.height, synthetic.height);
+        assert_eq!(chain.data_root, proof.data_root);
+        assert_eq!(
+            chain.data_root_tuple,
+            encode_data_root_tuple(synthetic.height, &proof.data_root).unwrap()
+        );
+        assert_eq!(chain.data_commitment, proof.data_commitment);
+        assert_eq!(chain.proof_nonce, proof.proof_nonce);
+        // The slot the storage proof of the synthetic proof is built for.
+        assert_eq!(
+            chain.storage_slot,
+            calculate_mapping_slot(DATA_COMMITMENTS_SLOT, U256::from(1))
+        );
+    }
+
+    #[test]
+    fn test_data_commitment_stored_serde_round_trip() {
+        let event = SP1BlobstreamDataCommitmentStored {
+            proof_nonce: U256::from(7),
+            start_block: 100,
+            end_block: 200,
+            data_commitment: B256::repeat_byte(0x22),
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        let decoded: SP1BlobstreamDataCommitmentStored = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.proof_nonce, event.proof_nonce);
+        assert_eq!(decoded.start_block, event.start_block);
+        assert_eq!(decoded.end_block, event.end_block);
+        assert_eq!(decoded.data_commitment, event.data_commitment);
+    }
+
+    // The expected slots are `keccak256(abi.encode(key, slot))`, as laid out by Solidity.
+
+    #[test]
+    fn test_mapping_slot_uint() {
+        assert_eq!(
+            mapping_slot_uint(0, 0u64),
+            alloy_primitives::b256!(
+                "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+            )
+        );
+        assert_eq!(
+            mapping_slot_uint(DATA_COMMITMENTS_SLOT, 1u64),
+            alloy_primitives::b256!(
+                "0x457c8a48b4735f56b938837eb0a8a5f9c55f23c1a85767ce3b65c3e59d3d32b7"
+            )
+        );
+        // A uint64 key is padded like the equal uint256.
+        assert_eq!(
+            mapping_slot_uint(DATA_COMMITMENTS_SLOT, u64::MAX),
+            alloy_primitives::b256!(
+                "0x0e712543abde6b65869b83a8b0e36e9d1395efd6fb86d5d93af46fafd2173d0c"
+            )
+        );
+        assert_eq!(
+            calculate_mapping_slot(DATA_COMMITMENTS_SLOT, U256::from(u64::MAX)),
+            mapping_slot_uint(DATA_COMMITMENTS_SLOT, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_mapping_slot_address() {
+        assert_eq!(
+            mapping_slot_address(3, Address::repeat_byte(0xbb)),
+            alloy_primitives::b256!(
+                "0x8e700853404c38bba2a49a3fd5fc194ca07c6d109868673c3d8fcaa58a409779"
+            )
+        );
+    }
+
+    #[test]
+    fn test_mapping_slot_bytes32() {
+        assert_eq!(
+            mapping_slot_bytes32(7, B256::repeat_byte(0xab)),
+            alloy_primitives::b256!(
+                "0xbd5c30b3c65f48fc42da750ecf4357eec4872c47a1b4ad75bdda2ed139747b19"
+            )
+        );
+    }
+
+    #[test]
+    fn test_encode_data_root_tuple_height_is_big_endian() {
+        for height in [0, 1, u64::MAX] {
+            let tuple = encode_data_root_tuple(height, &Hash::Sha256([0xaa; 32])).unwrap();
+            assert_eq!(U256::from_be_slice(&tuple[..32]), U256::from(height));
+        }
+    }
+
+    #[test]
+    fn test_blobstream_addresses_round_trip() {
+        let addresses = BlobstreamAddresses::from([
+            (31337, Address::repeat_byte(0xaa)),
+            (424242, Address::repeat_byte(0xbb)),
+        ]);
+        let encoded = encode_blobstream_addresses(&addresses);
+
+        assert_eq!(encoded.len(), 2 * BLOBSTREAM_ADDRESS_ENTRY_SIZE);
+        assert_eq!(decode_blobstream_addresses(&encoded).unwrap(), addresses);
+        assert!(decode_blobstream_addresses(&[]).unwrap().is_empty());
+        assert_eq!(
+            resolve_blobstream_address(31337, &addresses),
+            Some(Address::repeat_byte(0xaa))
+        );
+    }
+
+    #[test]
+    fn test_custom_addresses_never_replace_canonical_deployments() {
+        let addresses = BlobstreamAddresses::from([
+            (1, Address::repeat_byte(0xaa)),
+            (31337, Address::repeat_byte(0xbb)),
+        ]);
+
+        assert_eq!(
+            resolve_blobstream_address(1, &addresses),
+            blobstream_address(1)
+        );
+        assert!(check_blobstream_addresses(&addresses).is_err());
+        assert!(decode_blobstream_addresses(&encode_blobstream_addresses(&addresses)).is_err());
+    }
+
+    #[test]
+    fn test_blobstream_addresses_have_a_single_encoding() {
+        let addresses = BlobstreamAddresses::from([
+            (31337, Address::repeat_byte(0xaa)),
+            (424242, Address::repeat_byte(0xbb)),
+        ]);
+        let encoded = encode_blobstream_addresses(&addresses);
+
+        assert!(decode_blobstream_addresses(&encoded[1..]).is_err());
+        let mut swapped = encoded[BLOBSTREAM_ADDRESS_ENTRY_SIZE..].to_vec();
+        swapped.extend_from_slice(&encoded[..BLOBSTREAM_ADDRESS_ENTRY_SIZE]);
+        assert!(decode_blobstream_addresses(&swapped).is_err());
+    }
+
+    #[test]
+    fn test_supported_blobstream_chains_resolve() {
+        let chains = supported_blobstream_chains();
+        assert_eq!(chains.len(), supported_blobstream_chain_ids().len());
+
+        for (&(chain_id, address), &listed_id) in
+            chains.iter().zip(supported_blobstream_chain_ids())
+        {
+            assert_eq!(chain_id, listed_id);
+            assert_eq!(blobstream_address(chain_id), Some(address));
         }
-    } else {
-        None
+        assert_eq!(blobstream_address(31337), None);
     }
 }
@@ -0,0 +1,71 @@
+//! Errors returned when verifying an [OraclePayload](crate::payload::OraclePayload).
+
+use alloc::string::{String, ToString};
+use alloy_primitives::{Address, B256};
+
+use crate::blobstream::VerificationStage;
+
+/// An error verifying an [OraclePayload](crate::payload::OraclePayload).
+#[derive(Debug, thiserror::Error)]
+pub enum CelestiaVerificationError {
+    /// There is no canonical Blobstream deployment on the L1 chain.
+    #[error("no canonical Blobstream address found for chain id {0}")]
+    UnknownChainId(u64),
+    /// A custom Blobstream deployment was given for an L1 chain with a canonical one.
+    #[error("custom Blobstream address {address} can't replace the canonical one of chain id {chain_id}")]
+    CanonicalDeploymentOverride {
+        /// The L1 chain id.
+        chain_id: u64,
+        /// The custom Blobstream address.
+        address: Address,
+    },
+    /// The Blobstream proof failed to verify.
+    #[error("{0}")]
+    Proof(String),
+    /// The Blobstream proof failed to verify at `stage`.
+    #[error("{message}")]
+    Stage {
+        /// The verification stage that failed.
+        stage: VerificationStage,
+        /// The verification error.
+        message: String,
+    },
+    /// The blob doesn't recompute to the commitment that was requested.
+    #[error("blob has commitment {actual}, expected {expected}")]
+    CommitmentMismatch {
+        /// The requested commitment.
+        expected: B256,
+        /// The commitment recomputed from the blob.
+        actual: B256,
+    },
+    /// The shares of the blob were proven in another namespace than the requested one.
+    #[error("blob shares were proven in namespace {actual}, expected {expected}")]
+    NamespaceMismatch {
+        /// The hex encoded requested namespace.
+        expected: String,
+        /// The hex encoded namespace of the share proof.
+        actual: String,
+    },
+}
+
+impl CelestiaVerificationError {
+    /// Converts an error of [BlobstreamProof::validate](crate::blobstream::BlobstreamProof::validate)
+    /// or of the other proof checks, keeping the stage it failed at.
+    pub fn from_proof_error(err: anyhow::Error) -> Self {
+        match VerificationStage::of(&err) {
+            Some(stage) => Self::Stage {
+                stage,
+                message: err.to_string(),
+            },
+            None => Self::Proof(err.to_string()),
+        }
+    }
+
+    /// Returns the verification stage the proof failed at, if known.
+    pub const fn stage(&self) -> Option<VerificationStage> {
+        match self {
+            Self::Stage { stage, .. } => Some(*stage),
+            _ => None,
+        }
+    }
+}
@@ -5,6 +5,18 @@
 
 extern crate alloc;
 
-extern crate std;
-
 pub mod blobstream;
+
+pub mod errors;
+
+pub mod multiproof;
+
+pub mod payload;
+
+pub mod streaming;
+
+#[cfg(any(test, feature = "custom-hasher"))]
+pub mod trie;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
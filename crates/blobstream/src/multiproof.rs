@@ -0,0 +1,290 @@
+//! Storage multiproofs: the proofs of several Blobstream slots as a single set of trie nodes.
+//!
+//! Proofs of slots in the same storage trie share the nodes near the root. A [StorageMultiproof]
+//! holds every node once, and each slot is verified by walking from the storage root, resolving
+//! every node reference in the shared set. The nodes are those retained by alloy-trie's
+//! `HashBuilder` for several targets, or the union of the `eth_getProof` proofs of the slots.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use alloy_primitives::{keccak256, Bytes, B256, U256};
+use alloy_rlp::{Decodable, EMPTY_STRING_CODE};
+use alloy_trie::{nodes::TrieNode, Nibbles};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::blobstream::{calculate_mapping_slot, expected_storage_value, DATA_COMMITMENTS_SLOT};
+
+/// The trie nodes proving several slots of the Blobstream storage trie.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageMultiproof {
+    /// The distinct trie nodes of the proofs, in any order.
+    pub nodes: Vec<Bytes>,
+}
+
+impl StorageMultiproof {
+    /// Builds a multiproof from the proofs of individual slots against the same storage root,
+    /// keeping every shared node once.
+    pub fn from_proofs<'a>(proofs: impl IntoIterator<Item = &'a [Bytes]>) -> Self {
+        let mut nodes: Vec<Bytes> = proofs.into_iter().flatten().cloned().collect();
+        nodes.sort();
+        nodes.dedup();
+        Self { nodes }
+    }
+
+    /// Verifies that every `(nonce, commitment)` pair in `commitments` is stored in the
+    /// state_dataCommitments mapping of the Blobstream storage trie with root `storage_root`.
+    ///
+    /// Every node of the multiproof must be used by at least one of the slots, so that a proof
+    /// can't be padded with unrelated nodes.
+    ///
+    /// Security Note: This function assumes the storage_root comes from a secure source.
+    pub fn verify_commitments(
+        &self,
+        storage_root: B256,
+        commitments: &[(U256, B256)],
+    ) -> Result<()> {
+        let nodes: BTreeMap<B256, &Bytes> = self
+            .nodes
+            .iter()
+            .map(|node| (keccak256(node), node))
+            .collect();
+        let mut used = BTreeSet::new();
+
+        for (nonce, commitment) in commitments {
+            let value = expected_storage_value(*commitment).ok_or_else(|| {
+                anyhow!("Data commitment is zero, zero slots are absent from the trie")
+            })?;
+            let path = Nibbles::unpack(keccak256(calculate_mapping_slot(
+                DATA_COMMITMENTS_SLOT,
+                *nonce,
+            )));
+
+            verify_trie_path(storage_root, &path, &value, |hash| {
+                let node: &Bytes = nodes
+                    .get(&hash)
+                    .copied()
+                    .ok_or_else(|| anyhow!("Multiproof is missing the trie node {}", hash))?;
+                used.insert(hash);
+                Ok(&node[..])
+            })
+            .map_err(|e| {
+                anyhow!(
+                    "Storage proof verification failed for nonce {}: {}",
+                    nonce,
+                    e
+                )
+            })?;
+        }
+
+        if used.len() != nodes.len() {
+            return Err(anyhow!(
+                "Multiproof has {} nodes, only {} are used",
+                nodes.len(),
+                used.len()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Walks `path` down the Merkle Patricia trie with root `root` and checks that it ends in a leaf
+/// holding `value`.
+///
+/// The nodes referenced by hash are resolved with `resolve`, which must only return a node
+/// hashing to the given hash. Nodes shorter than a hash are embedded in their parent and never
+/// resolved.
+pub(crate) fn verify_trie_path<'a>(
+    root: B256,
+    path: &Nibbles,
+    value: &[u8],
+    mut resolve: impl FnMut(B256) -> Result<&'a [u8]>,
+) -> Result<()> {
+    let mut walked = 0;
+    let mut hash = Some(root);
+    let mut embedded = Vec::new();
+
+    loop {
+        let node = match hash {
+            Some(hash) => resolve(hash)?,
+            None => embedded.as_slice(),
+        };
+
+        // The reference to the next node: its RLP encoded hash, or the node itself.
+        let reference = match TrieNode::decode(&mut &node[..])
+            .map_err(|e| anyhow!("Invalid trie proof node: {}", e))?
+        {
+            TrieNode::Branch(branch) => {
+                let nibble = path
+                    .get(walked)
+                    .ok_or_else(|| anyhow!("Trie proof is longer than its key"))?;
+                if !branch.state_mask.is_bit_set(nibble) {
+                    return Err(anyhow!("Trie proof key is not in the trie"));
+                }
+                // The children of the branch are stored in nibble order, without the empty ones.
+                let index = (0..nibble)
+                    .filter(|child| branch.state_mask.is_bit_set(*child))
+                    .count();
+                walked += 1;
+                branch
+                    .stack
+                    .get(index)
+                    .ok_or_else(|| anyhow!("Trie proof branch is missing a child"))?
+                    .to_vec()
+            }
+            TrieNode::Extension(extension) => {
+                if !path.slice(walked..).starts_with(&extension.key) {
+                    return Err(anyhow!("Trie proof key is not in the trie"));
+                }
+                walked += extension.key.len();
+                extension.child.to_vec()
+            }
+            TrieNode::Leaf(leaf) => {
+                if path.slice(walked..) != leaf.key {
+                    return Err(anyhow!("Trie proof key is not in the trie"));
+                }
+                if leaf.value != value {
+                    return Err(anyhow!(
+                        "Trie proof value does not match the expected value"
+                    ));
+                }
+                return Ok(());
+            }
+            TrieNode::EmptyRoot => return Err(anyhow!("Trie proof key is not in the trie")),
+        };
+
+        if reference.len() == B256::len_bytes() + 1 && reference[0] == EMPTY_STRING_CODE + 32 {
+            hash = Some(B256::from_slice(&reference[1..]));
+        } else {
+            hash = None;
+            embedded = reference;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloy_trie::{proof::ProofRetainer, HashBuilder};
+
+    /// Builds the storage trie of `commitments` and returns its root and the multiproof of every
+    /// slot, retained by the hash builder.
+    fn storage_trie(commitments: &[(U256, B256)]) -> (B256, StorageMultiproof) {
+        let mut leaves: Vec<(Nibbles, Bytes)> = commitments
+            .iter()
+            .map(|(nonce, commitment)| {
+                let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, *nonce);
+                (
+                    Nibbles::unpack(keccak256(slot)),
+                    expected_storage_value(*commitment).unwrap(),
+                )
+            })
+            .collect();
+        leaves.sort();
+
+        let targets = leaves.iter().map(|(path, _)| *path).collect();
+        let mut builder = HashBuilder::default().with_proof_retainer(ProofRetainer::new(targets));
+        for (path, value) in &leaves {
+            builder.add_leaf(*path, value);
+        }
+        let root = builder.root();
+        let nodes = builder
+            .take_proof_nodes()
+            .into_nodes_sorted()
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect();
+
+        (root, StorageMultiproof { nodes })
+    }
+
+    fn commitments() -> Vec<(U256, B256)> {
+        vec![
+            (U256::from(1), B256::repeat_byte(0x11)),
+            (U256::from(2), B256::repeat_byte(0x22)),
+        ]
+    }
+
+    #[test]
+    fn test_verifies_two_slots_from_one_multiproof() {
+        let commitments = commitments();
+        let (root, multiproof) = storage_trie(&commitments);
+
+        multiproof.verify_commitments(root, &commitments).unwrap();
+        // The root branch is shared, the two slots need fewer nodes than two proofs.
+        assert_eq!(multiproof.nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_rejects_a_commitment_not_in_the_trie() {
+        let commitments = commitments();
+        let (root, multiproof) = storage_trie(&commitments);
+
+        let wrong = [(U256::from(2), B256::repeat_byte(0x33))];
+        let err = multiproof.verify_commitments(root, &wrong).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not match the expected value"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_rejects_missing_and_unused_nodes() {
+        let commitments = commitments();
+        let (root, multiproof) = storage_trie(&commitments);
+
+        // Proving a single slot leaves the leaf of the other one unused.
+        let err = multiproof
+            .verify_commitments(root, &commitments[..1])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Multiproof has 3 nodes, only 2 are used");
+
+        let mut truncated = multiproof;
+        truncated.nodes.retain(|node| keccak256(node) != root);
+        let err = truncated
+            .verify_commitments(root, &commitments)
+            .unwrap_err();
+        assert!(err.to_string().contains("missing the trie node"), "{err}");
+    }
+
+    #[test]
+    fn test_from_proofs_keeps_shared_nodes_once() {
+        let commitments = commitments();
+        let (root, multiproof) = storage_trie(&commitments);
+        let root_node = multiproof
+            .nodes
+            .iter()
+            .find(|node| keccak256(node) == root)
+            .unwrap()
+            .clone();
+        let leaves: Vec<Bytes> = multiproof
+            .nodes
+            .iter()
+            .filter(|node| **node != root_node)
+            .cloned()
+            .collect();
+
+        // The eth_getProof proofs of the two slots, both starting at the root.
+        let first = [root_node.clone(), leaves[0].clone()];
+        let second = [root_node, leaves[1].clone()];
+        let combined = StorageMultiproof::from_proofs([&first[..], &second[..]]);
+
+        assert_eq!(combined.nodes.len(), 3);
+        combined.verify_commitments(root, &commitments).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_a_zero_commitment() {
+        let commitments = commitments();
+        let (root, multiproof) = storage_trie(&commitments);
+
+        assert!(multiproof
+            .verify_commitments(root, &[(U256::from(1), B256::ZERO)])
+            .is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! The oracle payload the host serves for a Celestia blob: the blob and its [BlobstreamProof].
+//!
+//! The encoding is shared by the kona client oracle and the standalone verifiers, so that every
+//! reader decodes payloads the same way.
+
+use alloc::{boxed::Box, format, string::ToString, vec, vec::Vec};
+use alloy_primitives::{hex, keccak256, Bytes, B256};
+use anyhow::{anyhow, Result};
+use bincode::Options;
+use celestia_types::{
+    consts::appconsts::SHARE_VERSION_ZERO, nmt::Namespace, AppVersion, Blob, Commitment,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blobstream::{at_stage, BlobstreamProof, ProofLimits, VerificationStage},
+    errors::CelestiaVerificationError,
+};
+
+/// The bincode configuration payloads are encoded with: the layout of `bincode::serialize`
+/// (fixed size little endian integers), rejecting any bytes left after the payload.
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .reject_trailing_bytes()
+}
+
+/// The version of the payload encoding, the first byte of every encoded payload. Bump it whenever
+/// the layout of [OraclePayload] or [BlobstreamProof] changes, so that payloads cached in an older
+/// layout are rejected instead of misread.
+pub const PAYLOAD_VERSION: u8 = 1;
+
+/// A structure containing a Celestia Blob and its corresponding proofs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OraclePayload {
+    /// The Celestia blob data
+    pub blob: Bytes,
+    /// Blobstream proof data
+    pub blobstream_proof: BlobstreamProof,
+}
+
+impl OraclePayload {
+    /// Create a new OraclePayload instance
+    pub fn new(blob: Bytes, blobstream_proof: BlobstreamProof) -> Self {
+        Self {
+            blob,
+            blobstream_proof,
+        }
+    }
+
+    /// Returns `true` if the keccak256 hash of the blob is `expected`, for comparing a payload
+    /// against a blob obtained independently. The blob isn't verified against Blobstream.
+    pub fn verify_blob_hash(&self, expected: B256) -> bool {
+        keccak256(&self.blob) == expected
+    }
+
+    /// Recomputes the commitment of the blob in the namespace its shares were proven in, to bind
+    /// the blob to the commitment that was requested.
+    pub fn proven_commitment(&self) -> Result<Commitment, celestia_types::Error> {
+        Commitment::from_blob(
+            self.blobstream_proof.share_proof.namespace(),
+            &self.blob,
+            SHARE_VERSION_ZERO,
+            None,
+            AppVersion::latest(),
+        )
+    }
+
+    /// Checks that the share proof proves exactly the shares of the blob, split in the namespace
+    /// they were proven in: as many shares, with the same contents, in the same order.
+    ///
+    /// [BlobstreamProof::validate] only proves that the shares of the share proof are in the data
+    /// root, this check binds them to the blob. Without it, a valid proof of unrelated shares at
+    /// the same height would vouch for any blob.
+    pub fn check_blob_shares(&self) -> Result<()> {
+        let share_proof = &self.blobstream_proof.share_proof;
+        let shares = Blob::new(
+            share_proof.namespace(),
+            self.blob.to_vec(),
+            None,
+            AppVersion::latest(),
+        )
+        .and_then(|blob| blob.to_shares())
+        .map_err(|e| anyhow!("Failed to split the blob into shares: {}", e))
+        .map_err(at_stage(VerificationStage::ShareProof))?;
+
+        let proven = share_proof.shares();
+        if shares.len() != proven.len() {
+            return Err(at_stage(VerificationStage::ShareProof)(anyhow!(
+                "Share proof proves {} shares, the blob has {}",
+                proven.len(),
+                shares.len()
+            )));
+        }
+        if let Some(index) = shares.iter().zip(proven).position(|(share, proven)| {
+            AsRef::<[u8]>::as_ref(share) != AsRef::<[u8]>::as_ref(proven)
+        }) {
+            return Err(at_stage(VerificationStage::ShareProof)(anyhow!(
+                "Share {} of the share proof does not match the blob",
+                index
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the blob is made of the shares the share proof proves, see
+    /// [OraclePayload::check_blob_shares], and that it recomputes to `commitment` in the namespace
+    /// they were proven in. When `namespace` is given, the shares must have been proven in it.
+    ///
+    /// This binds the blob to the commitment that was requested, once the proof itself was
+    /// verified with [BlobstreamProof::validate].
+    pub fn verify_blob(
+        &self,
+        commitment: Commitment,
+        namespace: Option<Namespace>,
+    ) -> Result<(), CelestiaVerificationError> {
+        let proven_namespace = self.blobstream_proof.share_proof.namespace();
+        if let Some(namespace) = namespace {
+            if namespace != proven_namespace {
+                return Err(CelestiaVerificationError::NamespaceMismatch {
+                    expected: hex::encode(namespace.as_bytes()),
+                    actual: hex::encode(proven_namespace.as_bytes()),
+                });
+            }
+        }
+
+        self.check_blob_shares()
+            .map_err(CelestiaVerificationError::from_proof_error)?;
+
+        let actual = self
+            .proven_commitment()
+            .map_err(|err| CelestiaVerificationError::Proof(err.to_string()))?;
+        if actual != commitment {
+            return Err(CelestiaVerificationError::CommitmentMismatch {
+                expected: B256::from(*commitment.hash()),
+                actual: B256::from(*actual.hash()),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Serialize the struct to bytes using serde with a binary format, prefixed with
+    /// [PAYLOAD_VERSION]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn core::error::Error>> {
+        let mut bytes = vec![PAYLOAD_VERSION];
+        bytes.extend(bincode_options().serialize(self)?);
+        Ok(bytes)
+    }
+
+    /// Deserialize from bytes back into the struct, rejecting proofs over the default
+    /// [ProofLimits]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn core::error::Error>> {
+        Self::from_bytes_with_limits(bytes, &ProofLimits::default())
+    }
+
+    /// Deserialize from bytes back into the struct, rejecting proofs over `limits`
+    ///
+    /// The whole buffer must be consumed, a payload followed by trailing bytes is rejected, as is
+    /// a payload of another version than [PAYLOAD_VERSION].
+    pub fn from_bytes_with_limits(
+        bytes: &[u8],
+        limits: &ProofLimits,
+    ) -> Result<Self, Box<dyn core::error::Error>> {
+        let (version, bytes) = bytes.split_first().ok_or("oracle payload is empty")?;
+        if *version != PAYLOAD_VERSION {
+            return Err(format!(
+                "unsupported oracle payload version {version}, expected {PAYLOAD_VERSION}"
+            )
+            .into());
+        }
+
+        let deserialized: Self = bincode_options().deserialize(bytes)?;
+        deserialized.blobstream_proof.check_limits(limits)?;
+        Ok(deserialized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::synthetic_proof;
+    use alloy_primitives::Address;
+
+    fn payload() -> OraclePayload {
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        OraclePayload::new(Bytes::from_static(b"hana-blob"), fixture.proof)
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = payload();
+        let decoded = OraclePayload::from_bytes(&payload.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.blob, payload.blob);
+        assert_eq!(
+            decoded.blobstream_proof.to_bytes().unwrap(),
+            payload.blobstream_proof.to_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_payload_starts_with_its_version() {
+        assert_eq!(payload().to_bytes().unwrap()[0], PAYLOAD_VERSION);
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_other_versions() {
+        let mut bytes = payload().to_bytes().unwrap();
+        bytes[0] = PAYLOAD_VERSION + 1;
+
+        let err = OraclePayload::from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "unsupported oracle payload version {}, expected {PAYLOAD_VERSION}",
+                PAYLOAD_VERSION + 1
+            )
+        );
+        assert!(OraclePayload::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_trailing_bytes() {
+        let mut bytes = payload().to_bytes().unwrap();
+        bytes.push(0);
+
+        assert!(OraclePayload::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_with_limits_rejects_large_proofs() {
+        let bytes = payload().to_bytes().unwrap();
+        let limits = ProofLimits {
+            max_nodes: 0,
+            ..Default::default()
+        };
+
+        assert!(OraclePayload::from_bytes_with_limits(&bytes, &limits).is_err());
+    }
+
+    #[test]
+    fn test_check_blob_shares_accepts_the_proven_blob() {
+        payload().check_blob_shares().unwrap();
+    }
+
+    #[test]
+    fn test_check_blob_shares_rejects_other_shares() {
+        // A blob of the same length, the share proof proving the shares of another one.
+        let mut payload = payload();
+        payload.blob = Bytes::from_static(b"hana-evil");
+
+        let err = payload.check_blob_shares().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share 0 of the share proof does not match the blob"
+        );
+        assert_eq!(
+            VerificationStage::of(&err),
+            Some(VerificationStage::ShareProof)
+        );
+    }
+
+    #[test]
+    fn test_check_blob_shares_rejects_a_wider_range() {
+        // The share proof covers one more share than the blob has.
+        let mut payload = payload();
+        let mut json = serde_json::to_value(&payload.blobstream_proof.share_proof).unwrap();
+        let share = json["data"][0].clone();
+        json["data"].as_array_mut().unwrap().push(share);
+        json["share_proofs"][0]["end"] = 2.into();
+        payload.blobstream_proof.share_proof = serde_json::from_value(json).unwrap();
+
+        let err = payload.check_blob_shares().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof proves 2 shares, the blob has 1"
+        );
+    }
+
+    #[test]
+    fn test_verify_blob_accepts_the_requested_blob() {
+        let payload = payload();
+        let commitment = payload.proven_commitment().unwrap();
+
+        payload.verify_blob(commitment, None).unwrap();
+        payload
+            .verify_blob(commitment, Some(crate::test_utils::synthetic_namespace()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_verify_blob_rejects_another_commitment() {
+        let payload = payload();
+        let other = Commitment::new([0x11; 32]);
+
+        let err = payload.verify_blob(other, None).unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::CommitmentMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_blob_rejects_another_namespace() {
+        let payload = payload();
+        let commitment = payload.proven_commitment().unwrap();
+        let namespace = Namespace::new_v0(b"other").unwrap();
+
+        let err = payload
+            .verify_blob(commitment, Some(namespace))
+            .unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::NamespaceMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_blob_rejects_unproven_shares() {
+        // The blob matches the requested commitment, but the shares proven are another blob's.
+        let mut payload = payload();
+        payload.blob = Bytes::from_static(b"hana-evil");
+        let commitment = payload.proven_commitment().unwrap();
+
+        let err = payload.verify_blob(commitment, None).unwrap_err();
+        assert_eq!(err.stage(), Some(VerificationStage::ShareProof));
+    }
+
+    #[test]
+    fn test_verify_blob_hash() {
+        let payload = payload();
+        assert!(payload.verify_blob_hash(keccak256(b"hana-blob")));
+        assert!(!payload.verify_blob_hash(keccak256(b"another-blob")));
+    }
+}
@@ -0,0 +1,248 @@
+//! Streaming share verification: the shares of a blob verified against the data root one row at
+//! a time.
+//!
+//! [ShareProof::verify] needs every share of the blob at once, which for large blobs doesn't fit
+//! the memory of FPVM and zkVM targets. A [ShareProofRow] holds the shares of a single row with the
+//! proofs linking them to the data root, so rows can be decoded and verified one by one by a
+//! [StreamingShareVerifier], which keeps none of them. [ShareProof::verify] remains the default.
+
+use alloc::vec::Vec;
+use alloy_primitives::Bytes;
+use anyhow::{anyhow, Result};
+use celestia_types::{
+    hash::Hash,
+    nmt::{Namespace, NamespaceProof, NamespacedHash},
+    MerkleProof, ShareProof,
+};
+
+/// The shares a share proof proves in one row, with the proofs linking them to the data root.
+#[derive(Debug, Clone)]
+pub struct ShareProofRow {
+    /// The root of the row.
+    pub row_root: NamespacedHash,
+    /// The proof of `row_root` in the data root.
+    pub row_proof: MerkleProof,
+    /// The namespace proof of `shares` in `row_root`.
+    pub namespace_proof: NamespaceProof,
+    /// The proven shares of the row, in order.
+    pub shares: Vec<Bytes>,
+}
+
+/// Splits `share_proof` into its rows, for callers already holding the whole proof.
+pub fn share_proof_rows(share_proof: &ShareProof) -> Result<Vec<ShareProofRow>> {
+    let row_proof = share_proof.row_proof();
+    let share_proofs = share_proof.share_proofs();
+    if share_proofs.len() != row_proof.row_roots().len()
+        || share_proofs.len() != row_proof.proofs().len()
+    {
+        return Err(anyhow!(
+            "Share proof has {} namespace proofs for {} rows",
+            share_proofs.len(),
+            row_proof.row_roots().len()
+        ));
+    }
+
+    let mut shares = share_proof.shares();
+    let mut rows = Vec::with_capacity(share_proofs.len());
+    for ((namespace_proof, row_root), proof) in share_proofs
+        .iter()
+        .zip(row_proof.row_roots())
+        .zip(row_proof.proofs())
+    {
+        let len = namespace_proof
+            .end_idx()
+            .saturating_sub(namespace_proof.start_idx()) as usize;
+        if shares.len() < len {
+            return Err(anyhow!("Share proof is missing shares for its rows"));
+        }
+        let (row_shares, rest) = shares.split_at(len);
+        rows.push(ShareProofRow {
+            row_root: row_root.clone(),
+            row_proof: proof.clone(),
+            namespace_proof: namespace_proof.clone(),
+            shares: row_shares
+                .iter()
+                .map(|share| Bytes::copy_from_slice(AsRef::<[u8]>::as_ref(share)))
+                .collect(),
+        });
+        shares = rest;
+    }
+
+    if !shares.is_empty() {
+        return Err(anyhow!("Share proof has shares outside its rows"));
+    }
+
+    Ok(rows)
+}
+
+/// Verifies the rows of a share proof against a data root as they arrive, holding at most
+/// `max_row_shares` shares of a single row at a time.
+///
+/// The rows must prove one contiguous range of shares, like [check_share_proof_rows]: every row
+/// after the first proven from its first share, every row before the last up to the same end.
+///
+/// [check_share_proof_rows]: crate::blobstream::check_share_proof_rows
+#[derive(Debug, Clone)]
+pub struct StreamingShareVerifier {
+    /// The data root the rows are verified against.
+    data_root: [u8; 32],
+    /// The namespace the shares are proven in.
+    namespace: Namespace,
+    /// The most shares a single row may hold.
+    max_row_shares: usize,
+    /// The end of the range proven in the first row.
+    row_end: Option<u64>,
+    /// Whether a row ended before `row_end`, which makes it the last one.
+    ended: bool,
+    /// The number of shares verified so far.
+    verified: usize,
+}
+
+impl StreamingShareVerifier {
+    /// Creates a verifier of the shares proven in `namespace` against `data_root`, rejecting rows
+    /// of more than `max_row_shares` shares.
+    pub fn new(data_root: Hash, namespace: Namespace, max_row_shares: usize) -> Result<Self> {
+        let Hash::Sha256(data_root) = data_root else {
+            return Err(anyhow!("Data root is empty, expected a 32 byte hash"));
+        };
+        if max_row_shares == 0 {
+            return Err(anyhow!(
+                "Share verification chunk size must be at least one share"
+            ));
+        }
+
+        Ok(Self {
+            data_root,
+            namespace,
+            max_row_shares,
+            row_end: None,
+            ended: false,
+            verified: 0,
+        })
+    }
+
+    /// Verifies the next row: its root against the data root, and its shares against its root.
+    pub fn verify_row(&mut self, row: &ShareProofRow) -> Result<()> {
+        if row.shares.len() > self.max_row_shares {
+            return Err(anyhow!(
+                "Share proof row has {} shares, the chunk size is {}",
+                row.shares.len(),
+                self.max_row_shares
+            ));
+        }
+
+        let proof = &row.namespace_proof;
+        if proof.end_idx().saturating_sub(proof.start_idx()) as usize != row.shares.len() {
+            return Err(anyhow!(
+                "Share proof row has {} shares for {} proven shares",
+                row.shares.len(),
+                proof.end_idx().saturating_sub(proof.start_idx())
+            ));
+        }
+        match self.row_end {
+            None => self.row_end = Some(proof.end_idx()),
+            Some(_) if proof.start_idx() != 0 => {
+                return Err(anyhow!(
+                    "Share proof leaves a gap at the start of a row after the first"
+                ))
+            }
+            Some(end) if self.ended || proof.end_idx() > end => {
+                return Err(anyhow!(
+                    "Share proof leaves a gap at the end of a row before the last"
+                ))
+            }
+            Some(end) => self.ended = proof.end_idx() < end,
+        }
+
+        row.row_proof
+            .verify(row.row_root.to_array(), self.data_root)
+            .map_err(|e| anyhow!("Row proof verification failed: {}", e))?;
+        proof
+            .verify_range(&row.row_root, &row.shares, *self.namespace)
+            .map_err(|e| anyhow!("Share proof verification failed: {}", e))?;
+
+        self.verified += row.shares.len();
+        Ok(())
+    }
+
+    /// Finishes the verification, returning the number of verified shares.
+    pub fn finish(self) -> Result<usize> {
+        if self.verified == 0 {
+            return Err(anyhow!("Share proof has no shares"));
+        }
+        Ok(self.verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{synthetic_namespace, synthetic_two_row_share_proof};
+
+    /// Verifies `rows` with a streaming verifier, returning the number of verified shares.
+    fn stream(data_root: Hash, rows: &[ShareProofRow], max_row_shares: usize) -> Result<usize> {
+        let mut verifier =
+            StreamingShareVerifier::new(data_root, synthetic_namespace(), max_row_shares)?;
+        for row in rows {
+            verifier.verify_row(row)?;
+        }
+        verifier.finish()
+    }
+
+    #[test]
+    fn test_streaming_matches_batch_verification() {
+        let (_, data_root, share_proof) = synthetic_two_row_share_proof();
+        let rows = share_proof_rows(&share_proof).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        share_proof.verify(data_root).unwrap();
+        assert_eq!(
+            stream(data_root, &rows, 2).unwrap(),
+            share_proof.shares().len()
+        );
+
+        let other_root = Hash::Sha256([0x42; 32]);
+        assert!(share_proof.verify(other_root).is_err());
+        assert!(stream(other_root, &rows, 2).is_err());
+    }
+
+    #[test]
+    fn test_streaming_rejects_a_tampered_share() {
+        let (_, data_root, share_proof) = synthetic_two_row_share_proof();
+        let mut rows = share_proof_rows(&share_proof).unwrap();
+        let mut share = rows[1].shares[0].to_vec();
+        share[100] ^= 0x01;
+        rows[1].shares[0] = share.into();
+
+        let err = stream(data_root, &rows, 2).unwrap_err();
+        assert!(
+            err.to_string().contains("Share proof verification failed"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_streaming_bounds_the_row_size() {
+        let (_, data_root, share_proof) = synthetic_two_row_share_proof();
+        let rows = share_proof_rows(&share_proof).unwrap();
+
+        let err = stream(data_root, &rows, 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof row has 2 shares, the chunk size is 1"
+        );
+    }
+
+    #[test]
+    fn test_streaming_rejects_a_row_with_missing_shares() {
+        let (_, data_root, share_proof) = synthetic_two_row_share_proof();
+        let mut rows = share_proof_rows(&share_proof).unwrap();
+        rows[0].shares.pop();
+
+        let err = stream(data_root, &rows, 2).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Share proof row has 1 shares for 2 proven shares"
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Synthetic [BlobstreamProof]s for tests and fixtures.
+//!
+//! [synthetic_proof] builds every layer of a proof from scratch: a single share in a 2x2 extended
+//! data square, its namespaced merkle proof and row proof, a data commitment over two data root
+//! tuples, and a Blobstream account holding the commitment, each trie having a single leaf. The
+//! result passes [BlobstreamProof::validate], so tests can break one layer at a time.
+//! [synthetic_two_row_share_proof] builds the share proof of a blob spanning two rows.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use alloy_consensus::Header;
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::Encodable;
+use alloy_trie::TrieAccount;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use celestia_types::{
+    hash::Hash, nmt::Namespace, AppVersion, Blob, DataAvailabilityHeader, MerkleProof, ShareProof,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::blobstream::{
+    calculate_mapping_slot, encode_data_root_tuple, expected_storage_value, BlobstreamProof,
+    DATA_COMMITMENTS_SLOT,
+};
+
+/// The size of a Celestia share.
+const SHARE_SIZE: usize = 512;
+
+/// The size of a namespace, with its version byte.
+const NAMESPACE_SIZE: usize = 29;
+
+/// The size of a namespaced hash: the min and max namespaces followed by the SHA-256 digest.
+const NAMESPACED_HASH_SIZE: usize = 2 * NAMESPACE_SIZE + 32;
+
+/// The namespace of the parity shares, ignored when computing the max namespace of a node.
+const PARITY_NAMESPACE: [u8; NAMESPACE_SIZE] = [0xff; NAMESPACE_SIZE];
+
+/// The proof nonce the synthetic data commitment is stored under.
+pub const SYNTHETIC_PROOF_NONCE: u64 = 1;
+
+/// The namespace the synthetic share is proven in.
+pub fn synthetic_namespace() -> Namespace {
+    Namespace::new_v0(b"hana-test").expect("valid v0 namespace")
+}
+
+/// A [BlobstreamProof] built by [synthetic_proof], with the inputs it verifies against.
+#[derive(Debug, Clone)]
+pub struct SyntheticProof {
+    /// The proof.
+    pub proof: BlobstreamProof,
+    /// The Celestia height the proof is for.
+    pub height: u64,
+    /// The hash of the L1 block the proof is anchored at.
+    pub l1_head: B256,
+    /// The Blobstream deployment the proof was built for.
+    pub blobstream_address: Address,
+    /// The data availability header of the square holding the share, hashing to the proof's data
+    /// root.
+    pub dah: DataAvailabilityHeader,
+}
+
+/// Builds a [BlobstreamProof] of a blob at `height` stored in the Blobstream deployment at
+/// `blobstream_address`, passing [BlobstreamProof::validate].
+pub fn synthetic_proof(blobstream_address: Address, height: u64) -> SyntheticProof {
+    let namespace = synthetic_namespace();
+    let mut share = [0u8; SHARE_SIZE];
+    share[..NAMESPACE_SIZE].copy_from_slice(namespace.as_bytes());
+    // A sequence start share of version 0, holding a blob of 9 bytes.
+    share[NAMESPACE_SIZE] = 0x01;
+    share[NAMESPACE_SIZE + 1..NAMESPACE_SIZE + 5].copy_from_slice(&9u32.to_be_bytes());
+    share[NAMESPACE_SIZE + 5..NAMESPACE_SIZE + 14].copy_from_slice(b"hana-blob");
+
+    // The first row holds the share followed by its parity share, the others only parity shares.
+    let share_leaf = nmt_leaf(namespace.as_bytes(), &share);
+    let parity_leaf = nmt_leaf(&PARITY_NAMESPACE, &[0u8; SHARE_SIZE]);
+    let parity_row = nmt_inner(&parity_leaf, &parity_leaf);
+    let row_roots = [nmt_inner(&share_leaf, &parity_leaf), parity_row];
+    let column_roots = [nmt_inner(&share_leaf, &parity_leaf), parity_row];
+
+    let dah_leaves: Vec<&[u8]> = row_roots
+        .iter()
+        .chain(column_roots.iter())
+        .map(|root| root.as_slice())
+        .collect();
+    let data_root = Hash::Sha256(merkle_root(&dah_leaves));
+    let dah = data_availability_header(&row_roots, &column_roots);
+    let share_proof = share_proof(&share, &namespace, &row_roots[0], &parity_leaf, &dah_leaves);
+
+    // The data commitment covers `height` and the next block.
+    let next_tuple = encode_data_root_tuple(height + 1, &Hash::Sha256([0x42; 32])).unwrap();
+    let tuple = encode_data_root_tuple(height, &data_root).unwrap();
+    let tuples: [&[u8]; 2] = [&tuple, &next_tuple];
+    let data_commitment = B256::from(merkle_root(&tuples));
+    let data_root_tuple_proof = merkle_proof(&tuples, 0);
+
+    let proof_nonce = U256::from(SYNTHETIC_PROOF_NONCE);
+    let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, proof_nonce);
+    let storage_leaf = trie_leaf(
+        keccak256(slot),
+        &expected_storage_value(data_commitment).unwrap(),
+    );
+    let storage_root = keccak256(&storage_leaf);
+
+    let account = TrieAccount {
+        nonce: 1,
+        balance: U256::ZERO,
+        storage_root,
+        code_hash: keccak256(b"SP1Blobstream"),
+    };
+    let account_leaf = trie_leaf(keccak256(blobstream_address), &alloy_rlp::encode(account));
+
+    let block_header = Header {
+        number: 1_000,
+        state_root: keccak256(&account_leaf),
+        ..Default::default()
+    };
+    let l1_head = block_header.hash_slow();
+
+    let proof = BlobstreamProof::new(
+        blobstream_address,
+        data_root,
+        data_commitment,
+        data_root_tuple_proof,
+        share_proof,
+        proof_nonce,
+        storage_root,
+        vec![storage_leaf],
+        vec![account_leaf],
+        account.balance,
+        account.nonce,
+        account.code_hash,
+        block_header,
+        Vec::new(),
+    );
+
+    SyntheticProof {
+        proof,
+        height,
+        l1_head,
+        blobstream_address,
+        dah,
+    }
+}
+
+/// Builds the [ShareProof] of a blob filling the four shares of a 2x2 original data square, two
+/// shares in each of the first two rows, and returns it with the blob and the data root.
+///
+/// Unlike [synthetic_proof], the shares span several rows, for tests of the share verification.
+pub fn synthetic_two_row_share_proof() -> (Vec<u8>, Hash, ShareProof) {
+    let namespace = synthetic_namespace();
+    // 478 bytes fill the sequence start share, 482 bytes each continuation share.
+    let blob = vec![0xab; 478 + 3 * 482];
+    let shares = Blob::new(namespace, blob.clone(), None, AppVersion::latest())
+        .and_then(|blob| blob.to_shares())
+        .expect("valid blob");
+    assert_eq!(shares.len(), 4);
+
+    // Each of the first two rows holds two shares followed by their two parity shares, the
+    // parity rows and the columns only matter through their roots.
+    let leaves: Vec<_> = shares
+        .iter()
+        .map(|share| nmt_leaf(namespace.as_bytes(), AsRef::<[u8]>::as_ref(share)))
+        .collect();
+    let parity_leaf = nmt_leaf(&PARITY_NAMESPACE, &[0u8; SHARE_SIZE]);
+    let parity_pair = nmt_inner(&parity_leaf, &parity_leaf);
+    let parity_row = nmt_inner(&parity_pair, &parity_pair);
+    let row_roots = [
+        nmt_inner(&nmt_inner(&leaves[0], &leaves[1]), &parity_pair),
+        nmt_inner(&nmt_inner(&leaves[2], &leaves[3]), &parity_pair),
+        parity_row,
+        parity_row,
+    ];
+    let column_roots = [parity_row; 4];
+
+    let dah_leaves: Vec<&[u8]> = row_roots
+        .iter()
+        .chain(column_roots.iter())
+        .map(|root| root.as_slice())
+        .collect();
+    let data_root = Hash::Sha256(merkle_root(&dah_leaves));
+
+    let namespace_proof = json!({
+        "start": 0,
+        "end": 2,
+        "nodes": [STANDARD.encode(parity_pair)],
+        "leaf_hash": "",
+        "is_max_namespace_ignored": true,
+    });
+    let share_proof = serde_json::from_value(json!({
+        "data": shares
+            .iter()
+            .map(|share| STANDARD.encode(AsRef::<[u8]>::as_ref(share)))
+            .collect::<Vec<_>>(),
+        "share_proofs": [namespace_proof.clone(), namespace_proof],
+        "namespace_id": STANDARD.encode(&namespace.as_bytes()[1..]),
+        "namespace_version": namespace.as_bytes()[0],
+        "row_proof": {
+            "row_roots": [
+                alloy_primitives::hex::encode_upper(row_roots[0]),
+                alloy_primitives::hex::encode_upper(row_roots[1]),
+            ],
+            "proofs": [merkle_proof_json(&dah_leaves, 0), merkle_proof_json(&dah_leaves, 1)],
+            "root": "",
+            "start_row": 0,
+            "end_row": 1,
+        },
+    }))
+    .expect("valid share proof");
+
+    (blob, data_root, share_proof)
+}
+
+/// Returns the [DataAvailabilityHeader] of a square with the given row and column roots.
+fn data_availability_header(
+    row_roots: &[[u8; NAMESPACED_HASH_SIZE]],
+    column_roots: &[[u8; NAMESPACED_HASH_SIZE]],
+) -> DataAvailabilityHeader {
+    let encode = |roots: &[[u8; NAMESPACED_HASH_SIZE]]| -> Vec<String> {
+        roots.iter().map(|root| STANDARD.encode(root)).collect()
+    };
+
+    serde_json::from_value(json!({
+        "row_roots": encode(row_roots),
+        "column_roots": encode(column_roots),
+    }))
+    .expect("valid data availability header")
+}
+
+/// Returns the namespaced hash of a leaf holding `share` in `namespace`.
+fn nmt_leaf(namespace: &[u8], share: &[u8]) -> [u8; NAMESPACED_HASH_SIZE] {
+    let digest = Sha256::new()
+        .chain_update([0x00])
+        .chain_update(namespace)
+        .chain_update(share)
+        .finalize();
+
+    let mut node = [0u8; NAMESPACED_HASH_SIZE];
+    node[..NAMESPACE_SIZE].copy_from_slice(namespace);
+    node[NAMESPACE_SIZE..2 * NAMESPACE_SIZE].copy_from_slice(namespace);
+    node[2 * NAMESPACE_SIZE..].copy_from_slice(&digest);
+    node
+}
+
+/// Returns the namespaced hash of the parent of `left` and `right`, ignoring the parity
+/// namespace in the max namespace as Celestia trees do.
+fn nmt_inner(
+    left: &[u8; NAMESPACED_HASH_SIZE],
+    right: &[u8; NAMESPACED_HASH_SIZE],
+) -> [u8; NAMESPACED_HASH_SIZE] {
+    let min = |node: &[u8; NAMESPACED_HASH_SIZE]| -> [u8; NAMESPACE_SIZE] {
+        node[..NAMESPACE_SIZE].try_into().unwrap()
+    };
+    let max = |node: &[u8; NAMESPACED_HASH_SIZE]| -> [u8; NAMESPACE_SIZE] {
+        node[NAMESPACE_SIZE..2 * NAMESPACE_SIZE].try_into().unwrap()
+    };
+
+    let parent_max = if min(left) == PARITY_NAMESPACE {
+        PARITY_NAMESPACE
+    } else if min(right) == PARITY_NAMESPACE {
+        max(left)
+    } else {
+        max(left).max(max(right))
+    };
+    let digest = Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize();
+
+    let mut node = [0u8; NAMESPACED_HASH_SIZE];
+    node[..NAMESPACE_SIZE].copy_from_slice(&min(left).min(min(right)));
+    node[NAMESPACE_SIZE..2 * NAMESPACE_SIZE].copy_from_slice(&parent_max);
+    node[2 * NAMESPACE_SIZE..].copy_from_slice(&digest);
+    node
+}
+
+/// Returns the [ShareProof] of `share`, the first share of the first row of the square.
+fn share_proof(
+    share: &[u8; SHARE_SIZE],
+    namespace: &Namespace,
+    row_root: &[u8; NAMESPACED_HASH_SIZE],
+    parity_leaf: &[u8; NAMESPACED_HASH_SIZE],
+    dah_leaves: &[&[u8]],
+) -> ShareProof {
+    let row_proof = merkle_proof_json(dah_leaves, 0);
+
+    serde_json::from_value(json!({
+        "data": [STANDARD.encode(share)],
+        "share_proofs": [{
+            "start": 0,
+            "end": 1,
+            "nodes": [STANDARD.encode(parity_leaf)],
+            "leaf_hash": "",
+            "is_max_namespace_ignored": true,
+        }],
+        "namespace_id": STANDARD.encode(&namespace.as_bytes()[1..]),
+        "namespace_version": namespace.as_bytes()[0],
+        "row_proof": {
+            "row_roots": [alloy_primitives::hex::encode_upper(row_root)],
+            "proofs": [row_proof],
+            "root": "",
+            "start_row": 0,
+            "end_row": 0,
+        },
+    }))
+    .expect("valid share proof")
+}
+
+/// Returns the hash of a leaf of a Tendermint merkle tree.
+fn leaf_hash(leaf: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0x00])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+/// Returns the hash of an inner node of a Tendermint merkle tree.
+fn inner_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Returns the root of the Tendermint merkle tree of `leaves`.
+fn merkle_root(leaves: &[&[u8]]) -> [u8; 32] {
+    match leaves {
+        [] => Sha256::digest(b"").into(),
+        [leaf] => leaf_hash(leaf),
+        _ => {
+            let split = split_point(leaves.len());
+            inner_hash(
+                &merkle_root(&leaves[..split]),
+                &merkle_root(&leaves[split..]),
+            )
+        }
+    }
+}
+
+/// Returns the sibling hashes on the path of leaf `index`, from the bottom up.
+fn merkle_aunts(leaves: &[&[u8]], index: usize) -> Vec<[u8; 32]> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+
+    let split = split_point(leaves.len());
+    let (mut aunts, sibling) = if index < split {
+        (
+            merkle_aunts(&leaves[..split], index),
+            merkle_root(&leaves[split..]),
+        )
+    } else {
+        (
+            merkle_aunts(&leaves[split..], index - split),
+            merkle_root(&leaves[..split]),
+        )
+    };
+    aunts.push(sibling);
+    aunts
+}
+
+/// Returns the largest power of two smaller than `len`.
+fn split_point(len: usize) -> usize {
+    let mut split = 1;
+    while split * 2 < len {
+        split *= 2;
+    }
+    split
+}
+
+/// Returns the proof of leaf `index` in the Tendermint merkle tree of `leaves`, as JSON.
+fn merkle_proof_json(leaves: &[&[u8]], index: usize) -> serde_json::Value {
+    let aunts: Vec<String> = merkle_aunts(leaves, index)
+        .iter()
+        .map(|aunt| STANDARD.encode(aunt))
+        .collect();
+
+    json!({
+        "total": leaves.len().to_string(),
+        "index": index.to_string(),
+        "leaf_hash": STANDARD.encode(leaf_hash(leaves[index])),
+        "aunts": aunts,
+    })
+}
+
+/// Returns the proof of leaf `index` in the Tendermint merkle tree of `leaves`.
+fn merkle_proof(leaves: &[&[u8]], index: usize) -> MerkleProof {
+    serde_json::from_value(merkle_proof_json(leaves, index)).expect("valid merkle proof")
+}
+
+/// Returns the leaf node of a Merkle Patricia trie holding `value` as its only entry, under the
+/// hashed key `hashed_key`.
+pub(crate) fn trie_leaf(hashed_key: B256, value: &[u8]) -> Bytes {
+    // The whole key is the leaf path, an even number of nibbles.
+    let mut path = Vec::with_capacity(33);
+    path.push(0x20);
+    path.extend_from_slice(hashed_key.as_slice());
+
+    let mut node = Vec::new();
+    alloy_rlp::Header {
+        list: true,
+        payload_length: path.as_slice().length() + value.length(),
+    }
+    .encode(&mut node);
+    path.as_slice().encode(&mut node);
+    value.encode(&mut node);
+    node.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_proof_validates() {
+        let address = Address::repeat_byte(0xbb);
+        let synthetic = synthetic_proof(address, 100);
+
+        synthetic
+            .proof
+            .validate(synthetic.height, synthetic.l1_head, address)
+            .unwrap();
+        assert_eq!(synthetic.dah.hash(), synthetic.proof.data_root);
+    }
+
+    #[test]
+    fn test_synthetic_two_row_share_proof_verifies() {
+        let (_, data_root, share_proof) = synthetic_two_row_share_proof();
+
+        share_proof.verify(data_root).unwrap();
+        assert_eq!(share_proof.shares().len(), 4);
+        assert_eq!(share_proof.share_proofs().len(), 2);
+    }
+
+    #[test]
+    fn test_merkle_aunts_recompute_the_root() {
+        let leaves: [&[u8]; 5] = [b"a", b"b", b"c", b"d", b"e"];
+        let root = merkle_root(&leaves);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let aunts = merkle_aunts(&leaves, index);
+            let proof = merkle_proof(&leaves, index);
+            assert_eq!(aunts.len(), if index == 4 { 1 } else { 3 });
+            proof.verify(leaf.to_vec(), root).unwrap();
+        }
+    }
+}
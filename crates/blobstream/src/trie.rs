@@ -0,0 +1,186 @@
+//! Merkle Patricia trie proof verification with a configurable node hasher.
+//!
+//! [verify_data_commitment](crate::blobstream::verify_data_commitment) always hashes trie keys
+//! and nodes with keccak256, as every Ethereum chain does. The functions here take the hasher as
+//! a type parameter instead, for experimenting with state tries hashed differently. Real chains
+//! must keep using [Keccak256].
+
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::Decodable;
+use alloy_trie::{nodes::TrieNode, Nibbles, TrieAccount};
+use anyhow::{anyhow, Result};
+
+use crate::{
+    blobstream::{calculate_mapping_slot, expected_storage_value, DATA_COMMITMENTS_SLOT},
+    multiproof::verify_trie_path,
+};
+
+/// The hash function of a Merkle Patricia trie, used both for its keys and its nodes.
+pub trait TrieHasher {
+    /// Hashes `data`.
+    fn hash(data: &[u8]) -> B256;
+}
+
+/// The keccak256 hasher of the Ethereum state and storage tries.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keccak256;
+
+impl TrieHasher for Keccak256 {
+    fn hash(data: &[u8]) -> B256 {
+        keccak256(data)
+    }
+}
+
+/// Verifies that `proof` proves `value` is stored under `key` in the trie with root `root`, the
+/// key and the nodes being hashed with `H`.
+///
+/// Only inclusion proofs are supported, the proof must end in the leaf holding `value`. Nodes
+/// embedded in their parent are not listed in the proof.
+pub fn verify_inclusion<'a, H, I>(root: B256, key: &[u8], value: &[u8], proof: I) -> Result<()>
+where
+    H: TrieHasher,
+    I: IntoIterator<Item = &'a Bytes>,
+{
+    let mut proof = proof.into_iter();
+    verify_trie_path(root, &Nibbles::unpack(H::hash(key)), value, |hash| {
+        let node = proof
+            .next()
+            .ok_or_else(|| anyhow!("Trie proof does not end in a leaf"))?;
+        if H::hash(node) != hash {
+            return Err(anyhow!(
+                "Trie proof node does not match its parent reference"
+            ));
+        }
+        Ok(&node[..])
+    })
+}
+
+/// Same as [verify_commitment_in_storage](crate::blobstream::verify_commitment_in_storage), the
+/// storage trie being hashed with `H`.
+///
+/// Security Note: This function assumes the storage_root comes from a secure source.
+pub fn verify_commitment_in_storage_with<'a, H, I>(
+    storage_root: B256,
+    storage_proof: I,
+    commitment_nonce: U256,
+    expected_commitment: B256,
+) -> Result<()>
+where
+    H: TrieHasher,
+    I: IntoIterator<Item = &'a Bytes>,
+{
+    let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, commitment_nonce);
+    let value = expected_storage_value(expected_commitment)
+        .ok_or_else(|| anyhow!("Data commitment is zero, zero slots are absent from the trie"))?;
+    verify_inclusion::<H, _>(storage_root, slot.as_slice(), &value, storage_proof)
+        .map_err(|e| anyhow!("Storage proof verification failed: {}", e))
+}
+
+/// Returns the account of `address` proven by `account_proof` against `state_root`, the state
+/// trie being hashed with `H`.
+///
+/// Security Note: This function assumes the state_root comes from a secure source.
+pub fn proven_account_with<H: TrieHasher>(
+    state_root: B256,
+    address: Address,
+    account_proof: &[Bytes],
+) -> Result<TrieAccount> {
+    // The account is the value of the leaf terminating the proof.
+    let leaf = account_proof
+        .last()
+        .ok_or_else(|| anyhow!("Account proof is empty"))?;
+    let value = match TrieNode::decode(&mut leaf.as_ref()) {
+        Ok(TrieNode::Leaf(leaf)) => leaf.value,
+        _ => return Err(anyhow!("Account proof does not end in a leaf")),
+    };
+
+    verify_inclusion::<H, _>(state_root, address.as_slice(), &value, account_proof)
+        .map_err(|e| anyhow!("Account proof verification failed: {}", e))?;
+
+    TrieAccount::decode(&mut value.as_slice())
+        .map_err(|e| anyhow!("Invalid account in account proof: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{synthetic_proof, trie_leaf};
+    use alloc::vec;
+    use sha2::{Digest, Sha256};
+
+    /// A hasher no Ethereum trie uses, to tell which hasher the verification ran with.
+    struct Sha256Hasher;
+
+    impl TrieHasher for Sha256Hasher {
+        fn hash(data: &[u8]) -> B256 {
+            B256::from(<[u8; 32]>::from(Sha256::digest(data)))
+        }
+    }
+
+    #[test]
+    fn test_keccak256_verifies_the_ethereum_proofs() {
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let proof = &fixture.proof;
+
+        verify_commitment_in_storage_with::<Keccak256, _>(
+            proof.storage_root,
+            &proof.storage_proof,
+            proof.proof_nonce,
+            proof.data_commitment,
+        )
+        .unwrap();
+        let account = proven_account_with::<Keccak256>(
+            proof.block_header.state_root,
+            fixture.blobstream_address,
+            &proof.account_proof,
+        )
+        .unwrap();
+        assert_eq!(account.storage_root, proof.storage_root);
+    }
+
+    #[test]
+    fn test_verification_uses_the_given_hasher() {
+        let commitment = B256::repeat_byte(0x11);
+        let nonce = U256::from(1);
+        let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, nonce);
+        let leaf = trie_leaf(
+            Sha256Hasher::hash(slot.as_slice()),
+            &expected_storage_value(commitment).unwrap(),
+        );
+        let root = Sha256Hasher::hash(&leaf);
+        let proof = vec![leaf];
+
+        verify_commitment_in_storage_with::<Sha256Hasher, _>(root, &proof, nonce, commitment)
+            .unwrap();
+        assert!(
+            verify_commitment_in_storage_with::<Keccak256, _>(root, &proof, nonce, commitment)
+                .is_err()
+        );
+
+        // A proof of the keccak256 storage trie fails under the other hasher.
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let err = verify_commitment_in_storage_with::<Sha256Hasher, _>(
+            fixture.proof.storage_root,
+            &fixture.proof.storage_proof,
+            fixture.proof.proof_nonce,
+            fixture.proof.data_commitment,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Storage proof verification failed: Trie proof node does not match its parent reference"
+        );
+    }
+
+    #[test]
+    fn test_zero_commitment_is_rejected() {
+        let fixture = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        assert!(verify_commitment_in_storage_with::<Keccak256, _>(
+            fixture.proof.storage_root,
+            &fixture.proof.storage_proof,
+            fixture.proof.proof_nonce,
+            B256::ZERO,
+        )
+        .is_err());
+    }
+}
@@ -1,53 +1,83 @@
 //! [CelestiaDADataSource] an implementation of the [DataAvailabilityProvider] trait.
 
+use crate::pointer::CelestiaBlobPointer;
 use crate::source::CelestiaDASource;
-use crate::traits::CelestiaProvider;
+use crate::traits::{CelestiaProvider, SenderAwareProvider};
 
-use alloc::{boxed::Box, fmt::Debug};
+use alloc::{boxed::Box, collections::BTreeMap, fmt::Debug, string::ToString};
 use alloy_primitives::{Address, Bytes};
 use async_trait::async_trait;
-use celestia_types::Commitment;
+use celestia_types::nmt::Namespace;
 use kona_derive::{
-    BlobProvider, ChainProvider, DataAvailabilityProvider, EthereumDataSource, PipelineResult,
+    BlobProvider, ChainProvider, DataAvailabilityProvider, EthereumDataSource, PipelineError,
+    PipelineResult,
 };
 use kona_protocol::BlockInfo;
+
 /// A factory for creating a Celestia data source provider.
+///
+/// Celestia pointers are read from the base source `D`, which is an [EthereumDataSource] when
+/// created with [CelestiaDADataSource::new], and any other [SenderAwareProvider] yielding the raw
+/// batcher data when created with [CelestiaDADataSource::with_base_source].
 #[derive(Debug, Clone)]
-pub struct CelestiaDADataSource<C, B, A>
+pub struct CelestiaDADataSource<D, A>
 where
-    C: ChainProvider + Send + Clone,
-    B: BlobProvider + Send + Clone,
+    D: DataAvailabilityProvider<Item = Bytes> + Send + Clone,
     A: CelestiaProvider + Send + Clone,
 {
-    /// The blob source.
-    pub ethereum_source: EthereumDataSource<C, B>,
+    /// The source the blob pointers and non-Celestia data are read from.
+    pub base_source: D,
     /// The celestia source.
     pub celestia_source: CelestiaDASource<A>,
+    /// The namespace the pointers sent by each batcher without a namespace are fetched from.
+    pub batcher_namespaces: BTreeMap<Address, Namespace>,
 }
 
-impl<C, B, A> CelestiaDADataSource<C, B, A>
+impl<C, B, A> CelestiaDADataSource<EthereumDataSource<C, B>, A>
 where
-    C: ChainProvider + Send + Clone + Debug,
-    B: BlobProvider + Send + Clone + Debug,
+    C: ChainProvider + Send + Sync + Clone + Debug,
+    B: BlobProvider + Send + Sync + Clone + Debug,
     A: CelestiaProvider + Send + Clone + Debug,
 {
-    /// Creates a [CelestiaDADataSource] from the given sources.
+    /// Creates a [CelestiaDADataSource] reading blob pointers from L1.
     pub const fn new(
         ethereum_source: EthereumDataSource<C, B>,
         celestia_source: CelestiaDASource<A>,
     ) -> Self {
+        Self::with_base_source(ethereum_source, celestia_source)
+    }
+}
+
+impl<D, A> CelestiaDADataSource<D, A>
+where
+    D: DataAvailabilityProvider<Item = Bytes> + Send + Clone + Debug,
+    A: CelestiaProvider + Send + Clone + Debug,
+{
+    /// Creates a [CelestiaDADataSource] reading blob pointers from `base_source`.
+    pub const fn with_base_source(base_source: D, celestia_source: CelestiaDASource<A>) -> Self {
         Self {
-            ethereum_source,
+            base_source,
             celestia_source,
+            batcher_namespaces: BTreeMap::new(),
         }
     }
+
+    /// Fetches the blobs of the pointers sent by `batcher` without a namespace from
+    /// `namespace`, for rollups posting to several namespaces in one derivation.
+    ///
+    /// Pointers carrying a namespace are always fetched from it, and pointers of other batchers
+    /// from the provider's configured namespace. Either way the namespace the blob is fetched from
+    /// is checked against its share proof.
+    pub fn with_batcher_namespace(mut self, batcher: Address, namespace: Namespace) -> Self {
+        self.batcher_namespaces.insert(batcher, namespace);
+        self
+    }
 }
 
 #[async_trait]
-impl<C, B, A> DataAvailabilityProvider for CelestiaDADataSource<C, B, A>
+impl<D, A> DataAvailabilityProvider for CelestiaDADataSource<D, A>
 where
-    C: ChainProvider + Send + Sync + Clone + Debug,
-    B: BlobProvider + Send + Sync + Clone + Debug,
+    D: SenderAwareProvider<Item = Bytes> + Send + Sync + Clone + Debug,
     A: CelestiaProvider + Send + Sync + Clone + Debug,
 {
     type Item = Bytes;
@@ -57,28 +87,32 @@ where
         block_ref: &BlockInfo,
         batcher_address: Address,
     ) -> PipelineResult<Self::Item> {
-        // Feth Blob pointer from the Ethereum Data Source
-        let pointer_data = self
-            .ethereum_source
-            .next(block_ref, batcher_address)
+        // Feth Blob pointer from the base source. Whether the batcher posted it as calldata or in
+        // an EIP-4844 blob, the Ethereum source yields exactly the bytes the batcher submitted
+        // (blob data is decoded back to its original length), so the pointer is always the whole
+        // item and no carrier specific framing has to be stripped.
+        let (pointer_data, sender) = self
+            .base_source
+            .next_with_sender(block_ref, batcher_address)
             .await?;
 
-        let blob = if pointer_data[0] == 0x01 && pointer_data[1] == 0x01 && pointer_data[2] == 0x0c
-        {
-            let height_bytes = &pointer_data[3..11];
-            let height = u64::from_le_bytes(height_bytes.try_into().unwrap());
-            let hash_array: [u8; 32] = pointer_data[11..43]
-                .try_into()
-                .expect("Slice must be 32 bytes");
-            let commitment = Commitment::new(hash_array);
+        let pointer = CelestiaBlobPointer::decode(&pointer_data)
+            .map_err(|e| PipelineError::Provider(e.to_string()).crit())?;
 
-            info!("Fetching celestia blob at height: {:?}", height);
-            let celestia_blob = self.celestia_source.next(height, commitment).await?;
+        let blob = if let Some(pointer) = pointer {
+            info!("Fetching celestia blob at height: {:?}", pointer.height);
+            let namespace = pointer
+                .namespace
+                .or_else(|| self.batcher_namespaces.get(&sender).copied());
+            let celestia_blob = self
+                .celestia_source
+                .next(pointer.height, namespace, pointer.commitment)
+                .await?;
 
             celestia_blob
         } else {
             info!(
-                "Fetching data from Ethereum Source at ref: {:?}",
+                "Fetching data from the base source at ref: {:?}",
                 block_ref.number
             );
             pointer_data
@@ -89,6 +123,176 @@ where
 
     fn clear(&mut self) {
         self.celestia_source.clear();
-        self.ethereum_source.clear();
+        self.base_source.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        commitment, commitment_in, namespace, FixedProvider, NamespacedProvider,
+    };
+    use alloc::{collections::VecDeque, vec};
+    use alloy_consensus::{transaction::SignerRecoverable, Signed, TxEnvelope, TxLegacy};
+    use alloy_primitives::{Signature, TxKind, B256};
+    use kona_derive::test_utils::{TestBlobProvider, TestChainProvider};
+    use kona_genesis::RollupConfig;
+
+    const BATCHER: Address = Address::repeat_byte(0xba);
+
+    /// A base source yielding queued items, each with the sender of its transaction.
+    #[derive(Debug, Clone, Default)]
+    struct QueuedSource(VecDeque<(Bytes, Address)>);
+
+    #[async_trait]
+    impl DataAvailabilityProvider for QueuedSource {
+        type Item = Bytes;
+
+        async fn next(&mut self, block_ref: &BlockInfo, batcher: Address) -> PipelineResult<Bytes> {
+            Ok(self.next_with_sender(block_ref, batcher).await?.0)
+        }
+
+        fn clear(&mut self) {
+            self.0.clear();
+        }
+    }
+
+    #[async_trait]
+    impl SenderAwareProvider for QueuedSource {
+        async fn next_with_sender(
+            &mut self,
+            _: &BlockInfo,
+            _: Address,
+        ) -> PipelineResult<(Bytes, Address)> {
+            self.0.pop_front().ok_or(PipelineError::Eof.temp())
+        }
+    }
+
+    /// Returns a source yielding a pointer to `blob` sent by `sender`.
+    fn pointer_source(
+        blob: &Bytes,
+        sender: Address,
+    ) -> (CelestiaDADataSource<QueuedSource, FixedProvider>, Bytes) {
+        let pointer: Bytes = CelestiaBlobPointer::new_namespaced(1, commitment(blob), namespace())
+            .encode()
+            .into();
+        let base = QueuedSource([(pointer.clone(), sender)].into_iter().collect());
+        let celestia = CelestiaDASource::new(FixedProvider(blob.clone()));
+        (
+            CelestiaDADataSource::with_base_source(base, celestia),
+            pointer,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_follows_pointer() {
+        let blob = Bytes::from_static(b"referenced");
+        let (mut source, _) = pointer_source(&blob, BATCHER);
+
+        let item = source.next(&BlockInfo::default(), BATCHER).await.unwrap();
+        assert_eq!(item, blob);
+    }
+
+    #[tokio::test]
+    async fn test_passes_base_data_through_and_clears_both_sources() {
+        let frame = Bytes::from_static(b"\x00not a pointer");
+        let mut source = CelestiaDADataSource::with_base_source(
+            QueuedSource([(frame.clone(), BATCHER)].into_iter().collect()),
+            CelestiaDASource::new(FixedProvider(Bytes::from_static(b"unused"))),
+        );
+
+        // Data other than a Celestia pointer is yielded as the base source returned it.
+        let block = BlockInfo::default();
+        assert_eq!(source.next(&block, BATCHER).await.unwrap(), frame);
+
+        source.base_source.0.push_back((frame, BATCHER));
+        source
+            .celestia_source
+            .data
+            .push(Bytes::from_static(b"buffered"));
+        source.clear();
+        assert!(source.base_source.0.is_empty());
+        assert!(source.celestia_source.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_derives_from_two_namespaces_in_one_run() {
+        let (batches, blobs) = (namespace(), Namespace::new_v0(b"blobs").unwrap());
+        let (batch, blob) = (Bytes::from_static(b"batch"), Bytes::from_static(b"blob"));
+        let blob_batcher = Address::repeat_byte(0xbb);
+
+        // A pointer carrying its namespace, and one routed by the batcher that sent it.
+        let namespaced: Bytes = CelestiaBlobPointer::new_namespaced(1, commitment(&batch), batches)
+            .encode()
+            .into();
+        let routed: Bytes = CelestiaBlobPointer::new(1, commitment_in(blobs, &blob))
+            .encode()
+            .into();
+        let base = QueuedSource(
+            [(namespaced, BATCHER), (routed, blob_batcher)]
+                .into_iter()
+                .collect(),
+        );
+        let provider = NamespacedProvider(vec![(batches, batch.clone()), (blobs, blob.clone())]);
+        // Strict, so every blob is checked against the namespace it was requested from.
+        let celestia = CelestiaDASource::new(provider).with_strict(true);
+        let mut source = CelestiaDADataSource::with_base_source(base, celestia)
+            .with_batcher_namespace(blob_batcher, blobs);
+
+        let block = BlockInfo::default();
+        assert_eq!(source.next(&block, BATCHER).await.unwrap(), batch);
+        assert_eq!(source.next(&block, BATCHER).await.unwrap(), blob);
+    }
+
+    /// Returns an L1 chain whose only block holds a legacy transaction posting `data` to the
+    /// batch inbox, along with the block and the recovered signer of the transaction.
+    fn inbox_chain(config: &RollupConfig, data: Bytes) -> (TestChainProvider, BlockInfo, Address) {
+        let tx = TxLegacy {
+            to: TxKind::Call(config.batch_inbox_address),
+            input: data,
+            ..Default::default()
+        };
+        let tx = TxEnvelope::Legacy(Signed::new_unchecked(
+            tx,
+            Signature::test_signature(),
+            B256::ZERO,
+        ));
+        let signer = tx.recover_signer().unwrap();
+        let block = BlockInfo {
+            hash: B256::repeat_byte(1),
+            number: 1,
+            ..Default::default()
+        };
+        let mut chain = TestChainProvider::default();
+        chain.insert_block_with_transactions(1, block, vec![tx]);
+        (chain, block, signer)
+    }
+
+    #[tokio::test]
+    async fn test_ignores_pointer_sent_by_another_account() {
+        let blob = Bytes::from_static(b"referenced");
+        let pointer: Bytes = CelestiaBlobPointer::new_namespaced(1, commitment(&blob), namespace())
+            .encode()
+            .into();
+        let config = RollupConfig::default();
+        let (chain, block, signer) = inbox_chain(&config, pointer);
+        let ethereum =
+            EthereumDataSource::new_from_parts(chain, TestBlobProvider::default(), &config);
+        let source =
+            CelestiaDADataSource::new(ethereum, CelestiaDASource::new(FixedProvider(blob.clone())));
+
+        // The pointer posted by the batcher is followed.
+        let item = source.clone().next(&block, signer).await.unwrap();
+        assert_eq!(item, blob);
+
+        // The Ethereum source drops the transactions not signed by the batcher, so the same
+        // pointer never reaches the Celestia source when another account posted it.
+        let err = source
+            .clone()
+            .next(&block, Address::repeat_byte(0xee))
+            .await
+            .unwrap_err();
+        assert_eq!(err, PipelineError::Eof.temp());
     }
 }
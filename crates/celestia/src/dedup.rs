@@ -0,0 +1,254 @@
+//! A [CelestiaProvider] wrapper coalescing concurrent identical blob requests.
+
+use crate::CelestiaProvider;
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec};
+use alloy_primitives::Bytes;
+use async_trait::async_trait;
+use celestia_types::{nmt::Namespace, Commitment};
+use core::future::Future;
+use futures::channel::oneshot;
+use spin::Mutex;
+
+/// A request in flight: the height, the commitment and the explicit namespace, if any.
+type RequestKey = (u64, [u8; 32], Option<Vec<u8>>);
+
+/// A fetched blob, along with the namespace the inner provider reported for it, if any.
+type Fetched = (Option<Namespace>, Bytes);
+
+/// The callers waiting on each request in flight.
+type Waiters = BTreeMap<RequestKey, Vec<oneshot::Sender<Fetched>>>;
+
+/// Wraps a [CelestiaProvider] so that concurrent requests for the same blob share a single fetch.
+///
+/// The first caller for a key fetches the blob, later callers for the same key wait for it and
+/// receive a copy of the blob. Errors are not shared: if the fetch fails, or the first caller is
+/// dropped before it completes, every waiting caller makes its own request.
+///
+/// Only requests that overlap in time are coalesced, sequential repeats reach the inner provider.
+#[derive(Debug, Clone)]
+pub struct DedupCelestiaProvider<P> {
+    inner: P,
+    in_flight: Arc<Mutex<Waiters>>,
+}
+
+impl<P> DedupCelestiaProvider<P> {
+    /// Wraps `inner`.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            in_flight: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Returns the wrapped provider.
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+impl<P> DedupCelestiaProvider<P>
+where
+    P: CelestiaProvider + Send + Sync,
+{
+    async fn coalesce<F, Fut>(&self, key: RequestKey, fetch: F) -> Result<Fetched, P::Error>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<Fetched, P::Error>> + Send,
+    {
+        let waiter = {
+            let mut in_flight = self.in_flight.lock();
+            match in_flight.get_mut(&key) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    in_flight.insert(key.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+
+        if let Some(receiver) = waiter {
+            if let Ok(blob) = receiver.await {
+                return Ok(blob);
+            }
+            debug!(target: "celestia-dedup", "coalesced blob request failed, fetching it again");
+            return fetch().await;
+        }
+
+        let guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            key: Some(key),
+        };
+        let result = fetch().await;
+        let waiters = guard.finish();
+        if let Ok(ref blob) = result {
+            for waiter in waiters {
+                // The waiting caller may have been dropped in the meantime.
+                let _ = waiter.send(blob.clone());
+            }
+        }
+        result
+    }
+}
+
+/// Removes a request from the in flight map once it completes or is dropped. Dropping the
+/// waiters' senders wakes them up so they fetch the blob themselves.
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<Waiters>,
+    key: Option<RequestKey>,
+}
+
+impl InFlightGuard<'_> {
+    fn finish(mut self) -> Vec<oneshot::Sender<Fetched>> {
+        let key = self.key.take().expect("key is only taken once");
+        self.in_flight.lock().remove(&key).unwrap_or_default()
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight.lock().remove(&key);
+        }
+    }
+}
+
+#[async_trait]
+impl<P> CelestiaProvider for DedupCelestiaProvider<P>
+where
+    P: CelestiaProvider + Send + Sync,
+{
+    type Error = P::Error;
+
+    async fn blob_get(&self, height: u64, commitment: Commitment) -> Result<Bytes, Self::Error> {
+        let (_, blob) = self.blob_get_with_namespace(height, commitment).await?;
+        Ok(blob)
+    }
+
+    async fn blob_get_with_namespace(
+        &self,
+        height: u64,
+        commitment: Commitment,
+    ) -> Result<(Option<Namespace>, Bytes), Self::Error> {
+        let key = (height, *commitment.hash(), None);
+        self.coalesce(key, || {
+            self.inner.blob_get_with_namespace(height, commitment)
+        })
+        .await
+    }
+
+    async fn blob_get_namespaced(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: Commitment,
+    ) -> Result<Bytes, Self::Error> {
+        let key = (
+            height,
+            *commitment.hash(),
+            Some(namespace.as_bytes().to_vec()),
+        );
+        let (_, blob) = self
+            .coalesce(key, || async move {
+                let blob = self
+                    .inner
+                    .blob_get_namespaced(height, namespace, commitment)
+                    .await?;
+                Ok((Some(namespace), blob))
+            })
+            .await?;
+        Ok(blob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::NeverFails;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A provider counting its requests, each suspending once before returning the blob so that
+    /// concurrent requests overlap.
+    #[derive(Debug, Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    impl CountingProvider {
+        async fn fetch(&self) -> Result<Bytes, NeverFails> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+            Ok(Bytes::from_static(b"blob"))
+        }
+    }
+
+    #[async_trait]
+    impl CelestiaProvider for CountingProvider {
+        type Error = NeverFails;
+
+        async fn blob_get(&self, _: u64, _: Commitment) -> Result<Bytes, Self::Error> {
+            self.fetch().await
+        }
+
+        async fn blob_get_namespaced(
+            &self,
+            _: u64,
+            _: Namespace,
+            _: Commitment,
+        ) -> Result<Bytes, Self::Error> {
+            self.fetch().await
+        }
+    }
+
+    fn calls(provider: &DedupCelestiaProvider<CountingProvider>) -> usize {
+        provider.inner().calls.load(Ordering::Relaxed)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_identical_requests_share_one_fetch() {
+        let provider = DedupCelestiaProvider::new(CountingProvider::default());
+        let commitment = Commitment::new([1; 32]);
+
+        let (first, second, third) = tokio::join!(
+            provider.blob_get(7, commitment),
+            provider.blob_get(7, commitment),
+            provider.blob_get(7, commitment),
+        );
+
+        assert_eq!(calls(&provider), 1);
+        for blob in [first, second, third] {
+            assert_eq!(blob.unwrap(), Bytes::from_static(b"blob"));
+        }
+        assert!(provider.in_flight.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_distinct_requests_are_not_coalesced() {
+        let provider = DedupCelestiaProvider::new(CountingProvider::default());
+        let commitment = Commitment::new([1; 32]);
+        let namespace = Namespace::new_v0(b"hana").unwrap();
+
+        let (first, second, third) = tokio::join!(
+            provider.blob_get(7, commitment),
+            provider.blob_get(8, commitment),
+            provider.blob_get_namespaced(7, namespace, commitment),
+        );
+
+        assert_eq!(calls(&provider), 3);
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests_are_not_coalesced() {
+        let provider = DedupCelestiaProvider::new(CountingProvider::default());
+        let commitment = Commitment::new([1; 32]);
+
+        provider.blob_get(7, commitment).await.unwrap();
+        provider.blob_get(7, commitment).await.unwrap();
+
+        assert_eq!(calls(&provider), 2);
+    }
+}
@@ -9,10 +9,22 @@ extern crate alloc;
 extern crate tracing;
 
 mod traits;
-pub use traits::CelestiaProvider;
+pub use traits::{CelestiaProvider, SenderAwareProvider};
+
+mod pointer;
+pub use pointer::{
+    CelestiaBlobPointer, PointerDecodeError, CELESTIA_DA_VERSION_BYTE,
+    CELESTIA_NAMESPACED_DA_VERSION_BYTE, DERIVATION_VERSION_1, GENERIC_COMMITMENT_TYPE,
+};
 
 mod source;
-pub use source::CelestiaDASource;
+pub use source::{BlobManifest, CelestiaDASource};
 
 mod celestia;
 pub use celestia::CelestiaDADataSource;
+
+mod dedup;
+pub use dedup::DedupCelestiaProvider;
+
+#[cfg(test)]
+mod test_utils;
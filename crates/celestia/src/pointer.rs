@@ -0,0 +1,227 @@
+//! Encoding and decoding of the Celestia blob pointers posted to L1 by the batcher.
+
+use alloc::vec::Vec;
+use celestia_types::{nmt::Namespace, nmt::NS_SIZE, Commitment};
+
+/// The derivation version byte prefixing every alt-DA pointer posted to L1.
+pub const DERIVATION_VERSION_1: u8 = 0x01;
+
+/// The commitment type byte of a generic alt-DA commitment.
+pub const GENERIC_COMMITMENT_TYPE: u8 = 0x01;
+
+/// The DA layer byte identifying a Celestia blob pointer.
+pub const CELESTIA_DA_VERSION_BYTE: u8 = 0x0c;
+
+/// The DA layer byte identifying a Celestia blob pointer that also carries the namespace.
+pub const CELESTIA_NAMESPACED_DA_VERSION_BYTE: u8 = 0x0d;
+
+/// The length of a pointer that only references a height and commitment.
+const POINTER_LEN: usize = 3 + 8 + 32;
+
+/// The length of a pointer that additionally carries a namespace.
+const NAMESPACED_POINTER_LEN: usize = POINTER_LEN + NS_SIZE;
+
+/// A reference to a Celestia blob posted by the batcher.
+///
+/// Two versions are supported, distinguished by the DA layer byte:
+/// - [CELESTIA_DA_VERSION_BYTE] (`0x01 0x01 0x0c | height (8 bytes, LE) | commitment (32 bytes)`)
+/// - [CELESTIA_NAMESPACED_DA_VERSION_BYTE]
+///   (`0x01 0x01 0x0d | height (8 bytes, LE) | commitment (32 bytes) | namespace (29 bytes)`)
+///
+/// The height is little-endian, as in the oracle hints the blob is requested with, while the
+/// data root tuples committed to by Blobstream encode it big-endian.
+///
+/// Pointers without a namespace are fetched from the namespace configured on the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CelestiaBlobPointer {
+    /// The Celestia height the blob was included at.
+    pub height: u64,
+    /// The blob commitment.
+    pub commitment: Commitment,
+    /// The namespace the blob was posted to, if carried by the pointer.
+    pub namespace: Option<Namespace>,
+}
+
+/// An error decoding a [CelestiaBlobPointer].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PointerDecodeError {
+    /// The pointer length does not match its version.
+    #[error("invalid celestia pointer length {actual}, expected {expected}")]
+    InvalidLength {
+        /// The expected length for the pointer version.
+        expected: usize,
+        /// The length of the data received.
+        actual: usize,
+    },
+    /// The namespace bytes carried by the pointer are not a valid namespace.
+    #[error("invalid namespace in celestia pointer")]
+    InvalidNamespace,
+}
+
+impl CelestiaBlobPointer {
+    /// Creates a pointer to a blob in the provider's configured namespace.
+    pub const fn new(height: u64, commitment: Commitment) -> Self {
+        Self {
+            height,
+            commitment,
+            namespace: None,
+        }
+    }
+
+    /// Creates a pointer to a blob in an explicit namespace.
+    pub const fn new_namespaced(height: u64, commitment: Commitment, namespace: Namespace) -> Self {
+        Self {
+            height,
+            commitment,
+            namespace: Some(namespace),
+        }
+    }
+
+    /// Encodes the pointer into the bytes posted by the batcher.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(NAMESPACED_POINTER_LEN);
+        let da_version = match self.namespace {
+            None => CELESTIA_DA_VERSION_BYTE,
+            Some(_) => CELESTIA_NAMESPACED_DA_VERSION_BYTE,
+        };
+        encoded.extend_from_slice(&[DERIVATION_VERSION_1, GENERIC_COMMITMENT_TYPE, da_version]);
+        encoded.extend_from_slice(&self.height.to_le_bytes());
+        encoded.extend_from_slice(self.commitment.hash());
+        if let Some(namespace) = self.namespace {
+            encoded.extend_from_slice(namespace.as_bytes());
+        }
+        encoded
+    }
+
+    /// Decodes a pointer from the data posted by the batcher.
+    ///
+    /// `data` must be the whole item posted by the batcher, either its calldata or its decoded
+    /// EIP-4844 blob data. Data with a Celestia prefix but trailing or missing bytes is rejected
+    /// rather than parsed at fixed offsets, so a misframed pointer can't resolve to another
+    /// height.
+    ///
+    /// Returns `Ok(None)` if the data is not a Celestia pointer.
+    pub fn decode(data: &[u8]) -> Result<Option<Self>, PointerDecodeError> {
+        let expected = match data {
+            [DERIVATION_VERSION_1, GENERIC_COMMITMENT_TYPE, CELESTIA_DA_VERSION_BYTE, ..] => {
+                POINTER_LEN
+            }
+            [DERIVATION_VERSION_1, GENERIC_COMMITMENT_TYPE, CELESTIA_NAMESPACED_DA_VERSION_BYTE, ..] => {
+                NAMESPACED_POINTER_LEN
+            }
+            _ => return Ok(None),
+        };
+        if data.len() != expected {
+            return Err(PointerDecodeError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let height = u64::from_le_bytes(data[3..11].try_into().expect("Slice must be 8 bytes"));
+        let hash_array: [u8; 32] = data[11..43].try_into().expect("Slice must be 32 bytes");
+        let commitment = Commitment::new(hash_array);
+
+        let namespace = if expected == NAMESPACED_POINTER_LEN {
+            let namespace = Namespace::from_raw(&data[43..])
+                .map_err(|_| PointerDecodeError::InvalidNamespace)?;
+            Some(namespace)
+        } else {
+            None
+        };
+
+        Ok(Some(Self {
+            height,
+            commitment,
+            namespace,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_height_round_trip() {
+        let namespace = Namespace::new_v0(b"hana-test").unwrap();
+        let commitment = Commitment::new([0x11; 32]);
+        for height in [0, 1, u64::MAX] {
+            for pointer in [
+                CelestiaBlobPointer::new(height, commitment),
+                CelestiaBlobPointer::new_namespaced(height, commitment, namespace),
+            ] {
+                let encoded = pointer.encode();
+
+                // The height follows the 3 prefix bytes, little-endian.
+                assert_eq!(encoded[3..11], height.to_le_bytes());
+                assert_eq!(
+                    CelestiaBlobPointer::decode(&encoded).unwrap(),
+                    Some(pointer)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_namespaced_pointer_round_trip() {
+        let namespace = Namespace::new_v0(b"hana-test").unwrap();
+        let pointer =
+            CelestiaBlobPointer::new_namespaced(7, Commitment::new([0x11; 32]), namespace);
+
+        let encoded = pointer.encode();
+        assert_eq!(encoded.len(), NAMESPACED_POINTER_LEN);
+        assert_eq!(
+            encoded[..3],
+            [
+                DERIVATION_VERSION_1,
+                GENERIC_COMMITMENT_TYPE,
+                CELESTIA_NAMESPACED_DA_VERSION_BYTE
+            ]
+        );
+        assert_eq!(encoded[11..43], [0x11; 32]);
+        assert_eq!(encoded[43..], *namespace.as_bytes());
+
+        let decoded = CelestiaBlobPointer::decode(&encoded).unwrap().unwrap();
+        assert_eq!(decoded, pointer);
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    fn test_namespaced_pointer_decode_rejects_bad_namespaces() {
+        let namespace = Namespace::new_v0(b"hana-test").unwrap();
+        let encoded =
+            CelestiaBlobPointer::new_namespaced(7, Commitment::new([0x11; 32]), namespace).encode();
+
+        // A 0x0d pointer without its namespace.
+        assert_eq!(
+            CelestiaBlobPointer::decode(&encoded[..POINTER_LEN]),
+            Err(PointerDecodeError::InvalidLength {
+                expected: NAMESPACED_POINTER_LEN,
+                actual: POINTER_LEN,
+            })
+        );
+
+        // A version 0 namespace must start with zero bytes.
+        let mut invalid = encoded;
+        invalid[POINTER_LEN + 1] = 0xff;
+        assert_eq!(
+            CelestiaBlobPointer::decode(&invalid),
+            Err(PointerDecodeError::InvalidNamespace)
+        );
+    }
+
+    #[test]
+    fn test_pointer_decode_rejects_misframed_data() {
+        let mut encoded = CelestiaBlobPointer::new(1, Commitment::new([0x11; 32])).encode();
+        encoded.push(0);
+        assert_eq!(
+            CelestiaBlobPointer::decode(&encoded),
+            Err(PointerDecodeError::InvalidLength {
+                expected: POINTER_LEN,
+                actual: POINTER_LEN + 1,
+            })
+        );
+        assert_eq!(CelestiaBlobPointer::decode(b"not a pointer"), Ok(None));
+    }
+}
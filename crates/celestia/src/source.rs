@@ -1,12 +1,18 @@
 //! Celestia Data source
 
-use crate::traits::CelestiaProvider;
+use crate::{pointer::CelestiaBlobPointer, traits::CelestiaProvider};
 
-use alloc::vec::Vec;
+use alloc::{format, sync::Arc, vec::Vec};
 use alloy_primitives::Bytes;
-use celestia_types::Commitment;
+use celestia_types::{
+    consts::appconsts::SHARE_VERSION_ZERO, nmt::Namespace, AppVersion, Commitment,
+};
 use kona_derive::{PipelineError, PipelineErrorKind, PipelineResult};
 
+/// A log of the blobs fetched by a [CelestiaDASource], in fetch order, shared with whoever
+/// inspects it after the run.
+pub type BlobManifest = Arc<spin::Mutex<Vec<CelestiaBlobPointer>>>;
+
 /// Data source for Celestia DA
 #[derive(Debug, Clone)]
 pub struct CelestiaDASource<C>
@@ -17,23 +23,73 @@ where
     pub celestia_fetcher: C,
     /// Celestia Blobs
     pub data: Vec<Bytes>,
+    /// Whether to only check blobs against a namespace known to the source, rather than the one
+    /// the provider reports
+    pub strict: bool,
+    /// The namespace blobs referenced without one are fetched from, if known
+    pub namespace: Option<Namespace>,
+    /// The log every fetched blob is recorded to, if any
+    pub manifest: Option<BlobManifest>,
 }
 
 impl<C> CelestiaDASource<C>
 where
-    C: CelestiaProvider + Send,
+    C: CelestiaProvider + Send + Sync,
 {
     /// Creates a new celestia source.
     pub const fn new(celestia_fetcher: C) -> Self {
         Self {
             celestia_fetcher,
             data: Vec::new(),
+            strict: false,
+            namespace: None,
+            manifest: None,
         }
     }
 
+    /// Enables strict mode, in which blobs are only checked against the namespace of their pointer
+    /// or the one set with [CelestiaDASource::with_namespace].
+    ///
+    /// Every blob is checked against the commitment of the pointer that referenced it before it
+    /// is buffered, and rejected when its namespace is unknown, so that derivation never consumes
+    /// a blob the block didn't reference. Outside of strict mode, blobs referenced without a
+    /// namespace are checked against the namespace the provider reports, see
+    /// [CelestiaProvider::blob_get_with_namespace].
+    pub const fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the namespace the provider fetches blobs referenced without a namespace from, so
+    /// that their commitment can be checked as well.
+    pub const fn with_namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Records the height, commitment and namespace of every blob fetched by the source to
+    /// `manifest`, e.g. to pin the Celestia data a derivation depended on. The namespace is the
+    /// one of the pointer, `None` for blobs fetched from the provider's configured namespace.
+    pub fn with_manifest(mut self, manifest: BlobManifest) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
     /// Fetches the next blob from the source.
-    pub async fn next(&mut self, height: u64, commitment: Commitment) -> PipelineResult<Bytes> {
-        self.load_blobs(height, commitment).await?;
+    ///
+    /// The blob is only returned if it recomputes to `commitment`. Blobs referenced without a
+    /// namespace are checked in the provider's namespace, set with
+    /// [CelestiaDASource::with_namespace] or reported by the provider, see
+    /// [CelestiaDASource::with_strict].
+    ///
+    /// If no namespace is given, the blob is fetched from the provider's configured namespace.
+    pub async fn next(
+        &mut self,
+        height: u64,
+        namespace: Option<Namespace>,
+        commitment: Commitment,
+    ) -> PipelineResult<Bytes> {
+        self.load_blobs(height, namespace, commitment).await?;
         let next_data = match self.next_data() {
             Ok(d) => d,
             Err(e) => return e,
@@ -52,15 +108,54 @@ where
     async fn load_blobs(
         &mut self,
         height: u64,
+        namespace: Option<Namespace>,
         commitment: Commitment,
     ) -> Result<(), PipelineErrorKind> {
         info!(target: "celestia-source", "fetching blobs from celestia fetcher");
-        let blob = self.celestia_fetcher.blob_get(height, commitment).await;
+        let blob = match namespace {
+            Some(namespace) => self
+                .celestia_fetcher
+                .blob_get_namespaced(height, namespace, commitment)
+                .await
+                .map(|blob| (None, blob)),
+            None => {
+                self.celestia_fetcher
+                    .blob_get_with_namespace(height, commitment)
+                    .await
+            }
+        };
         match blob {
-            Ok(blob) => {
+            Ok((reported, blob)) => {
+                let checked_in = namespace
+                    .or(self.namespace)
+                    .or(reported.filter(|_| !self.strict));
+                let Some(checked_in) = checked_in else {
+                    return Err(PipelineError::Provider(format!(
+                        "namespace unknown, can't check the celestia blob at height {} against its pointer",
+                        height
+                    ))
+                    .crit());
+                };
+                verify_commitment(height, checked_in, &blob, commitment)?;
+
                 self.data.push(blob.clone());
 
-                info!(target: "celestia-source", "load_blobs {:?}", self.data);
+                if let Some(manifest) = &self.manifest {
+                    manifest.lock().push(CelestiaBlobPointer {
+                        height,
+                        commitment,
+                        namespace,
+                    });
+                }
+
+                debug!(
+                    target: "celestia-source",
+                    "loaded blob of {} bytes at height {} with commitment {:?}",
+                    blob.len(),
+                    height,
+                    commitment
+                );
+                trace!(target: "celestia-source", "load_blobs {:?}", self.data);
 
                 Ok(())
             }
@@ -91,3 +186,231 @@ where
         Ok(self.data.remove(0))
     }
 }
+
+/// Ensures `blob`, fetched from `namespace` at `height`, recomputes to `commitment`, binding the
+/// blob handed to derivation to the commitment of the pointer that referenced it.
+fn verify_commitment(
+    height: u64,
+    namespace: Namespace,
+    blob: &[u8],
+    commitment: Commitment,
+) -> Result<(), PipelineErrorKind> {
+    let computed = Commitment::from_blob(
+        namespace,
+        blob,
+        SHARE_VERSION_ZERO,
+        None,
+        AppVersion::latest(),
+    )
+    .map_err(|e| {
+        PipelineError::Provider(format!(
+            "failed to compute the commitment of the celestia blob at height {height}: {e}"
+        ))
+        .crit()
+    })?;
+
+    if computed != commitment {
+        return Err(PipelineError::Provider(format!(
+            "celestia blob at height {} has commitment {:?}, expected {:?}",
+            height, computed, commitment
+        ))
+        .crit());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        commitment, commitment_in, namespace, FixedProvider, NamespacedProvider, ReportingProvider,
+    };
+    use alloc::{
+        string::{String, ToString},
+        vec,
+    };
+    use spin::Mutex;
+
+    /// A subscriber recording the messages of the events up to a level.
+    #[derive(Debug, Clone)]
+    struct MessageRecorder {
+        level: tracing::Level,
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MessageRecorder {
+        fn new(level: tracing::Level) -> Self {
+            Self {
+                level,
+                messages: Default::default(),
+            }
+        }
+    }
+
+    impl tracing::field::Visit for MessageRecorder {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn core::fmt::Debug) {
+            if field.name() == "message" {
+                self.messages.lock().push(format!("{value:?}"));
+            }
+        }
+    }
+
+    impl tracing::Subscriber for MessageRecorder {
+        fn register_callsite(
+            &self,
+            _: &'static tracing::Metadata<'static>,
+        ) -> tracing::subscriber::Interest {
+            // The recorders of the test filter different levels, don't cache their interest.
+            tracing::subscriber::Interest::sometimes()
+        }
+
+        fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+            *metadata.level() <= self.level
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_blob_contents_are_only_logged_at_trace_level() {
+        let blob = Bytes::from_static(b"hana-blob");
+        let contents = blob.to_string();
+
+        for (level, logged) in [
+            (tracing::Level::INFO, false),
+            (tracing::Level::DEBUG, false),
+            (tracing::Level::TRACE, true),
+        ] {
+            let recorder = MessageRecorder::new(level);
+            let _guard = tracing::subscriber::set_default(recorder.clone());
+            CelestiaDASource::new(FixedProvider(blob.clone()))
+                .with_namespace(namespace())
+                .next(1, None, commitment(&blob))
+                .await
+                .unwrap();
+
+            let messages = recorder.messages.lock();
+            assert!(!messages.is_empty(), "{level}");
+            assert_eq!(
+                messages.iter().any(|message| message.contains(&contents)),
+                logged,
+                "{level}: {messages:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_rejects_blob_not_matching_its_pointer() {
+        let mut source = CelestiaDASource::new(FixedProvider(Bytes::from_static(b"unexpected")))
+            .with_strict(true);
+
+        let err = source
+            .next(1, Some(namespace()), commitment(b"referenced"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PipelineErrorKind::Critical(_)));
+        assert!(source.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_blob_that_cannot_be_checked() {
+        let blob = Bytes::from_static(b"referenced");
+        for strict in [true, false] {
+            let mut source = CelestiaDASource::new(FixedProvider(blob.clone())).with_strict(strict);
+            let err = source.next(1, None, commitment(&blob)).await.unwrap_err();
+            assert!(matches!(err, PipelineErrorKind::Critical(_)), "{strict}");
+            assert!(source.data.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checks_blob_against_the_reported_namespace() {
+        let blob = Bytes::from_static(b"referenced");
+        let mut source = CelestiaDASource::new(ReportingProvider(blob.clone()));
+        assert_eq!(source.next(1, None, commitment(&blob)).await.unwrap(), blob);
+
+        let err = source
+            .next(1, None, commitment(b"other"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PipelineErrorKind::Critical(_)));
+
+        // Strict mode doesn't take the provider's word for the namespace.
+        let mut strict = CelestiaDASource::new(ReportingProvider(blob.clone())).with_strict(true);
+        strict.next(1, None, commitment(&blob)).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_strict_yields_referenced_blob() {
+        let blob = Bytes::from_static(b"referenced");
+        let mut source = CelestiaDASource::new(FixedProvider(blob.clone()))
+            .with_strict(true)
+            .with_namespace(namespace());
+
+        assert_eq!(source.next(1, None, commitment(&blob)).await.unwrap(), blob);
+    }
+
+    #[tokio::test]
+    async fn test_same_height_is_fetched_from_each_namespace() {
+        let (batches, blobs) = (namespace(), Namespace::new_v0(b"blobs").unwrap());
+        let provider = NamespacedProvider(vec![
+            (batches, Bytes::from_static(b"batch")),
+            (blobs, Bytes::from_static(b"blob")),
+        ]);
+        // Strict, so every blob is checked against the namespace it was requested from.
+        let mut source = CelestiaDASource::new(provider).with_strict(true);
+
+        for (namespace, blob) in [(batches, &b"batch"[..]), (blobs, &b"blob"[..])] {
+            let fetched = source
+                .next(1, Some(namespace), commitment_in(namespace, blob))
+                .await
+                .unwrap();
+            assert_eq!(fetched, blob);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifest_lists_fetched_blobs_in_order() {
+        let blob = Bytes::from_static(b"referenced");
+        let manifest = BlobManifest::default();
+        let mut source = CelestiaDASource::new(FixedProvider(blob.clone()))
+            .with_namespace(namespace())
+            .with_manifest(manifest.clone());
+
+        for (height, namespace) in [(3, None), (1, Some(namespace())), (2, None)] {
+            source
+                .next(height, namespace, commitment(&blob))
+                .await
+                .unwrap();
+        }
+        // Blobs failing their commitment check are not recorded.
+        source
+            .next(4, None, commitment(b"other"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            *manifest.lock(),
+            [
+                CelestiaBlobPointer::new(3, commitment(&blob)),
+                CelestiaBlobPointer::new_namespaced(1, commitment(&blob), namespace()),
+                CelestiaBlobPointer::new(2, commitment(&blob)),
+            ]
+        );
+    }
+}
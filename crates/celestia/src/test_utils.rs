@@ -0,0 +1,128 @@
+//! Helpers shared by the tests of the crate.
+
+use crate::traits::CelestiaProvider;
+use alloc::{boxed::Box, string::ToString, vec::Vec};
+use alloy_primitives::Bytes;
+use async_trait::async_trait;
+use celestia_types::{
+    consts::appconsts::SHARE_VERSION_ZERO, nmt::Namespace, AppVersion, Commitment,
+};
+use kona_derive::{PipelineError, PipelineErrorKind};
+
+/// The error of test providers that never fail.
+#[derive(Debug)]
+pub(crate) struct NeverFails;
+
+impl core::fmt::Display for NeverFails {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("never fails")
+    }
+}
+
+impl From<NeverFails> for PipelineErrorKind {
+    fn from(err: NeverFails) -> Self {
+        PipelineError::Provider(err.to_string()).crit()
+    }
+}
+
+/// A provider serving the same blob for every request.
+#[derive(Debug, Clone)]
+pub(crate) struct FixedProvider(pub(crate) Bytes);
+
+#[async_trait]
+impl CelestiaProvider for FixedProvider {
+    type Error = NeverFails;
+
+    async fn blob_get(&self, _: u64, _: Commitment) -> Result<Bytes, Self::Error> {
+        Ok(self.0.clone())
+    }
+
+    async fn blob_get_namespaced(
+        &self,
+        _: u64,
+        _: Namespace,
+        _: Commitment,
+    ) -> Result<Bytes, Self::Error> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A provider serving the same blob for every request, reporting [namespace] as the namespace it
+/// fetches blobs referenced without one from.
+#[derive(Debug, Clone)]
+pub(crate) struct ReportingProvider(pub(crate) Bytes);
+
+#[async_trait]
+impl CelestiaProvider for ReportingProvider {
+    type Error = NeverFails;
+
+    async fn blob_get(&self, _: u64, _: Commitment) -> Result<Bytes, Self::Error> {
+        Ok(self.0.clone())
+    }
+
+    async fn blob_get_with_namespace(
+        &self,
+        _: u64,
+        _: Commitment,
+    ) -> Result<(Option<Namespace>, Bytes), Self::Error> {
+        Ok((Some(namespace()), self.0.clone()))
+    }
+
+    async fn blob_get_namespaced(
+        &self,
+        _: u64,
+        _: Namespace,
+        _: Commitment,
+    ) -> Result<Bytes, Self::Error> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A provider serving a blob per namespace, at every height.
+#[derive(Debug, Clone)]
+pub(crate) struct NamespacedProvider(pub(crate) Vec<(Namespace, Bytes)>);
+
+#[async_trait]
+impl CelestiaProvider for NamespacedProvider {
+    type Error = NeverFails;
+
+    async fn blob_get(&self, _: u64, _: Commitment) -> Result<Bytes, Self::Error> {
+        panic!("the test blobs are only fetched from an explicit namespace")
+    }
+
+    async fn blob_get_namespaced(
+        &self,
+        _: u64,
+        namespace: Namespace,
+        _: Commitment,
+    ) -> Result<Bytes, Self::Error> {
+        let (_, blob) = self
+            .0
+            .iter()
+            .find(|(served, _)| *served == namespace)
+            .expect("no blob in the namespace");
+        Ok(blob.clone())
+    }
+}
+
+/// The namespace of the test blobs.
+pub(crate) fn namespace() -> Namespace {
+    Namespace::new_v0(b"hana").unwrap()
+}
+
+/// Returns the commitment of `blob` in [namespace].
+pub(crate) fn commitment(blob: &[u8]) -> Commitment {
+    commitment_in(namespace(), blob)
+}
+
+/// Returns the commitment of `blob` in `namespace`.
+pub(crate) fn commitment_in(namespace: Namespace, blob: &[u8]) -> Commitment {
+    Commitment::from_blob(
+        namespace,
+        blob,
+        SHARE_VERSION_ZERO,
+        None,
+        AppVersion::latest(),
+    )
+    .unwrap()
+}
@@ -1,9 +1,13 @@
 use alloc::{boxed::Box, string::ToString};
-use alloy_primitives::Bytes;
+use alloy_primitives::{Address, Bytes};
 use async_trait::async_trait;
-use celestia_types::Commitment;
-use core::fmt::Display;
-use kona_derive::PipelineErrorKind;
+use celestia_types::{nmt::Namespace, Commitment};
+use core::fmt::{Debug, Display};
+use kona_derive::{
+    BlobProvider, ChainProvider, DataAvailabilityProvider, EthereumDataSource, PipelineErrorKind,
+    PipelineResult,
+};
+use kona_protocol::BlockInfo;
 
 /// Describes the functionality of the Celestia DA client needed to fetch a blob from calldata
 #[async_trait]
@@ -11,4 +15,59 @@ pub trait CelestiaProvider {
     type Error: Display + ToString + Into<PipelineErrorKind>;
 
     async fn blob_get(&self, height: u64, commitment: Commitment) -> Result<Bytes, Self::Error>;
+
+    /// Fetches a blob from the provider's configured namespace like [CelestiaProvider::blob_get],
+    /// along with that namespace, so that the blob can be checked against `commitment`. The
+    /// namespace is `None` when the provider can't tell it, which is the default.
+    async fn blob_get_with_namespace(
+        &self,
+        height: u64,
+        commitment: Commitment,
+    ) -> Result<(Option<Namespace>, Bytes), Self::Error>
+    where
+        Self: Sync,
+    {
+        Ok((None, self.blob_get(height, commitment).await?))
+    }
+
+    /// Fetches a blob from an explicit namespace rather than the provider's configured one.
+    async fn blob_get_namespaced(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: Commitment,
+    ) -> Result<Bytes, Self::Error>;
+}
+
+/// A [DataAvailabilityProvider] that knows the sender of the L1 transaction each item it yields
+/// was posted in, so that Celestia pointers can be routed by the batcher that posted them.
+#[async_trait]
+pub trait SenderAwareProvider: DataAvailabilityProvider {
+    /// Returns the next item, like [DataAvailabilityProvider::next], along with the sender of the
+    /// transaction it was posted in.
+    async fn next_with_sender(
+        &mut self,
+        block_ref: &BlockInfo,
+        batcher_address: Address,
+    ) -> PipelineResult<(Self::Item, Address)>;
+}
+
+#[async_trait]
+impl<C, B> SenderAwareProvider for EthereumDataSource<C, B>
+where
+    C: ChainProvider + Send + Sync + Clone + Debug,
+    B: BlobProvider + Send + Sync + Clone + Debug,
+{
+    /// The calldata and blob sources of kona only yield the data of the transactions whose
+    /// recovered signer is `batcher_address`, so it is the sender of every item. A pointer posted
+    /// by any other account never reaches the Celestia source, which needs no sender check of its
+    /// own.
+    async fn next_with_sender(
+        &mut self,
+        block_ref: &BlockInfo,
+        batcher_address: Address,
+    ) -> PipelineResult<(Self::Item, Address)> {
+        let item = self.next(block_ref, batcher_address).await?;
+        Ok((item, batcher_address))
+    }
 }
@@ -0,0 +1,306 @@
+//! A minimal CBOR ([RFC 8949](https://www.rfc-editor.org/rfc/rfc8949)) codec for the
+//! [interop](crate::interop) encoding.
+//!
+//! Items are written with definite lengths and the shortest argument encoding. Reading accepts any
+//! well-formed item without indefinite lengths, so decoders can skip fields of unknown tags
+//! whatever their type.
+
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+
+use crate::interop::InteropError;
+
+/// The deepest nesting of arrays, maps and tags read before giving up.
+const MAX_DEPTH: usize = 32;
+
+/// A CBOR data item.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    /// An unsigned integer, major type 0.
+    Unsigned(u64),
+    /// The negative integer `-1 - n`, major type 1.
+    Negative(u64),
+    /// A byte string, major type 2.
+    Bytes(Vec<u8>),
+    /// A text string, major type 3.
+    Text(String),
+    /// An array, major type 4.
+    Array(Vec<Value>),
+    /// A map, major type 5, in encoding order.
+    Map(Vec<(Value, Value)>),
+    /// A tagged item, major type 6.
+    Tagged(u64, Box<Value>),
+    /// `false` or `true`.
+    Bool(bool),
+    /// A floating point number of any width.
+    Float(f64),
+    /// Any other simple value, including `null` and `undefined`.
+    Simple(u8),
+}
+
+impl Value {
+    /// Returns the CBOR encoding of the item.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    /// Decodes a single item spanning all of `bytes`.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, InteropError> {
+        let mut reader = Reader { bytes };
+        let value = reader.value(0)?;
+        if !reader.bytes.is_empty() {
+            return Err(decode_error("trailing bytes after the item"));
+        }
+        Ok(value)
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Unsigned(value) => write_head(out, 0, *value),
+            Self::Negative(value) => write_head(out, 1, *value),
+            Self::Bytes(bytes) => {
+                write_head(out, 2, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            Self::Text(text) => {
+                write_head(out, 3, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Self::Array(items) => {
+                write_head(out, 4, items.len() as u64);
+                items.iter().for_each(|item| item.write(out));
+            }
+            Self::Map(entries) => {
+                write_head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.write(out);
+                    value.write(out);
+                }
+            }
+            Self::Tagged(tag, item) => {
+                write_head(out, 6, *tag);
+                item.write(out);
+            }
+            Self::Bool(value) => out.push(if *value { 0xf5 } else { 0xf4 }),
+            Self::Float(value) => {
+                out.push(0xfb);
+                out.extend_from_slice(&value.to_bits().to_be_bytes());
+            }
+            Self::Simple(value) => write_head(out, 7, *value as u64),
+        }
+    }
+}
+
+/// Writes the initial byte of an item of `major` type and its argument.
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+    let major = major << 5;
+    match argument {
+        0..=23 => out.push(major | argument as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, argument as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(argument as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&argument.to_be_bytes());
+        }
+    }
+}
+
+fn decode_error(reason: &str) -> InteropError {
+    InteropError::Decode(String::from(reason))
+}
+
+/// Reads items from the front of a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], InteropError> {
+        if len > self.bytes.len() {
+            return Err(decode_error("unexpected end of input"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], InteropError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    /// Reads the initial byte of an item, returning its major type, additional information and
+    /// argument.
+    fn head(&mut self) -> Result<(u8, u8, u64), InteropError> {
+        let [initial] = self.take_array::<1>()?;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let argument = match info {
+            0..=23 => info as u64,
+            24 => u8::from_be_bytes(self.take_array()?) as u64,
+            25 => u16::from_be_bytes(self.take_array()?) as u64,
+            26 => u32::from_be_bytes(self.take_array()?) as u64,
+            27 => u64::from_be_bytes(self.take_array()?),
+            31 => return Err(decode_error("indefinite lengths are not supported")),
+            _ => return Err(decode_error("reserved additional information")),
+        };
+        Ok((major, info, argument))
+    }
+
+    /// Converts the length of a string or collection, rejecting lengths beyond the input.
+    fn len(&self, argument: u64) -> Result<usize, InteropError> {
+        usize::try_from(argument)
+            .ok()
+            .filter(|len| *len <= self.bytes.len())
+            .ok_or_else(|| decode_error("length exceeds the input"))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, InteropError> {
+        if depth > MAX_DEPTH {
+            return Err(decode_error("items are nested too deeply"));
+        }
+
+        let (major, info, argument) = self.head()?;
+        Ok(match major {
+            0 => Value::Unsigned(argument),
+            1 => Value::Negative(argument),
+            2 => {
+                let len = self.len(argument)?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.len(argument)?;
+                let text = core::str::from_utf8(self.take(len)?)
+                    .map_err(|e| InteropError::Decode(format!("invalid text string: {e}")))?;
+                Value::Text(String::from(text))
+            }
+            4 => {
+                // Every item takes at least a byte, which bounds the allocation by the input.
+                let len = self.len(argument)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            5 => {
+                let len = self.len(argument)?;
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    entries.push((self.value(depth + 1)?, self.value(depth + 1)?));
+                }
+                Value::Map(entries)
+            }
+            6 => Value::Tagged(argument, Box::new(self.value(depth + 1)?)),
+            _ => match info {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                25 => Value::Float(half_to_f64(argument as u16)),
+                26 => Value::Float(f32::from_bits(argument as u32) as f64),
+                27 => Value::Float(f64::from_bits(argument)),
+                _ => Value::Simple(argument as u8),
+            },
+        })
+    }
+}
+
+/// Converts an IEEE 754 half precision float.
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+    let magnitude = match exponent {
+        0 => fraction * pow2(-24),
+        31 if fraction == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + fraction) * pow2(exponent - 25),
+    };
+    sign * magnitude
+}
+
+/// Returns `2^exponent`, for the exponents of half precision floats.
+fn pow2(exponent: i32) -> f64 {
+    f64::from_bits(((1023 + exponent) as u64) << 52)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_heads_use_the_shortest_argument() {
+        let cases: [(u64, &[u8]); 6] = [
+            (0, &[0x00]),
+            (23, &[0x17]),
+            (24, &[0x18, 0x18]),
+            (0x100, &[0x19, 0x01, 0x00]),
+            (0x1_0000, &[0x1a, 0x00, 0x01, 0x00, 0x00]),
+            (
+                u64::MAX,
+                &[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+        ];
+        for (value, encoding) in cases {
+            assert_eq!(Value::Unsigned(value).to_bytes(), encoding);
+            assert_eq!(Value::from_bytes(encoding).unwrap(), Value::Unsigned(value));
+        }
+    }
+
+    #[test]
+    fn test_round_trips_nested_items() {
+        let value = Value::Map(vec![
+            (Value::Unsigned(1), Value::Bytes(vec![0xaa; 30])),
+            (
+                Value::Unsigned(2),
+                Value::Array(vec![Value::Bool(true), Value::Negative(9)]),
+            ),
+            (
+                Value::Text(String::from("unknown")),
+                Value::Tagged(1, Box::new(Value::Float(1.5))),
+            ),
+        ]);
+        assert_eq!(Value::from_bytes(&value.to_bytes()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_reads_half_floats_and_simple_values() {
+        assert_eq!(
+            Value::from_bytes(&[0xf9, 0x3c, 0x00]).unwrap(),
+            Value::Float(1.0)
+        );
+        assert_eq!(
+            Value::from_bytes(&[0xf9, 0xc4, 0x00]).unwrap(),
+            Value::Float(-4.0)
+        );
+        assert_eq!(Value::from_bytes(&[0xf6]).unwrap(), Value::Simple(22));
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        // Truncated byte string, indefinite length, trailing byte and an oversized array.
+        for bytes in [
+            &[0x42, 0x00][..],
+            &[0x5f, 0xff],
+            &[0x00, 0x00],
+            &[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ] {
+            assert!(matches!(
+                Value::from_bytes(bytes),
+                Err(InteropError::Decode(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting() {
+        let bytes = vec![0x81; MAX_DEPTH + 2];
+        assert!(Value::from_bytes(&bytes).is_err());
+    }
+}
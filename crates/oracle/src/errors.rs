@@ -0,0 +1,180 @@
+//! Errors returned by the [OracleCelestiaProvider](crate::provider::OracleCelestiaProvider).
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+};
+use alloy_primitives::B256;
+use celestia_types::Commitment;
+use kona_derive::{PipelineError, PipelineErrorKind};
+use kona_preimage::errors::PreimageOracleError;
+use kona_proof::errors::OracleProviderError;
+
+use crate::provider::BLOB_VERIFICATION_FAILED;
+
+pub use hana_blobstream::errors::CelestiaVerificationError;
+
+/// An error fetching or verifying a Celestia blob through the preimage oracle.
+#[derive(Debug, thiserror::Error)]
+pub enum OracleCelestiaProviderError {
+    /// The preimage oracle failed to serve the payload.
+    #[error(transparent)]
+    Oracle(#[from] OracleProviderError),
+    /// A Celestia type in the payload was malformed.
+    #[error("celestia error: {0}")]
+    Celestia(#[from] celestia_types::Error),
+    /// The payload served by the host isn't a valid encoding of an oracle payload.
+    #[error("invalid celestia oracle payload: {0}")]
+    Payload(String),
+    /// The table of custom Blobstream deployments served by the host is malformed.
+    #[error("invalid blobstream address table: {0}")]
+    BlobstreamAddresses(String),
+    /// The blob failed verification against Blobstream.
+    #[error("{}: {0}", BLOB_VERIFICATION_FAILED)]
+    Verification(#[from] CelestiaVerificationError),
+    /// Fetching or verifying the blob at `height` with `commitment` failed.
+    #[error("celestia blob at height {height} with commitment {commitment}: {source}")]
+    Blob {
+        /// The Celestia height of the blob.
+        height: u64,
+        /// The blob commitment.
+        commitment: B256,
+        /// The error fetching or verifying the blob.
+        source: Box<OracleCelestiaProviderError>,
+    },
+}
+
+impl OracleCelestiaProviderError {
+    /// Attaches the blob the error occurred for.
+    pub fn with_blob(self, height: u64, commitment: &Commitment) -> Self {
+        Self::Blob {
+            height,
+            commitment: B256::from(*commitment.hash()),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl From<PreimageOracleError> for OracleCelestiaProviderError {
+    fn from(err: PreimageOracleError) -> Self {
+        Self::Oracle(err.into())
+    }
+}
+
+impl From<OracleCelestiaProviderError> for PipelineErrorKind {
+    fn from(err: OracleCelestiaProviderError) -> Self {
+        let message = err.to_string();
+        match err {
+            OracleCelestiaProviderError::Oracle(err) => err.into(),
+            // Keep the severity of the underlying error, with the blob in the message.
+            OracleCelestiaProviderError::Blob { source, .. } => match Self::from(*source) {
+                Self::Temporary(_) => PipelineError::Provider(message).temp(),
+                Self::Critical(_) => PipelineError::Provider(message).crit(),
+                reset => reset,
+            },
+            OracleCelestiaProviderError::Celestia(_)
+            | OracleCelestiaProviderError::Payload(_)
+            | OracleCelestiaProviderError::BlobstreamAddresses(_)
+            | OracleCelestiaProviderError::Verification(_) => {
+                PipelineError::Provider(message).crit()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::String};
+
+    fn critical_message(kind: PipelineErrorKind) -> String {
+        match kind {
+            PipelineErrorKind::Critical(PipelineError::Provider(message)) => message,
+            other => panic!("expected a critical provider error, got {other:?}"),
+        }
+    }
+
+    /// Returns the severity of `kind`.
+    fn severity(kind: &PipelineErrorKind) -> &'static str {
+        match kind {
+            PipelineErrorKind::Temporary(_) => "temporary",
+            PipelineErrorKind::Critical(_) => "critical",
+            PipelineErrorKind::Reset(_) => "reset",
+        }
+    }
+
+    #[test]
+    fn test_preimage_errors_convert_to_oracle_errors() {
+        let err = OracleCelestiaProviderError::from(PreimageOracleError::KeyNotFound);
+        assert!(
+            matches!(
+                err,
+                OracleCelestiaProviderError::Oracle(OracleProviderError::Preimage(
+                    PreimageOracleError::KeyNotFound
+                ))
+            ),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_oracle_errors_keep_their_severity() {
+        let expected =
+            PipelineErrorKind::from(OracleProviderError::from(PreimageOracleError::KeyNotFound));
+
+        let err = OracleCelestiaProviderError::from(PreimageOracleError::KeyNotFound);
+        assert_eq!(severity(&err.into()), severity(&expected));
+
+        let err = OracleCelestiaProviderError::from(PreimageOracleError::KeyNotFound)
+            .with_blob(42, &Commitment::new([0x11; 32]));
+        assert_eq!(severity(&err.into()), severity(&expected));
+    }
+
+    #[test]
+    fn test_malformed_data_is_critical() {
+        let celestia = celestia_types::nmt::Namespace::from_raw(&[0u8; 3]).unwrap_err();
+        let err = OracleCelestiaProviderError::from(celestia);
+        assert!(
+            matches!(err, OracleCelestiaProviderError::Celestia(_)),
+            "{err:?}"
+        );
+        assert!(critical_message(err.into()).starts_with("celestia error: "));
+
+        let err = OracleCelestiaProviderError::Payload("truncated".to_string());
+        assert_eq!(
+            critical_message(err.into()),
+            "invalid celestia oracle payload: truncated"
+        );
+
+        let err = OracleCelestiaProviderError::BlobstreamAddresses("truncated".to_string());
+        assert_eq!(
+            critical_message(err.into()),
+            "invalid blobstream address table: truncated"
+        );
+    }
+
+    #[test]
+    fn test_verification_error_is_critical() {
+        let err = OracleCelestiaProviderError::from(CelestiaVerificationError::UnknownChainId(7));
+
+        assert_eq!(
+            critical_message(err.into()),
+            "celestia blob verification failed: no canonical Blobstream address found for chain id 7"
+        );
+    }
+
+    #[test]
+    fn test_blob_error_keeps_the_severity_and_names_the_blob() {
+        let err = OracleCelestiaProviderError::from(CelestiaVerificationError::UnknownChainId(7))
+            .with_blob(42, &Commitment::new([0x11; 32]));
+
+        assert_eq!(
+            critical_message(err.into()),
+            format!(
+                "celestia blob at height 42 with commitment {}: celestia blob verification failed: \
+                 no canonical Blobstream address found for chain id 7",
+                B256::repeat_byte(0x11)
+            )
+        );
+    }
+}
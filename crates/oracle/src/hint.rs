@@ -1,6 +1,10 @@
 use core::{fmt, str::FromStr};
 
-use alloc::string::String;
+use alloc::{format, string::String, vec::Vec};
+use celestia_types::{
+    nmt::{Namespace, NS_SIZE},
+    Commitment,
+};
 use kona_proof::{errors::HintParsingError, HintType};
 // Add your HintWrapper
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -35,3 +39,91 @@ impl fmt::Display for HintWrapper {
         }
     }
 }
+
+/// The length of the data of a [HintWrapper::CelestiaDA] hint without a namespace.
+const CELESTIA_DA_HINT_LEN: usize = 8 + 32;
+
+/// The data of a [HintWrapper::CelestiaDA] hint, whose hash is also the key of the payload
+/// preimage.
+///
+/// Encoded as `height (8 bytes, LE) | commitment (32 bytes) | namespace (29 bytes, optional)`.
+/// The height is little-endian like in the Celestia blob pointers posted by the batcher, and
+/// unlike the big-endian height of the data root tuples committed to by Blobstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CelestiaDAHint {
+    /// The Celestia height of the blob.
+    pub height: u64,
+    /// The blob commitment.
+    pub commitment: Commitment,
+    /// The namespace of the blob, the host's namespace is used when unset.
+    pub namespace: Option<Namespace>,
+}
+
+impl CelestiaDAHint {
+    /// Encodes the hint data.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(CELESTIA_DA_HINT_LEN + NS_SIZE);
+        encoded.extend_from_slice(&self.height.to_le_bytes());
+        encoded.extend_from_slice(self.commitment.hash());
+        if let Some(namespace) = self.namespace {
+            encoded.extend_from_slice(namespace.as_bytes());
+        }
+        encoded
+    }
+
+    /// Decodes the hint data.
+    pub fn decode(data: &[u8]) -> Result<Self, HintParsingError> {
+        if data.len() != CELESTIA_DA_HINT_LEN && data.len() != CELESTIA_DA_HINT_LEN + NS_SIZE {
+            return Err(HintParsingError(format!(
+                "invalid celestia-da hint data length {}",
+                data.len()
+            )));
+        }
+
+        let height = u64::from_le_bytes(data[0..8].try_into().expect("Slice must be 8 bytes"));
+        let hash_array: [u8; 32] = data[8..40].try_into().expect("Slice must be 32 bytes");
+        let namespace = if data.len() == CELESTIA_DA_HINT_LEN {
+            None
+        } else {
+            Some(Namespace::from_raw(&data[40..]).map_err(|e| {
+                HintParsingError(format!("invalid namespace in celestia-da hint: {e}"))
+            })?)
+        };
+
+        Ok(Self {
+            height,
+            commitment: Commitment::new(hash_array),
+            namespace,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_celestia_da_hint_height_round_trip() {
+        let namespace = Namespace::new_v0(b"hana-test").unwrap();
+        for height in [0, 1, u64::MAX] {
+            for namespace in [None, Some(namespace)] {
+                let hint = CelestiaDAHint {
+                    height,
+                    commitment: Commitment::new([0x11; 32]),
+                    namespace,
+                };
+                let encoded = hint.encode();
+
+                // The height leads the hint data, little-endian.
+                assert_eq!(encoded[..8], height.to_le_bytes());
+                assert_eq!(CelestiaDAHint::decode(&encoded).unwrap(), hint);
+            }
+        }
+    }
+
+    #[test]
+    fn test_celestia_da_hint_rejects_other_lengths() {
+        assert!(CelestiaDAHint::decode(&[0; CELESTIA_DA_HINT_LEN - 1]).is_err());
+        assert!(CelestiaDAHint::decode(&[0; CELESTIA_DA_HINT_LEN + 1]).is_err());
+    }
+}
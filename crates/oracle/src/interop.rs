@@ -0,0 +1,557 @@
+//! A CBOR encoding of [OraclePayload] for verifiers written in other languages.
+//!
+//! The bincode encoding served to the client mirrors the Rust type layouts and can't reasonably be
+//! parsed elsewhere. This encoding is self-describing instead: every structure is a CBOR map
+//! keyed by the integer tags below, so decoders don't depend on field order and new fields can be
+//! added under new tags. Decoders must ignore tags they don't know.
+//!
+//! `OraclePayload`:
+//!
+//! | Tag | Field              | CBOR type                    |
+//! |-----|--------------------|------------------------------|
+//! | 1   | `blob`             | byte string                  |
+//! | 2   | `blobstream_proof` | map, `BlobstreamProof` below |
+//!
+//! `BlobstreamProof`:
+//!
+//! | Tag | Field                   | CBOR type                                                    |
+//! |-----|-------------------------|--------------------------------------------------------------|
+//! | 1   | `blobstream_address`    | byte string, 20 bytes                                        |
+//! | 2   | `data_root`             | byte string, 32 bytes                                        |
+//! | 3   | `data_commitment`       | byte string, 32 bytes                                        |
+//! | 4   | `data_root_tuple_proof` | map, `MerkleProof` below                                     |
+//! | 5   | `share_proof`           | map, `ShareProof` below                                      |
+//! | 6   | `proof_nonce`           | byte string, 32 bytes big endian                             |
+//! | 7   | `storage_root`          | byte string, 32 bytes                                        |
+//! | 8   | `storage_proof`         | array of byte strings, the RLP encoded trie nodes            |
+//! | 9   | `account_proof`         | array of byte strings, the RLP encoded trie nodes            |
+//! | 10  | `blobstream_balance`    | byte string, 32 bytes big endian                             |
+//! | 11  | `blobstream_nonce`      | unsigned integer                                             |
+//! | 12  | `blobstream_code_hash`  | byte string, 32 bytes                                        |
+//! | 13  | `block_header`          | byte string, the RLP encoded header                          |
+//! | 14  | `header_chain`          | array of byte strings, the RLP encoded headers, newest first |
+//!
+//! `MerkleProof`, the proof of a leaf of a Tendermint merkle tree:
+//!
+//! | Tag | Field       | CBOR type                                                 |
+//! |-----|-------------|-----------------------------------------------------------|
+//! | 1   | `total`     | unsigned integer, the number of leaves                    |
+//! | 2   | `index`     | unsigned integer, the index of the proven leaf            |
+//! | 3   | `leaf_hash` | byte string, 32 bytes                                     |
+//! | 4   | `aunts`     | array of byte strings, 32 bytes each, from the bottom up  |
+//!
+//! `ShareProof`, the proof of a range of shares of a Celestia data square:
+//!
+//! | Tag | Field          | CBOR type                                                         |
+//! |-----|----------------|-------------------------------------------------------------------|
+//! | 1   | `data`         | array of byte strings, the 512 byte shares                        |
+//! | 2   | `namespace`    | byte string, 29 bytes: the version followed by the id             |
+//! | 3   | `share_proofs` | array of maps, `NamespaceProof` below, one per row                |
+//! | 4   | `row_proof`    | map, `RowProof` below                                             |
+//!
+//! `NamespaceProof`, the namespaced merkle proof of the shares of a row:
+//!
+//! | Tag | Field                      | CBOR type                                           |
+//! |-----|----------------------------|-----------------------------------------------------|
+//! | 1   | `start`                    | unsigned integer, the first share of the row        |
+//! | 2   | `end`                      | unsigned integer, the share after the last one      |
+//! | 3   | `nodes`                    | array of byte strings, 90 byte namespaced hashes    |
+//! | 4   | `leaf_hash`                | byte string, empty unless proving an absence        |
+//! | 5   | `is_max_namespace_ignored` | boolean                                             |
+//!
+//! `RowProof`, the proof of the row roots against the data root:
+//!
+//! | Tag | Field       | CBOR type                                                  |
+//! |-----|-------------|------------------------------------------------------------|
+//! | 1   | `row_roots` | array of byte strings, 90 byte namespaced hashes           |
+//! | 2   | `proofs`    | array of maps, `MerkleProof` above, one per row root       |
+//! | 3   | `root`      | byte string, empty when not set                            |
+//! | 4   | `start_row` | unsigned integer, the first row                            |
+//! | 5   | `end_row`   | unsigned integer, the last row (inclusive)                 |
+//!
+//! Headers are RLP encoded as on L1, so their hash is the keccak256 of the bytes.
+//!
+//! Items are encoded with definite lengths and the shortest integer arguments, and maps list
+//! their tags in increasing order, so a payload has a single encoding.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use alloy_consensus::Header;
+use alloy_primitives::{hex, Address, Bytes, B256, U256};
+use alloy_rlp::Decodable;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use celestia_types::{hash::Hash, MerkleProof, ShareProof};
+use hana_blobstream::blobstream::BlobstreamProof;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value as Json};
+
+use crate::{cbor::Value, payload::OraclePayload};
+
+/// An error encoding or decoding the interop encoding of an [OraclePayload].
+#[derive(Debug, thiserror::Error)]
+pub enum InteropError {
+    /// The payload couldn't be encoded.
+    #[error("failed to encode interop payload: {0}")]
+    Encode(String),
+    /// The bytes aren't valid CBOR, or not a map where one is expected.
+    #[error("failed to decode interop payload: {0}")]
+    Decode(String),
+    /// A required field is missing.
+    #[error("interop payload is missing the field with tag {0}")]
+    MissingField(u64),
+    /// A field doesn't have the type or length of the schema.
+    #[error("interop payload field with tag {0} is malformed")]
+    InvalidField(u64),
+}
+
+impl OraclePayload {
+    /// Serializes the payload with the interop encoding, see the [module docs](self).
+    pub fn to_interop_bytes(&self) -> Result<Vec<u8>, InteropError> {
+        let value = Value::Map(vec![
+            (tag(1), Value::Bytes(self.blob.to_vec())),
+            (tag(2), proof_to_value(&self.blobstream_proof)?),
+        ]);
+        Ok(value.to_bytes())
+    }
+
+    /// Deserializes a payload serialized with the interop encoding, see the [module docs](self).
+    ///
+    /// Unlike [OraclePayload::from_bytes], the proof isn't checked against any
+    /// [ProofLimits](hana_blobstream::blobstream::ProofLimits).
+    pub fn from_interop_bytes(bytes: &[u8]) -> Result<Self, InteropError> {
+        let mut payload = TaggedMap::new(Value::from_bytes(bytes)?)?;
+        let blob = Bytes::from(payload.bytes(1)?);
+        let blobstream_proof = proof_from_value(payload.take(2)?)?;
+
+        Ok(Self::new(blob, blobstream_proof))
+    }
+}
+
+/// Returns the map key of `tag`.
+fn tag(tag: u64) -> Value {
+    Value::Unsigned(tag)
+}
+
+/// Encodes a list of byte strings.
+fn bytes_array<T: AsRef<[u8]>>(items: impl IntoIterator<Item = T>) -> Value {
+    Value::Array(
+        items
+            .into_iter()
+            .map(|item| Value::Bytes(item.as_ref().to_vec()))
+            .collect(),
+    )
+}
+
+/// Encodes a [BlobstreamProof] as the map of the module docs.
+fn proof_to_value(proof: &BlobstreamProof) -> Result<Value, InteropError> {
+    Ok(Value::Map(vec![
+        (tag(1), Value::Bytes(proof.blobstream_address.to_vec())),
+        (tag(2), Value::Bytes(proof.data_root.as_bytes().to_vec())),
+        (tag(3), Value::Bytes(proof.data_commitment.to_vec())),
+        (
+            tag(4),
+            merkle_proof_to_value(&to_json(&proof.data_root_tuple_proof)?)?,
+        ),
+        (tag(5), share_proof_to_value(&proof.share_proof)?),
+        (tag(6), Value::Bytes(proof.proof_nonce.to_be_bytes_vec())),
+        (tag(7), Value::Bytes(proof.storage_root.to_vec())),
+        (tag(8), bytes_array(&proof.storage_proof)),
+        (tag(9), bytes_array(&proof.account_proof)),
+        (
+            tag(10),
+            Value::Bytes(proof.blobstream_balance.to_be_bytes_vec()),
+        ),
+        (tag(11), Value::Unsigned(proof.blobstream_nonce)),
+        (tag(12), Value::Bytes(proof.blobstream_code_hash.to_vec())),
+        (
+            tag(13),
+            Value::Bytes(alloy_rlp::encode(&proof.block_header)),
+        ),
+        (
+            tag(14),
+            bytes_array(proof.header_chain.iter().map(alloy_rlp::encode)),
+        ),
+    ]))
+}
+
+/// Decodes a [BlobstreamProof] from the map of the module docs.
+fn proof_from_value(value: Value) -> Result<BlobstreamProof, InteropError> {
+    let mut proof = TaggedMap::new(value)?;
+
+    let block_header = decode_header(&proof.bytes(13)?).ok_or(InteropError::InvalidField(13))?;
+    let header_chain = proof
+        .bytes_array(14)?
+        .iter()
+        .map(|header| decode_header(header).ok_or(InteropError::InvalidField(14)))
+        .collect::<Result<_, _>>()?;
+    // The nested structures are reported as a whole, their tags being ambiguous on their own.
+    let data_root_tuple_proof = merkle_proof_from_value(proof.take(4)?)
+        .and_then(from_json)
+        .map_err(|_| InteropError::InvalidField(4))?;
+    let share_proof =
+        share_proof_from_value(proof.take(5)?).map_err(|_| InteropError::InvalidField(5))?;
+
+    Ok(BlobstreamProof::new(
+        Address::from(proof.fixed::<20>(1)?),
+        Hash::Sha256(proof.fixed::<32>(2)?),
+        B256::from(proof.fixed::<32>(3)?),
+        data_root_tuple_proof,
+        share_proof,
+        U256::from_be_bytes(proof.fixed::<32>(6)?),
+        B256::from(proof.fixed::<32>(7)?),
+        proof.bytes_array(8)?.into_iter().map(Bytes::from).collect(),
+        proof.bytes_array(9)?.into_iter().map(Bytes::from).collect(),
+        U256::from_be_bytes(proof.fixed::<32>(10)?),
+        proof.u64(11)?,
+        B256::from(proof.fixed::<32>(12)?),
+        block_header,
+        header_chain,
+    ))
+}
+
+/// Decodes an RLP encoded header, rejecting trailing bytes.
+fn decode_header(mut bytes: &[u8]) -> Option<Header> {
+    let header = Header::decode(&mut bytes).ok()?;
+    bytes.is_empty().then_some(header)
+}
+
+// The `celestia-types` proofs only expose their fields through serde, so they are converted
+// through their JSON representation: integers as numbers or decimal strings, hashes and shares as
+// base64, and row roots as hex.
+
+fn to_json<T: Serialize>(value: &T) -> Result<Json, InteropError> {
+    serde_json::to_value(value).map_err(|e| InteropError::Encode(e.to_string()))
+}
+
+fn from_json<T: DeserializeOwned>(json: Json) -> Result<T, InteropError> {
+    serde_json::from_value(json).map_err(|e| InteropError::Decode(e.to_string()))
+}
+
+/// Returns the error of a JSON field that doesn't have the expected shape.
+fn unexpected(field: &str) -> InteropError {
+    InteropError::Encode(format!("unexpected JSON representation of `{field}`"))
+}
+
+fn json_u64(json: &Json, field: &str) -> Result<u64, InteropError> {
+    match json.get(field) {
+        Some(Json::Number(number)) => number.as_u64(),
+        Some(Json::String(string)) => string.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| unexpected(field))
+}
+
+fn json_array<'a>(json: &'a Json, field: &str) -> Result<&'a [Json], InteropError> {
+    json.get(field)
+        .and_then(Json::as_array)
+        .map(Vec::as_slice)
+        .ok_or_else(|| unexpected(field))
+}
+
+fn base64_bytes(json: &Json, field: &str) -> Result<Vec<u8>, InteropError> {
+    json.as_str()
+        .and_then(|string| STANDARD.decode(string).ok())
+        .ok_or_else(|| unexpected(field))
+}
+
+fn hex_bytes(json: &Json, field: &str) -> Result<Vec<u8>, InteropError> {
+    json.as_str()
+        .and_then(|string| hex::decode(string).ok())
+        .ok_or_else(|| unexpected(field))
+}
+
+fn base64_array(json: &Json, field: &str) -> Result<Value, InteropError> {
+    let items = json_array(json, field)?
+        .iter()
+        .map(|item| base64_bytes(item, field))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes_array(items))
+}
+
+/// Encodes the JSON representation of a [MerkleProof] as the map of the module docs.
+fn merkle_proof_to_value(json: &Json) -> Result<Value, InteropError> {
+    Ok(Value::Map(vec![
+        (tag(1), Value::Unsigned(json_u64(json, "total")?)),
+        (tag(2), Value::Unsigned(json_u64(json, "index")?)),
+        (
+            tag(3),
+            Value::Bytes(base64_bytes(&json["leaf_hash"], "leaf_hash")?),
+        ),
+        (tag(4), base64_array(json, "aunts")?),
+    ]))
+}
+
+/// Decodes the map of a [MerkleProof] of the module docs to its JSON representation.
+fn merkle_proof_from_value(value: Value) -> Result<Json, InteropError> {
+    let mut proof = TaggedMap::new(value)?;
+    Ok(json!({
+        "total": proof.u64(1)?.to_string(),
+        "index": proof.u64(2)?.to_string(),
+        "leaf_hash": STANDARD.encode(proof.fixed::<32>(3)?),
+        "aunts": proof
+            .bytes_array(4)?
+            .iter()
+            .map(|aunt| STANDARD.encode(aunt))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Encodes a [ShareProof] as the map of the module docs.
+fn share_proof_to_value(share_proof: &ShareProof) -> Result<Value, InteropError> {
+    let json = to_json(share_proof)?;
+    let row_proof = &json["row_proof"];
+
+    let share_proofs = json_array(&json, "share_proofs")?
+        .iter()
+        .map(|proof| {
+            Ok(Value::Map(vec![
+                (tag(1), Value::Unsigned(json_u64(proof, "start")?)),
+                (tag(2), Value::Unsigned(json_u64(proof, "end")?)),
+                (tag(3), base64_array(proof, "nodes")?),
+                (
+                    tag(4),
+                    Value::Bytes(base64_bytes(&proof["leaf_hash"], "leaf_hash")?),
+                ),
+                (
+                    tag(5),
+                    Value::Bool(
+                        proof["is_max_namespace_ignored"]
+                            .as_bool()
+                            .ok_or_else(|| unexpected("is_max_namespace_ignored"))?,
+                    ),
+                ),
+            ]))
+        })
+        .collect::<Result<Vec<_>, InteropError>>()?;
+    let row_roots = json_array(row_proof, "row_roots")?
+        .iter()
+        .map(|root| hex_bytes(root, "row_roots"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let row_root_proofs = json_array(row_proof, "proofs")?
+        .iter()
+        .map(merkle_proof_to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Value::Map(vec![
+        (tag(1), base64_array(&json, "data")?),
+        (
+            tag(2),
+            Value::Bytes(share_proof.namespace().as_bytes().to_vec()),
+        ),
+        (tag(3), Value::Array(share_proofs)),
+        (
+            tag(4),
+            Value::Map(vec![
+                (tag(1), bytes_array(row_roots)),
+                (tag(2), Value::Array(row_root_proofs)),
+                (tag(3), Value::Bytes(hex_bytes(&row_proof["root"], "root")?)),
+                (tag(4), Value::Unsigned(json_u64(row_proof, "start_row")?)),
+                (tag(5), Value::Unsigned(json_u64(row_proof, "end_row")?)),
+            ]),
+        ),
+    ]))
+}
+
+/// Decodes a [ShareProof] from the map of the module docs.
+fn share_proof_from_value(value: Value) -> Result<ShareProof, InteropError> {
+    let mut share_proof = TaggedMap::new(value)?;
+    let data = share_proof.bytes_array(1)?;
+    let namespace = share_proof.fixed::<29>(2)?;
+
+    let share_proofs = share_proof
+        .array(3)?
+        .into_iter()
+        .map(|proof| {
+            let mut proof = TaggedMap::new(proof)?;
+            Ok(json!({
+                "start": proof.u64(1)?,
+                "end": proof.u64(2)?,
+                "nodes": proof
+                    .bytes_array(3)?
+                    .iter()
+                    .map(|node| STANDARD.encode(node))
+                    .collect::<Vec<_>>(),
+                "leaf_hash": STANDARD.encode(proof.bytes(4)?),
+                "is_max_namespace_ignored": proof.bool(5)?,
+            }))
+        })
+        .collect::<Result<Vec<_>, InteropError>>()?;
+
+    let mut row_proof = TaggedMap::new(share_proof.take(4)?)?;
+    let proofs = row_proof
+        .array(2)?
+        .into_iter()
+        .map(merkle_proof_from_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    from_json(json!({
+        "data": data.iter().map(|share| STANDARD.encode(share)).collect::<Vec<_>>(),
+        "share_proofs": share_proofs,
+        "namespace_id": STANDARD.encode(&namespace[1..]),
+        "namespace_version": namespace[0],
+        "row_proof": {
+            "row_roots": row_proof
+                .bytes_array(1)?
+                .iter()
+                .map(hex::encode_upper)
+                .collect::<Vec<_>>(),
+            "proofs": proofs,
+            "root": hex::encode_upper(row_proof.bytes(3)?),
+            "start_row": row_proof.u64(4)?,
+            "end_row": row_proof.u64(5)?,
+        },
+    }))
+}
+
+/// The entries of a CBOR map keyed by integer tags.
+struct TaggedMap(Vec<(Value, Value)>);
+
+impl TaggedMap {
+    fn new(value: Value) -> Result<Self, InteropError> {
+        match value {
+            Value::Map(entries) => Ok(Self(entries)),
+            _ => Err(InteropError::Decode("expected a map".to_string())),
+        }
+    }
+
+    fn take(&mut self, tag: u64) -> Result<Value, InteropError> {
+        let position = self
+            .0
+            .iter()
+            .position(|(key, _)| *key == Value::Unsigned(tag))
+            .ok_or(InteropError::MissingField(tag))?;
+        Ok(self.0.swap_remove(position).1)
+    }
+
+    fn bytes(&mut self, tag: u64) -> Result<Vec<u8>, InteropError> {
+        match self.take(tag)? {
+            Value::Bytes(bytes) => Ok(bytes),
+            _ => Err(InteropError::InvalidField(tag)),
+        }
+    }
+
+    fn fixed<const N: usize>(&mut self, tag: u64) -> Result<[u8; N], InteropError> {
+        self.bytes(tag)?
+            .try_into()
+            .map_err(|_| InteropError::InvalidField(tag))
+    }
+
+    fn u64(&mut self, tag: u64) -> Result<u64, InteropError> {
+        match self.take(tag)? {
+            Value::Unsigned(value) => Ok(value),
+            _ => Err(InteropError::InvalidField(tag)),
+        }
+    }
+
+    fn bool(&mut self, tag: u64) -> Result<bool, InteropError> {
+        match self.take(tag)? {
+            Value::Bool(value) => Ok(value),
+            _ => Err(InteropError::InvalidField(tag)),
+        }
+    }
+
+    fn array(&mut self, tag: u64) -> Result<Vec<Value>, InteropError> {
+        match self.take(tag)? {
+            Value::Array(items) => Ok(items),
+            _ => Err(InteropError::InvalidField(tag)),
+        }
+    }
+
+    fn bytes_array(&mut self, tag: u64) -> Result<Vec<Vec<u8>>, InteropError> {
+        self.array(tag)?
+            .into_iter()
+            .map(|item| match item {
+                Value::Bytes(bytes) => Ok(bytes),
+                _ => Err(InteropError::InvalidField(tag)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::nmt::Namespace;
+    use hana_blobstream::test_utils::synthetic_proof;
+
+    /// A payload of the blob `hana-blob` whose proof fields are filled with repeated bytes, e.g.
+    /// `0xbb` for the Blobstream address and `0x11` for the data root. Any change to the encoding
+    /// of the schema breaks this fixture.
+    const GOLDEN_PAYLOAD: &[u8] = include_bytes!("../testdata/interop_payload.cbor");
+
+    #[test]
+    fn test_interop_round_trip_of_synthetic_payload() {
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof);
+
+        let bytes = payload.to_interop_bytes().unwrap();
+        let decoded = OraclePayload::from_interop_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.blob, payload.blob);
+        assert_eq!(decoded.to_interop_bytes().unwrap(), bytes);
+        decoded
+            .blobstream_proof
+            .validate(
+                synthetic.height,
+                synthetic.l1_head,
+                synthetic.blobstream_address,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_interop_golden_payload() {
+        let payload = OraclePayload::from_interop_bytes(GOLDEN_PAYLOAD).unwrap();
+        let proof = &payload.blobstream_proof;
+
+        assert_eq!(payload.blob, Bytes::from_static(b"hana-blob"));
+        assert_eq!(proof.blobstream_address, Address::repeat_byte(0xbb));
+        assert_eq!(proof.data_root, Hash::Sha256([0x11; 32]));
+        assert_eq!(proof.data_commitment, B256::repeat_byte(0x22));
+        assert_eq!(proof.proof_nonce, U256::from(1));
+        assert_eq!(proof.blobstream_nonce, 1);
+        assert_eq!(proof.block_header.number, 1000);
+        assert!(proof.header_chain.is_empty());
+        assert_eq!(
+            proof.share_proof.namespace(),
+            Namespace::new_v0(b"hana-test").unwrap()
+        );
+        assert_eq!(proof.share_proof.shares().len(), 1);
+        assert_eq!(proof.share_proof.row_proof().row_roots().len(), 1);
+
+        assert_eq!(payload.to_interop_bytes().unwrap(), GOLDEN_PAYLOAD);
+    }
+
+    #[test]
+    fn test_interop_ignores_unknown_tags() {
+        let Value::Map(mut entries) = Value::from_bytes(GOLDEN_PAYLOAD).unwrap() else {
+            panic!("payload is a map");
+        };
+        entries.push((tag(99), Value::Text("from a newer encoder".to_string())));
+
+        let payload = OraclePayload::from_interop_bytes(&Value::Map(entries).to_bytes()).unwrap();
+        assert_eq!(payload.to_interop_bytes().unwrap(), GOLDEN_PAYLOAD);
+    }
+
+    #[test]
+    fn test_interop_reports_missing_and_malformed_fields() {
+        let Value::Map(mut entries) = Value::from_bytes(GOLDEN_PAYLOAD).unwrap() else {
+            panic!("payload is a map");
+        };
+        entries[0].1 = Value::Unsigned(1);
+        assert!(matches!(
+            OraclePayload::from_interop_bytes(&Value::Map(entries.clone()).to_bytes()),
+            Err(InteropError::InvalidField(1))
+        ));
+
+        entries.remove(1);
+        assert!(matches!(
+            OraclePayload::from_interop_bytes(&Value::Map(entries).to_bytes()),
+            Err(InteropError::MissingField(2))
+        ));
+    }
+}
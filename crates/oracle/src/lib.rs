@@ -5,8 +5,16 @@
 
 extern crate alloc;
 
+pub mod errors;
+
 pub mod hint;
 
 pub mod provider;
 
 pub mod payload;
+
+pub mod interop;
+
+mod cbor;
+
+pub mod metrics;
@@ -0,0 +1,82 @@
+//! Counters of the Blobstream proofs that failed verification, by [VerificationStage], and of
+//! the blobs rejected.
+//!
+//! The counters are owned by whoever runs the verification and passed to the
+//! [OracleCelestiaProvider](crate::provider::OracleCelestiaProvider), see
+//! [OracleCelestiaProvider::with_metrics](crate::provider::OracleCelestiaProvider::with_metrics).
+//! A host running the client in process exports them on the `/metrics` endpoint of its health
+//! server.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use hana_blobstream::blobstream::VerificationStage;
+
+/// The number of Blobstream proofs that failed verification at each [VerificationStage].
+#[derive(Debug, Default)]
+pub struct VerificationMetrics {
+    failures: [AtomicU64; VerificationStage::ALL.len()],
+    /// The number of blobs rejected, including those failing a check outside of the proof stages.
+    rejected_blobs: AtomicU64,
+}
+
+impl VerificationMetrics {
+    /// Records a proof that failed verification at `stage`.
+    pub fn record_failure(&self, stage: VerificationStage) {
+        self.failures[stage as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a blob rejected by the verification.
+    pub fn record_rejected_blob(&self) {
+        self.rejected_blobs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of blobs rejected by the verification.
+    pub fn rejected_blobs(&self) -> u64 {
+        self.rejected_blobs.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of proofs that failed verification at each stage.
+    pub fn failures(&self) -> [(VerificationStage, u64); VerificationStage::ALL.len()] {
+        VerificationStage::ALL
+            .map(|stage| (stage, self.failures[stage as usize].load(Ordering::Relaxed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_failure_counts_each_stage_separately() {
+        let metrics = VerificationMetrics::default();
+        metrics.record_failure(VerificationStage::AccountProof);
+        metrics.record_failure(VerificationStage::AccountProof);
+        metrics.record_failure(VerificationStage::ShareProof);
+
+        for (stage, count) in metrics.failures() {
+            let expected = match stage {
+                VerificationStage::AccountProof => 2,
+                VerificationStage::ShareProof => 1,
+                _ => 0,
+            };
+            assert_eq!(count, expected, "stage {stage}");
+        }
+    }
+
+    #[test]
+    fn test_record_rejected_blob_is_counted_apart_from_the_stages() {
+        let metrics = VerificationMetrics::default();
+        metrics.record_rejected_blob();
+
+        assert_eq!(metrics.rejected_blobs(), 1);
+        assert!(metrics.failures().iter().all(|(_, count)| *count == 0));
+    }
+
+    #[test]
+    fn test_metrics_are_not_shared_between_instances() {
+        let first = VerificationMetrics::default();
+        let second = VerificationMetrics::default();
+        first.record_failure(VerificationStage::BlockHash);
+
+        assert!(second.failures().iter().all(|(_, count)| *count == 0));
+    }
+}
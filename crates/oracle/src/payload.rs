@@ -1,35 +1,481 @@
-use alloc::{boxed::Box, vec::Vec};
-use alloy_primitives::Bytes;
-use hana_blobstream::blobstream::BlobstreamProof;
-use serde::{Deserialize, Serialize};
-
-/// A structure containing a Celestia Blob and its corresponding proofs
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OraclePayload {
-    /// The Celestia blob data
-    pub blob: Bytes,
-    /// Blobstream proof data
-    pub blobstream_proof: BlobstreamProof,
+use alloy_primitives::{Address, B256};
+use celestia_types::{nmt::Namespace, Commitment};
+use hana_blobstream::blobstream::{blobstream_address, BlobstreamProof};
+
+pub use hana_blobstream::payload::OraclePayload;
+
+use crate::{errors::CelestiaVerificationError, provider::VerificationContext};
+
+/// Verifies that the blob in `payload` is the one with `commitment` included at `height`, against
+/// the canonical Blobstream deployment of `chain_id` at the L1 block `l1_head`. When `namespace`
+/// is given, the shares of the blob must have been proven in it.
+///
+/// These are the checks the [OracleCelestiaProvider](crate::provider::OracleCelestiaProvider)
+/// runs once it has loaded the boot info, without needing an oracle, see
+/// [verify_oracle_payload_with].
+///
+/// Security Note: `commitment`, `l1_head` and `chain_id` must come from a secure source, like the
+/// blob pointer and the boot info.
+pub fn verify_oracle_payload(
+    payload: &OraclePayload,
+    height: u64,
+    commitment: Commitment,
+    namespace: Option<Namespace>,
+    l1_head: B256,
+    chain_id: u64,
+) -> Result<(), CelestiaVerificationError> {
+    let context = VerificationContext {
+        l1_head,
+        l1_chain_id: chain_id,
+        anchored_storage_root: None,
+        blobstream_address: None,
+        expected_code_hash: None,
+    };
+    verify_oracle_payload_with(payload, height, commitment, namespace, &context)
 }
 
-impl OraclePayload {
-    /// Create a new OraclePayload instance
-    pub fn new(blob: Bytes, blobstream_proof: BlobstreamProof) -> Self {
-        Self {
-            blob,
-            blobstream_proof,
+/// Same as [verify_oracle_payload], against every value of `context`: the Blobstream deployment
+/// it selects, its pinned code hash and its anchored storage root, if any. A deployment selected
+/// for a chain with a canonical one other than it is rejected.
+///
+/// This is what the [OracleCelestiaProvider](crate::provider::OracleCelestiaProvider) calls for
+/// every payload it is served.
+///
+/// Security Note: `commitment` and `context` must come from a secure source, see
+/// [VerificationContext].
+pub fn verify_oracle_payload_with(
+    payload: &OraclePayload,
+    height: u64,
+    commitment: Commitment,
+    namespace: Option<Namespace>,
+    context: &VerificationContext,
+) -> Result<(), CelestiaVerificationError> {
+    let expected_blobstream_address = match (
+        blobstream_address(context.l1_chain_id),
+        context.blobstream_address,
+    ) {
+        (Some(canonical), Some(address)) if address != canonical => {
+            return Err(CelestiaVerificationError::CanonicalDeploymentOverride {
+                chain_id: context.l1_chain_id,
+                address,
+            })
         }
+        (Some(canonical), _) => canonical,
+        (None, Some(address)) => address,
+        (None, None) => {
+            return Err(CelestiaVerificationError::UnknownChainId(
+                context.l1_chain_id,
+            ))
+        }
+    };
+
+    if let Some(code_hash) = context.expected_code_hash {
+        verify_code_hash(&payload.blobstream_proof, code_hash)?;
+    }
+
+    match context.anchored_storage_root {
+        Some(storage_root) => verify_oracle_payload_anchored(
+            payload,
+            height,
+            commitment,
+            namespace,
+            storage_root,
+            expected_blobstream_address,
+        ),
+        None => {
+            verify_blobstream_proof_at(
+                &payload.blobstream_proof,
+                height,
+                context.l1_head,
+                expected_blobstream_address,
+            )?;
+            // Bind the verified blob to the commitment that was requested.
+            payload.verify_blob(commitment, namespace)
+        }
+    }
+}
+
+/// Same as [verify_oracle_payload], proving the data commitment against a Blobstream storage
+/// root verified out of band instead of against an L1 block, see
+/// [BlobstreamProof::validate_anchored].
+///
+/// Security Note: `commitment`, `anchored_storage_root` and `expected_blobstream_address` must
+/// come from a secure source that committed to them.
+pub fn verify_oracle_payload_anchored(
+    payload: &OraclePayload,
+    height: u64,
+    commitment: Commitment,
+    namespace: Option<Namespace>,
+    anchored_storage_root: B256,
+    expected_blobstream_address: Address,
+) -> Result<(), CelestiaVerificationError> {
+    payload
+        .blobstream_proof
+        .validate_anchored(height, anchored_storage_root, expected_blobstream_address)
+        .map_err(CelestiaVerificationError::from_proof_error)?;
+
+    // Bind the verified blob to the commitment that was requested.
+    payload.verify_blob(commitment, namespace)
+}
+
+/// Verifies a [BlobstreamProof] on its own, for tooling that only checks proofs and has no use
+/// for the blob bytes. See [verify_oracle_payload].
+pub fn verify_blobstream_proof(
+    proof: &BlobstreamProof,
+    height: u64,
+    l1_head: B256,
+    chain_id: u64,
+) -> Result<(), CelestiaVerificationError> {
+    let expected_blobstream_address =
+        blobstream_address(chain_id).ok_or(CelestiaVerificationError::UnknownChainId(chain_id))?;
+
+    verify_blobstream_proof_at(proof, height, l1_head, expected_blobstream_address)
+}
+
+/// Same as [verify_blobstream_proof], against the Blobstream deployment at
+/// `expected_blobstream_address` rather than the canonical one of a chain, e.g. one resolved with
+/// [resolve_blobstream_address](hana_blobstream::blobstream::resolve_blobstream_address).
+///
+/// Security Note: `l1_head` and `expected_blobstream_address` must come from a secure source.
+pub fn verify_blobstream_proof_at(
+    proof: &BlobstreamProof,
+    height: u64,
+    l1_head: B256,
+    expected_blobstream_address: Address,
+) -> Result<(), CelestiaVerificationError> {
+    // Verify the data commitment exists in storage on the supplied L1 block hash, that the
+    // blob's shares are included in the data root, and that the data root is committed to in
+    // the data commitment.
+    proof
+        .validate(height, l1_head, expected_blobstream_address)
+        .map_err(CelestiaVerificationError::from_proof_error)
+}
+
+/// Verifies that the Blobstream contract the proof was generated against runs the code hashing to
+/// `expected_code_hash`, see [BlobstreamProof::check_code_hash].
+///
+/// Security Note: `expected_code_hash` must come from a secure source, like the l1 head.
+pub fn verify_code_hash(
+    proof: &BlobstreamProof,
+    expected_code_hash: B256,
+) -> Result<(), CelestiaVerificationError> {
+    proof
+        .check_code_hash(expected_code_hash)
+        .map_err(CelestiaVerificationError::from_proof_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Bytes, U256};
+    use hana_blobstream::blobstream::VerificationStage;
+    use hana_blobstream::test_utils::{synthetic_namespace, synthetic_proof, SyntheticProof};
+
+    /// Ethereum mainnet, which has a canonical Blobstream deployment.
+    const CHAIN_ID: u64 = 1;
+
+    /// A payload proven against the canonical Blobstream deployment of [CHAIN_ID], with the
+    /// commitment of its blob.
+    fn canonical_payload() -> (SyntheticProof, OraclePayload, Commitment) {
+        let synthetic = synthetic_proof(blobstream_address(CHAIN_ID).unwrap(), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof.clone());
+        let commitment = payload.proven_commitment().unwrap();
+        (synthetic, payload, commitment)
+    }
+
+    fn failed_stage(synthetic: &SyntheticProof, height: u64, l1_head: B256) -> VerificationStage {
+        verify_blobstream_proof_at(
+            &synthetic.proof,
+            height,
+            l1_head,
+            synthetic.blobstream_address,
+        )
+        .unwrap_err()
+        .stage()
+        .expect("error tagged with its stage")
+    }
+
+    #[test]
+    fn test_verify_blobstream_proof_at_accepts_valid_proof() {
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        verify_blobstream_proof_at(
+            &synthetic.proof,
+            synthetic.height,
+            synthetic.l1_head,
+            synthetic.blobstream_address,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verification_errors_carry_the_failed_stage() {
+        let valid = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let (height, l1_head) = (valid.height, valid.l1_head);
+
+        assert_eq!(
+            failed_stage(&valid, height, B256::repeat_byte(1)),
+            VerificationStage::BlockHash
+        );
+
+        let mut account = valid.clone();
+        account.proof.blobstream_balance = U256::from(1);
+        assert_eq!(
+            failed_stage(&account, height, l1_head),
+            VerificationStage::AccountProof
+        );
+
+        let mut storage = valid.clone();
+        storage.proof.data_commitment = B256::repeat_byte(2);
+        assert_eq!(
+            failed_stage(&storage, height, l1_head),
+            VerificationStage::StorageProof
+        );
+
+        let mut share = valid.clone();
+        share.proof.data_root = celestia_types::hash::Hash::Sha256([3; 32]);
+        assert_eq!(
+            failed_stage(&share, height, l1_head),
+            VerificationStage::ShareProof
+        );
+
+        assert_eq!(
+            failed_stage(&valid, height + 1, l1_head),
+            VerificationStage::DataRootTuple
+        );
     }
 
-    /// Serialize the struct to bytes using serde with a binary format
-    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn core::error::Error>> {
-        let bytes = bincode::serialize(self)?;
-        Ok(bytes)
+    #[test]
+    fn test_verify_oracle_payload_accepts_the_requested_blob() {
+        let (synthetic, payload, commitment) = canonical_payload();
+
+        verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            Some(synthetic_namespace()),
+            synthetic.l1_head,
+            CHAIN_ID,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_an_unknown_chain() {
+        let (synthetic, payload, commitment) = canonical_payload();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            None,
+            synthetic.l1_head,
+            424242,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::UnknownChainId(424242)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_another_deployment() {
+        // Sepolia has another canonical deployment than the one the proof was generated for.
+        let (synthetic, payload, commitment) = canonical_payload();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            None,
+            synthetic.l1_head,
+            11155111,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CelestiaVerificationError::Proof(_)), "{err}");
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_another_l1_head() {
+        let (synthetic, payload, commitment) = canonical_payload();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            None,
+            B256::repeat_byte(1),
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                CelestiaVerificationError::Stage {
+                    stage: VerificationStage::BlockHash,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_another_commitment() {
+        let (synthetic, payload, _) = canonical_payload();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            Commitment::new([0x11; 32]),
+            None,
+            synthetic.l1_head,
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::CommitmentMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_another_namespace() {
+        let (synthetic, payload, commitment) = canonical_payload();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            Some(Namespace::new_v0(b"other").unwrap()),
+            synthetic.l1_head,
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::NamespaceMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_rejects_unproven_shares() {
+        // A valid proof of unrelated shares, paired with a blob matching the commitment.
+        let (synthetic, mut payload, _) = canonical_payload();
+        payload.blob = Bytes::from_static(b"hana-evil");
+        let commitment = payload.proven_commitment().unwrap();
+
+        let err = verify_oracle_payload(
+            &payload,
+            synthetic.height,
+            commitment,
+            None,
+            synthetic.l1_head,
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert_eq!(err.stage(), Some(VerificationStage::ShareProof));
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_with_uses_the_context() {
+        // A custom deployment on a chain without a canonical one.
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof.clone());
+        let commitment = payload.proven_commitment().unwrap();
+        let context = VerificationContext {
+            l1_head: synthetic.l1_head,
+            l1_chain_id: 424242,
+            anchored_storage_root: None,
+            blobstream_address: Some(synthetic.blobstream_address),
+            expected_code_hash: Some(synthetic.proof.blobstream_code_hash),
+        };
+        verify_oracle_payload_with(&payload, synthetic.height, commitment, None, &context).unwrap();
+
+        let pinned = VerificationContext {
+            expected_code_hash: Some(B256::repeat_byte(1)),
+            ..context
+        };
+        let err = verify_oracle_payload_with(&payload, synthetic.height, commitment, None, &pinned)
+            .unwrap_err();
+        assert_eq!(err.stage(), Some(VerificationStage::AccountProof));
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_with_rejects_canonical_overrides() {
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof);
+        let commitment = payload.proven_commitment().unwrap();
+        let context = VerificationContext {
+            l1_head: synthetic.l1_head,
+            l1_chain_id: CHAIN_ID,
+            anchored_storage_root: None,
+            blobstream_address: Some(synthetic.blobstream_address),
+            expected_code_hash: None,
+        };
+
+        let err =
+            verify_oracle_payload_with(&payload, synthetic.height, commitment, None, &context)
+                .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                CelestiaVerificationError::CanonicalDeploymentOverride { chain_id: CHAIN_ID, address }
+                    if address == synthetic.blobstream_address
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_oracle_payload_anchored_binds_the_blob() {
+        let (synthetic, payload, commitment) = canonical_payload();
+        let storage_root = synthetic.proof.storage_root;
+
+        verify_oracle_payload_anchored(
+            &payload,
+            synthetic.height,
+            commitment,
+            None,
+            storage_root,
+            synthetic.blobstream_address,
+        )
+        .unwrap();
+
+        let err = verify_oracle_payload_anchored(
+            &payload,
+            synthetic.height,
+            Commitment::new([0x11; 32]),
+            None,
+            storage_root,
+            synthetic.blobstream_address,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, CelestiaVerificationError::CommitmentMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_code_hash() {
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        verify_code_hash(&synthetic.proof, synthetic.proof.blobstream_code_hash).unwrap();
+
+        let err = verify_code_hash(&synthetic.proof, B256::repeat_byte(1)).unwrap_err();
+        assert_eq!(err.stage(), Some(VerificationStage::AccountProof));
     }
 
-    /// Deserialize from bytes back into the struct
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn core::error::Error>> {
-        let deserialized = bincode::deserialize(bytes)?;
-        Ok(deserialized)
+    #[test]
+    fn test_wrong_blobstream_address_has_no_stage() {
+        let synthetic = synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let err = verify_blobstream_proof_at(
+            &synthetic.proof,
+            synthetic.height,
+            synthetic.l1_head,
+            Address::repeat_byte(0xcc),
+        )
+        .unwrap_err();
+        assert!(err.stage().is_none());
     }
 }
@@ -1,49 +1,141 @@
 use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
-use alloc::vec::Vec;
-use alloy_primitives::{keccak256, Bytes};
+use alloy_primitives::{keccak256, Address, Bytes, B256};
 use async_trait::async_trait;
-use celestia_types::Commitment;
-use hana_blobstream::blobstream::{
-    blobstream_address, encode_data_root_tuple, verify_data_commitment,
-};
+use celestia_types::{nmt::Namespace, Commitment};
+use hana_blobstream::blobstream::{decode_blobstream_addresses, BlobstreamAddresses};
 use hana_celestia::CelestiaProvider;
-use kona_preimage::errors::PreimageOracleError;
-use kona_preimage::{CommsClient, PreimageKey, PreimageKeyType};
-use kona_proof::errors::OracleProviderError;
+use kona_preimage::{CommsClient, PreimageKey, PreimageKeyType, PreimageOracleClient};
 use kona_proof::{BootInfo, Hint};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::hint::HintWrapper;
-use crate::payload::OraclePayload;
+use crate::errors::{CelestiaVerificationError, OracleCelestiaProviderError};
+use crate::hint::{CelestiaDAHint, HintWrapper};
+use crate::metrics::VerificationMetrics;
+use crate::payload::{verify_oracle_payload_with, OraclePayload};
+
+/// The prefix of the error message returned when a Celestia blob fails verification, used by the
+/// host to distinguish verification failures from other oracle errors.
+pub const BLOB_VERIFICATION_FAILED: &str = "celestia blob verification failed";
+
+/// The local preimage key of the table of custom Blobstream deployments, encoded with
+/// [encode_blobstream_addresses](hana_blobstream::blobstream::encode_blobstream_addresses). The
+/// host serves it from `--blobstream-addresses`, an empty table when none are configured.
+///
+/// The [OracleCelestiaProvider] never reads it: nothing in the [BootInfo] commits to it.
+///
+/// *Security Note*: A program reading the table must commit to it itself, as it decides which
+/// contract proofs are verified against, and pass the entry it selects in a
+/// [VerificationContext].
+pub const BLOBSTREAM_ADDRESSES_KEY: u64 = 0x100;
+
+/// Loads the table of custom Blobstream deployments from the [BLOBSTREAM_ADDRESSES_KEY] local
+/// input, see its security note.
+pub async fn load_blobstream_addresses<O>(
+    oracle: &O,
+) -> Result<BlobstreamAddresses, OracleCelestiaProviderError>
+where
+    O: PreimageOracleClient + ?Sized,
+{
+    let encoded = oracle
+        .get(PreimageKey::new_local(BLOBSTREAM_ADDRESSES_KEY))
+        .await?;
+    decode_blobstream_addresses(&encoded)
+        .map_err(|err| OracleCelestiaProviderError::BlobstreamAddresses(err.to_string()))
+}
+
+/// The values Celestia blobs are verified against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationContext {
+    /// The L1 head the Blobstream proofs must be anchored at.
+    pub l1_head: B256,
+    /// The chain id of the L1 the Blobstream contract is deployed on.
+    pub l1_chain_id: u64,
+    /// The Blobstream storage root at the l1 head, when it was verified against L1 out of band.
+    /// Opts into skipping the per blob account proof, see
+    /// [BlobstreamProof::validate_anchored](hana_blobstream::blobstream::BlobstreamProof::validate_anchored).
+    ///
+    /// *Security Note*: The root must be committed to in the program that is verified on-chain,
+    /// like the l1 head.
+    pub anchored_storage_root: Option<B256>,
+    /// The Blobstream deployment proofs are verified against, instead of the canonical one of
+    /// `l1_chain_id`, e.g. resolved from a table of custom deployments with
+    /// [resolve_blobstream_address](hana_blobstream::blobstream::resolve_blobstream_address).
+    ///
+    /// Only chains without a canonical deployment can be given one, proofs are rejected when
+    /// another deployment is selected for a chain with a canonical one. When the context is
+    /// loaded from the [BootInfo], this is `None`.
+    ///
+    /// *Security Note*: The address must be committed to in the program that is verified
+    /// on-chain, like the l1 head.
+    pub blobstream_address: Option<Address>,
+    /// The hash the code of the Blobstream contract must have, when pinned, see
+    /// [BlobstreamProof::check_code_hash](hana_blobstream::blobstream::BlobstreamProof::check_code_hash).
+    ///
+    /// *Security Note*: The hash must be committed to in the program that is verified on-chain,
+    /// like the l1 head.
+    pub expected_code_hash: Option<B256>,
+}
 
 /// An oracle-backed da storage.
 #[derive(Debug, Clone)]
 pub struct OracleCelestiaProvider<T: CommsClient> {
     oracle: Arc<T>,
+    /// The verification context, loaded from the [BootInfo] when not set.
+    context: Option<VerificationContext>,
+    /// The counters of failed verifications, if they are recorded.
+    metrics: Option<Arc<VerificationMetrics>>,
 }
 
 impl<T: CommsClient + Clone> OracleCelestiaProvider<T> {
     /// Constructs a new `OracleBlobProvider`.
     pub fn new(oracle: Arc<T>) -> Self {
-        Self { oracle }
+        Self {
+            oracle,
+            context: None,
+            metrics: None,
+        }
+    }
+
+    /// Verifies blobs against `context` instead of loading the [BootInfo] from the oracle.
+    ///
+    /// *Security Note*: `context.l1_head` must be committed to in the verified program just like
+    /// the boot info's l1 head would be.
+    pub const fn with_verification_context(mut self, context: VerificationContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Records the blobs failing verification in `metrics`, by the stage they failed at.
+    pub fn with_metrics(mut self, metrics: Arc<VerificationMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 }
 
-#[async_trait]
-impl<T: CommsClient + Sync + Send> CelestiaProvider for OracleCelestiaProvider<T> {
-    type Error = OracleProviderError;
+impl<T: CommsClient + Sync + Send> OracleCelestiaProvider<T> {
+    /// Requests the [OraclePayload] behind `hint` from the host and verifies it against
+    /// Blobstream and the hinted commitment before returning the blob.
+    async fn get_verified_blob(
+        &self,
+        hint: CelestiaDAHint,
+    ) -> Result<Bytes, OracleCelestiaProviderError> {
+        Ok(self.get_verified_payload(hint).await?.blob)
+    }
 
-    async fn blob_get(&self, height: u64, commitment: Commitment) -> Result<Bytes, Self::Error> {
-        let mut encoded = Vec::new();
-        encoded.extend_from_slice(&height.to_le_bytes());
-        encoded.extend_from_slice(commitment.hash());
+    /// Same as [OracleCelestiaProvider::get_verified_blob], returning the whole verified payload.
+    async fn get_verified_payload(
+        &self,
+        hint: CelestiaDAHint,
+    ) -> Result<OraclePayload, OracleCelestiaProviderError> {
+        let height = hint.height;
+        let encoded = hint.encode();
 
         // Perform Inclusion checks against the data root
-        let hint = Hint::new(HintWrapper::CelestiaDA, encoded.clone());
+        let oracle_hint = Hint::new(HintWrapper::CelestiaDA, encoded.clone());
 
-        hint.send(&*self.oracle).await?;
+        oracle_hint.send(&*self.oracle).await?;
 
         let oracle_result = self
             .oracle
@@ -54,69 +146,287 @@ impl<T: CommsClient + Sync + Send> CelestiaProvider for OracleCelestiaProvider<T
             .await?;
 
         let payload = OraclePayload::from_bytes(&oracle_result)
-            .expect("Failed to deserialize Celestia Oracle Payload");
-
-        // Load the boot info from the oracle.
-        // *Security Note*: This BootInfo must be committed to in the program that is verified on-chain. The l1Head
-        // must be verified to match a blockhash on the blockchain where this program is being verified.
-        let boot = BootInfo::load(self.oracle.as_ref()).await?;
-
-        // Get the expected blobstream address for the chain id.
-        let expected_blobstream_address = blobstream_address(boot.rollup_config.l1_chain_id)
-            .expect("No canonical Blobstream address found for chain id");
-
-        // Verify the data commitment exists in storage on the supplied L1 block hash.
-        match verify_data_commitment(
-            payload.blobstream_proof.storage_root,
-            payload.blobstream_proof.storage_proof,
-            payload.blobstream_proof.account_proof,
-            payload.blobstream_proof.proof_nonce,
-            payload.blobstream_proof.data_commitment,
-            expected_blobstream_address,
-            payload.blobstream_proof.blobstream_balance,
-            payload.blobstream_proof.blobstream_nonce,
-            payload.blobstream_proof.blobstream_code_hash,
-            payload.blobstream_proof.block_header,
-            boot.l1_head,
-        ) {
-            Ok(_) => info!("Celestia data commitment succesfully verified!"),
-            Err(err) => {
-                return Err(OracleProviderError::Preimage(PreimageOracleError::Other(
-                    err.to_string(),
-                )))
-            }
+            .map_err(|err| OracleCelestiaProviderError::Payload(err.to_string()))?;
+
+        // *Security Note*: With `trust-host` the blob is returned without any verification, so a
+        // malicious host can feed arbitrary data into derivation.
+        if cfg!(feature = "trust-host") {
+            // Verified payloads are checked for the invariants their verifier relies on, which
+            // for an anchored proof don't include the account proof.
+            payload.blobstream_proof.check_invariants().map_err(|err| {
+                self.record_failure(CelestiaVerificationError::Proof(err.to_string()))
+            })?;
+            warn!(
+                "trust-host is enabled, skipping verification of the Celestia blob at height {}. \
+                 This is insecure and must never be used in production!",
+                height
+            );
+            return Ok(payload);
         }
 
-        match payload
-            .blobstream_proof
-            .share_proof
-            .verify(payload.blobstream_proof.data_root)
-        {
-            Ok(_) => info!("Celestia blobs ShareProof succesfully verified!"),
-            Err(err) => {
-                return Err(OracleProviderError::Preimage(PreimageOracleError::Other(
-                    err.to_string(),
-                )))
+        let context = match self.context {
+            Some(context) => context,
+            None => {
+                // Load the boot info from the oracle.
+                // *Security Note*: This BootInfo must be committed to in the program that is verified on-chain. The l1Head
+                // must be verified to match a blockhash on the blockchain where this program is being verified.
+                let boot = BootInfo::load(self.oracle.as_ref()).await?;
+                VerificationContext {
+                    l1_head: boot.l1_head,
+                    l1_chain_id: boot.rollup_config.l1_chain_id,
+                    anchored_storage_root: None,
+                    blobstream_address: None,
+                    expected_code_hash: None,
+                }
+            }
+        };
+
+        verify_oracle_payload_with(&payload, height, hint.commitment, hint.namespace, &context)
+            .map_err(|err| self.record_failure(err))?;
+        info!("Celestia blobstream proof successfully verified!");
+
+        Ok(payload)
+    }
+}
+
+impl<T: CommsClient> OracleCelestiaProvider<T> {
+    /// Counts a rejected blob and its failed stage, if metrics are recorded, returning `err`.
+    fn record_failure(&self, err: CelestiaVerificationError) -> CelestiaVerificationError {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_rejected_blob();
+            if let Some(stage) = err.stage() {
+                metrics.record_failure(stage);
             }
         }
+        err
+    }
+}
+
+#[async_trait]
+impl<T: CommsClient + Sync + Send> CelestiaProvider for OracleCelestiaProvider<T> {
+    type Error = OracleCelestiaProviderError;
+
+    async fn blob_get(&self, height: u64, commitment: Commitment) -> Result<Bytes, Self::Error> {
+        let hint = CelestiaDAHint {
+            height,
+            commitment,
+            namespace: None,
+        };
+
+        self.get_verified_blob(hint)
+            .await
+            .map_err(|err| err.with_blob(height, &commitment))
+    }
 
-        // Verify that the encoded data root tuple is valid against the data commitment in the contract.
-        let encoded_data_root_tuple =
-            encode_data_root_tuple(height, &payload.blobstream_proof.data_root);
-        match payload.blobstream_proof.data_root_tuple_proof.verify(
-            encoded_data_root_tuple,
-            payload.blobstream_proof.data_commitment.0,
-        ) {
-            Ok(_) => {
-                info!("Celestia data root tuple succesfully verified!")
+    /// The namespace is the one the shares of the blob were proven in, which the payload is
+    /// verified against.
+    async fn blob_get_with_namespace(
+        &self,
+        height: u64,
+        commitment: Commitment,
+    ) -> Result<(Option<Namespace>, Bytes), Self::Error> {
+        let hint = CelestiaDAHint {
+            height,
+            commitment,
+            namespace: None,
+        };
+
+        let payload = self
+            .get_verified_payload(hint)
+            .await
+            .map_err(|err| err.with_blob(height, &commitment))?;
+        Ok((
+            Some(payload.blobstream_proof.share_proof.namespace()),
+            payload.blob,
+        ))
+    }
+
+    async fn blob_get_namespaced(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: Commitment,
+    ) -> Result<Bytes, Self::Error> {
+        // The namespace is part of the hint data so that it is committed to in the preimage key.
+        let hint = CelestiaDAHint {
+            height,
+            commitment,
+            namespace: Some(namespace),
+        };
+
+        self.get_verified_blob(hint)
+            .await
+            .map_err(|err| err.with_blob(height, &commitment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{vec, vec::Vec};
+    use kona_preimage::{
+        errors::{PreimageOracleError, PreimageOracleResult},
+        HintWriterClient, PreimageOracleClient,
+    };
+
+    /// An oracle serving fixed preimages, ignoring hints.
+    #[derive(Debug, Clone, Default)]
+    struct MockOracle {
+        preimages: Arc<Vec<(PreimageKey, Vec<u8>)>>,
+    }
+
+    impl MockOracle {
+        /// Serves `payload` as the payload of `hint`.
+        fn serving(hint: &CelestiaDAHint, payload: Vec<u8>) -> Self {
+            let key = PreimageKey::new(*keccak256(hint.encode()), PreimageKeyType::GlobalGeneric);
+            Self {
+                preimages: Arc::new(vec![(key, payload)]),
             }
-            Err(err) => {
-                return Err(OracleProviderError::Preimage(PreimageOracleError::Other(
-                    err.to_string(),
-                )))
+        }
+    }
+
+    #[async_trait]
+    impl PreimageOracleClient for MockOracle {
+        async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+            self.preimages
+                .iter()
+                .find(|(preimage_key, _)| *preimage_key == key)
+                .map(|(_, preimage)| preimage.clone())
+                .ok_or(PreimageOracleError::KeyNotFound)
+        }
+
+        async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
+            let preimage = self.get(key).await?;
+            if preimage.len() != buf.len() {
+                return Err(PreimageOracleError::BufferLengthMismatch(
+                    buf.len(),
+                    preimage.len(),
+                ));
             }
+            buf.copy_from_slice(&preimage);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HintWriterClient for MockOracle {
+        async fn write(&self, _hint: &str) -> PreimageOracleResult<()> {
+            Ok(())
+        }
+    }
+
+    fn hint() -> CelestiaDAHint {
+        CelestiaDAHint {
+            height: 100,
+            commitment: Commitment::new([0x11; 32]),
+            namespace: None,
         }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payload_is_an_error() {
+        let oracle = MockOracle::serving(&hint(), vec![0xff; 8]);
+        let provider = OracleCelestiaProvider::new(Arc::new(oracle));
+
+        let err = provider.get_verified_blob(hint()).await.unwrap_err();
+        assert!(
+            matches!(err, OracleCelestiaProviderError::Payload(ref message) if !message.is_empty()),
+            "{err}"
+        );
+    }
+
+    #[cfg(not(feature = "trust-host"))]
+    #[tokio::test]
+    async fn test_verification_context_replaces_the_boot_info() {
+        let synthetic =
+            hana_blobstream::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof);
+        let hint = CelestiaDAHint {
+            height: synthetic.height,
+            commitment: payload.proven_commitment().unwrap(),
+            namespace: None,
+        };
+        // The oracle serves no boot info, any read of it fails.
+        let oracle = Arc::new(MockOracle::serving(&hint, payload.to_bytes().unwrap()));
+        OracleCelestiaProvider::new(oracle.clone())
+            .get_verified_blob(hint)
+            .await
+            .unwrap_err();
+
+        // A chain id without a canonical deployment, the context names the deployment.
+        let context = VerificationContext {
+            l1_head: synthetic.l1_head,
+            l1_chain_id: 424242,
+            anchored_storage_root: None,
+            blobstream_address: Some(synthetic.blobstream_address),
+            expected_code_hash: None,
+        };
+        let blob = OracleCelestiaProvider::new(oracle)
+            .with_verification_context(context)
+            .get_verified_blob(hint)
+            .await
+            .unwrap();
+        assert_eq!(blob, payload.blob);
+    }
+
+    #[cfg(not(feature = "trust-host"))]
+    #[tokio::test]
+    async fn test_reports_the_proven_namespace() {
+        let synthetic =
+            hana_blobstream::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), synthetic.proof);
+        let commitment = payload.proven_commitment().unwrap();
+        let hint = CelestiaDAHint {
+            height: synthetic.height,
+            commitment,
+            namespace: None,
+        };
+        let oracle = Arc::new(MockOracle::serving(&hint, payload.to_bytes().unwrap()));
+        let context = VerificationContext {
+            l1_head: synthetic.l1_head,
+            l1_chain_id: 424242,
+            anchored_storage_root: None,
+            blobstream_address: Some(synthetic.blobstream_address),
+            expected_code_hash: None,
+        };
+
+        let (namespace, blob) = OracleCelestiaProvider::new(oracle)
+            .with_verification_context(context)
+            .blob_get_with_namespace(synthetic.height, commitment)
+            .await
+            .unwrap();
+        assert_eq!(
+            namespace,
+            Some(hana_blobstream::test_utils::synthetic_namespace())
+        );
+        assert_eq!(blob, payload.blob);
+    }
+
+    #[cfg(feature = "trust-host")]
+    #[tokio::test]
+    async fn test_trust_host_returns_unverified_blob() {
+        // The proof is well formed but doesn't prove this blob, and no boot info is served to
+        // verify it against.
+        let synthetic =
+            hana_blobstream::test_utils::synthetic_proof(Address::repeat_byte(0xbb), 100);
+        let blob = Bytes::from_static(b"unverified");
+        let payload = OraclePayload::new(blob.clone(), synthetic.proof);
+        let oracle = MockOracle::serving(&hint(), payload.to_bytes().unwrap());
+        let provider = OracleCelestiaProvider::new(Arc::new(oracle));
+        assert_eq!(provider.get_verified_blob(hint()).await.unwrap(), blob);
 
-        Ok(payload.blob)
+        // Malformed proofs are still rejected.
+        let mut malformed = payload;
+        malformed.blobstream_proof.storage_proof.clear();
+        let oracle = MockOracle::serving(&hint(), malformed.to_bytes().unwrap());
+        let provider = OracleCelestiaProvider::new(Arc::new(oracle));
+        let err = provider.get_verified_blob(hint()).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                OracleCelestiaProviderError::Verification(CelestiaVerificationError::Proof(_))
+            ),
+            "{err}"
+        );
     }
 }
@@ -1,192 +1,778 @@
-use alloc::{boxed::Box, vec::Vec};
-use alloy_primitives::{keccak256, Address, Bytes, FixedBytes, B256};
-use alloy_provider::{Provider, RootProvider};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloy_consensus::Header;
+use alloy_primitives::{Address, Bytes, FixedBytes, B256, U256};
+use alloy_provider::Provider;
+use alloy_rlp::Decodable;
 use alloy_rpc_types_eth::{
-    BlockId, BlockNumberOrTag, Filter, FilterBlockOption, FilterSet, RpcBlockHash,
+    BlockId, BlockNumberOrTag, EIP1186AccountProofResponse, Filter, FilterBlockOption, FilterSet,
+    Header as RpcHeader, Log, RpcBlockHash,
 };
 use alloy_sol_types::SolEvent;
+use alloy_transport::TransportError;
 use anyhow::ensure;
-use celestia_rpc::{blobstream::BlobstreamClient, Client, HeaderClient, ShareClient};
-use celestia_types::{Blob, DataAvailabilityHeader};
+use celestia_types::{Blob, DataAvailabilityHeader, ShareProof};
+use core::num::NonZeroUsize;
+use futures::future::join_all;
 use hana_blobstream::blobstream::{
-    blobstream_address, calculate_mapping_slot, encode_data_root_tuple, verify_data_commitment,
-    BlobstreamProof, SP1Blobstream, SP1BlobstreamDataCommitmentStored, DATA_COMMITMENTS_SLOT,
+    calculate_mapping_slot, check_share_proof_rows, diagnose_header_hash, header_field_diff,
+    resolve_blobstream_address, verify_data_commitment, verify_data_root_tuple,
+    BlobstreamAddresses, BlobstreamProof, ProofLimits, SP1Blobstream,
+    SP1BlobstreamDataCommitmentStored, DATA_COMMITMENTS_SLOT,
+};
+use jsonrpsee::core::ClientError;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tracing::{info, warn};
+
+use crate::{
+    celestia_node::CelestiaNode,
+    commitment_index::CommitmentIndex,
+    errors::{is_celestia_api_error, ProofError, MISSING_HISTORY_ERRORS},
+    header_cache::HeaderCache,
+    rpc_counter::RpcCallCounter,
 };
-use tracing::info;
 
 // Geth has a default of 5000 block limit for filters
-const FILTER_BLOCK_RANGE: u64 = 5000;
+pub(crate) const FILTER_BLOCK_RANGE: u64 = 5000;
+
+/// The smallest window the backward scan shrinks to when the provider rejects its queries.
+const MIN_FILTER_BLOCK_RANGE: u64 = 100;
+
+/// The largest window the backward scan widens to over ranges without events.
+const MAX_FILTER_BLOCK_RANGE: u64 = 50_000;
+
+/// Windows returning more events than this are shrunk, to keep responses small.
+const DENSE_WINDOW_EVENTS: usize = 200;
+
+/// The number of L1 blocks queried at once by the backward scan of [find_data_commitment],
+/// tuned to the density of the events and to the range limits of the provider.
+///
+/// The window starts at [FILTER_BLOCK_RANGE], halves when the provider rejects a query or a
+/// window returns many events, and doubles while windows return none.
+#[derive(Debug, Clone, Copy)]
+struct AdaptiveWindow {
+    size: u64,
+}
+
+impl AdaptiveWindow {
+    const fn new() -> Self {
+        Self {
+            size: FILTER_BLOCK_RANGE,
+        }
+    }
+
+    const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Halves the window, returning `false` if it is already at its smallest.
+    fn shrink(&mut self) -> bool {
+        if self.size <= MIN_FILTER_BLOCK_RANGE {
+            return false;
+        }
+        self.size = (self.size / 2).max(MIN_FILTER_BLOCK_RANGE);
+        true
+    }
+
+    /// Adjusts the window after a query returned `events` events.
+    fn observe(&mut self, events: usize) {
+        if events > DENSE_WINDOW_EVENTS {
+            self.shrink();
+        } else if events == 0 {
+            self.size = (self.size * 2).min(MAX_FILTER_BLOCK_RANGE);
+        }
+    }
+}
+
+/// Constraints on the indexed topics of the `DataCommitmentStored` events fetched from L1.
+///
+/// Unset fields match any value. Constraining them lets the L1 node filter events server-side
+/// when the caller already knows part of the commitment it is looking for. The decoded events are
+/// checked against the constraints as well, in case the node ignored them.
+///
+/// The topic constraints assume the indexed fields of the canonical event, leave them unset
+/// when scanning a fork with a different [CommitmentEvent].
+#[derive(Debug, Clone, Default)]
+pub struct CommitmentTopicFilter {
+    /// The event the commitments are read from.
+    pub event: CommitmentEvent,
+    /// Only match commitments starting at this Celestia height.
+    pub start_block: Option<u64>,
+    /// Only match commitments ending at this Celestia height.
+    pub end_block: Option<u64>,
+    /// Only match this data commitment.
+    pub data_commitment: Option<B256>,
+}
+
+/// The event a Blobstream deployment emits when it stores a data commitment, letting forks of
+/// SP1Blobstream with a renamed or retyped event be scanned.
+#[derive(Debug, Clone, Copy)]
+pub struct CommitmentEvent {
+    /// The topic0 of the event.
+    pub signature_hash: B256,
+    /// Decodes a log matching `signature_hash`, returning `None` for logs that don't decode.
+    pub decode: fn(&Log) -> Option<SP1BlobstreamDataCommitmentStored>,
+}
+
+impl Default for CommitmentEvent {
+    /// The canonical SP1Blobstream `DataCommitmentStored` event.
+    fn default() -> Self {
+        Self {
+            signature_hash: SP1Blobstream::DataCommitmentStored::SIGNATURE_HASH,
+            decode: decode_data_commitment_stored,
+        }
+    }
+}
+
+/// Decodes a canonical SP1Blobstream `DataCommitmentStored` log.
+fn decode_data_commitment_stored(log: &Log) -> Option<SP1BlobstreamDataCommitmentStored> {
+    // Try to decode the log using SP1Blobstream's generated event decoder
+    let event = SP1Blobstream::DataCommitmentStored::decode_log(&log.clone().into()).ok()?;
+    Some(SP1BlobstreamDataCommitmentStored {
+        proof_nonce: event.proofNonce,
+        start_block: event.startBlock,
+        end_block: event.endBlock,
+        data_commitment: event.dataCommitment,
+    })
+}
+
+impl CommitmentTopicFilter {
+    /// Returns the topics for the indexed event fields, in declaration order.
+    fn topics(&self) -> [FilterSet<B256>; 3] {
+        let block_topic = |block: Option<u64>| -> FilterSet<B256> {
+            block
+                .map(|block| vec![B256::from(U256::from(block))].into())
+                .unwrap_or_default()
+        };
+
+        [
+            block_topic(self.start_block),
+            block_topic(self.end_block),
+            self.data_commitment
+                .map(|commitment| vec![commitment].into())
+                .unwrap_or_default(),
+        ]
+    }
+
+    /// Returns `true` if `event` satisfies the constraints of the filter.
+    fn matches(&self, event: &SP1BlobstreamDataCommitmentStored) -> bool {
+        self.start_block
+            .is_none_or(|start| start == event.start_block)
+            && self.end_block.is_none_or(|end| end == event.end_block)
+            && self
+                .data_commitment
+                .is_none_or(|commitment| commitment == event.data_commitment)
+    }
+}
 
 /// Find the data commitment  that contains the given Celestia height by parsing event logs
 /// This function assumes that the l1_head_block_number is one such that the event for relaying
 /// the celestia_height in question has already passed, its up to implementation on how to do this,
 /// Example: https://github.com/succinctlabs/op-succinct/blob/46482d3f21eb435b4cffae6c80d14bf3cc1c6e19/utils/celestia/host/src/host.rs#L40C17-L40C98
-pub async fn find_data_commitment(
+///
+/// If the caller knows roughly which L1 block relayed the commitment, it can pass it as
+/// `hint_block` to scan a window centered on that block first, falling back to the full backward
+/// scan if the commitment isn't found there. `topic_filter` narrows the events the L1 node returns.
+///
+/// The backward scan sizes its windows adaptively, see [AdaptiveWindow].
+pub async fn find_data_commitment<P: Provider>(
     celestia_height: u64,
     blobstream_address: Address,
-    eth_provider: &RootProvider,
+    eth_provider: &P,
     l1_head_block_number: u64,
+    hint_block: Option<u64>,
+    topic_filter: &CommitmentTopicFilter,
 ) -> Result<SP1BlobstreamDataCommitmentStored, Box<dyn core::error::Error>> {
-    // Calculate event signature manually for reliability
-    let event_signature = "DataCommitmentStored(uint256,uint64,uint64,bytes32)";
-    let event_selector = keccak256(event_signature.as_bytes());
-    let topic0: FilterSet<B256> = vec![event_selector].into();
+    // A node behind the requested head returns no logs for the missing blocks, which would send
+    // the scan all the way back to genesis.
+    let node_head = eth_provider.get_block_number().await?;
+    if node_head < l1_head_block_number {
+        return Err(ProofError::L1NodeBehind {
+            requested: l1_head_block_number,
+            node_head,
+        }
+        .into());
+    }
+
+    // Heights past the latest relayed block can't be found yet, no need to scan for them.
+    let latest_block = SP1Blobstream::new(blobstream_address, eth_provider)
+        .latestBlock()
+        .call()
+        .block(BlockId::number(l1_head_block_number))
+        .await?;
+    if celestia_height >= latest_block {
+        return Err(ProofError::CommitmentNotYetRelayed {
+            height: celestia_height,
+            latest_block,
+        }
+        .into());
+    }
+
+    if let Some(hint_block) = hint_block {
+        let hint_block = hint_block.min(l1_head_block_number);
+        let start = hint_block.saturating_sub(FILTER_BLOCK_RANGE / 2);
+        let end = (hint_block + FILTER_BLOCK_RANGE / 2).min(l1_head_block_number);
+
+        let events =
+            get_data_commitment_events(blobstream_address, eth_provider, start, end, topic_filter)
+                .await?;
+        if let Some((block_number, stored_event)) = covering_event(events, celestia_height)? {
+            info!(
+                "Found Data Root submission event near hint block_number={} proof_nonce={} start={} end={}",
+                block_number,
+                stored_event.proof_nonce,
+                stored_event.start_block,
+                stored_event.end_block
+            );
+            return Ok(stored_event);
+        }
+    }
 
     // Start from the given Ethereum block height and scan backwards
+    let mut window = AdaptiveWindow::new();
     let mut end = l1_head_block_number;
-    let mut start = end.saturating_sub(FILTER_BLOCK_RANGE);
 
     loop {
-        // Create filter for DataCommitmentStored events
-        let filter = Filter {
-            block_option: FilterBlockOption::Range {
-                from_block: Some(BlockNumberOrTag::Number(start)),
-                to_block: Some(BlockNumberOrTag::Number(end)),
-            },
-            address: vec![blobstream_address].into(),
-            topics: [
-                topic0.clone(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
-            ],
-        };
-
-        // Get logs using the client reference
-        let logs = eth_provider.get_logs(&filter).await?;
-
-        // Parse logs using the generated event type
-        for log in logs {
-            // Try to decode the log using SP1Blobstream's generated event decoder
-            if let Ok(event) = SP1Blobstream::DataCommitmentStored::decode_log(&log.clone().into())
-            {
-                // Check if this event contains the celestia_height
-                if event.startBlock <= celestia_height && celestia_height < event.endBlock {
-                    let stored_event = SP1BlobstreamDataCommitmentStored {
-                        proof_nonce: event.proofNonce,
-                        start_block: event.startBlock,
-                        end_block: event.endBlock,
-                        data_commitment: event.dataCommitment,
-                    };
-
-                    info!(
-                        "Found Data Root submission event block_number={} proof_nonce={} start={} end={}",
-                        log.clone().block_number.unwrap(),
-                        stored_event.proof_nonce,
-                        stored_event.start_block,
-                        stored_event.end_block
-                    );
-
-                    return Ok(stored_event);
-                }
+        let start = end.saturating_sub(window.size());
+        let events = match get_data_commitment_events(
+            blobstream_address,
+            eth_provider,
+            start,
+            end,
+            topic_filter,
+        )
+        .await
+        {
+            Ok(events) => events,
+            // Providers cap the block range or the number of logs of a query, retry the range
+            // with a smaller window.
+            Err(e) if window.shrink() => {
+                warn!(
+                    "Log query for L1 blocks {}..={} failed, retrying with a window of {} blocks: {}",
+                    start,
+                    end,
+                    window.size(),
+                    e
+                );
+                continue;
             }
+            Err(e) => return Err(e),
+        };
+        window.observe(events.len());
+
+        if let Some((block_number, stored_event)) = covering_event(events, celestia_height)? {
+            info!(
+                "Found Data Root submission event block_number={} proof_nonce={} start={} end={}",
+                block_number,
+                stored_event.proof_nonce,
+                stored_event.start_block,
+                stored_event.end_block
+            );
+
+            return Ok(stored_event);
         }
 
         // If we've reached the beginning of the chain, stop
         if start == 0 {
-            return Err("No matching event found for the given Celestia height".into());
+            return Err(ProofError::CommitmentNotFound(celestia_height).into());
         }
 
         // Move to the previous batch
         end = start;
-        start = end.saturating_sub(FILTER_BLOCK_RANGE);
     }
 }
 
+/// Returns the event of `events` covering `celestia_height`, if any.
+///
+/// Blobstream ranges never overlap, so distinct events covering the same height mean the event
+/// data can't be trusted and are reported as [ProofError::AmbiguousCommitment] rather than
+/// resolved by picking one.
+fn covering_event(
+    events: Vec<(u64, SP1BlobstreamDataCommitmentStored)>,
+    celestia_height: u64,
+) -> Result<Option<(u64, SP1BlobstreamDataCommitmentStored)>, ProofError> {
+    let mut covering = events
+        .into_iter()
+        .filter(|(_, event)| event.covers(celestia_height));
+    let Some(first) = covering.next() else {
+        return Ok(None);
+    };
+
+    for (_, other) in covering {
+        if other.proof_nonce != first.1.proof_nonce
+            || other.data_commitment != first.1.data_commitment
+        {
+            return Err(ProofError::AmbiguousCommitment {
+                height: celestia_height,
+                first: first.1.proof_nonce,
+                second: other.proof_nonce,
+            });
+        }
+    }
+
+    Ok(Some(first))
+}
+
+/// Returns whether Blobstream holds a data commitment covering `celestia_height` at the given L1
+/// block, without generating a proof.
+///
+/// Heights not relayed yet are answered from the contract's `latestBlock` alone, so this is cheap
+/// to poll before calling [get_blobstream_proof].
+pub async fn is_height_committed<P: Provider>(
+    celestia_height: u64,
+    blobstream_address: Address,
+    eth_provider: &P,
+    l1_head_block_number: u64,
+) -> Result<bool, Box<dyn core::error::Error>> {
+    match find_data_commitment(
+        celestia_height,
+        blobstream_address,
+        eth_provider,
+        l1_head_block_number,
+        None,
+        &CommitmentTopicFilter::default(),
+    )
+    .await
+    {
+        Ok(_) => Ok(true),
+        Err(err) => match err.downcast_ref::<ProofError>() {
+            Some(
+                ProofError::CommitmentNotYetRelayed { .. } | ProofError::CommitmentNotFound(_),
+            ) => Ok(false),
+            _ => Err(err),
+        },
+    }
+}
+
+/// Fetches every `DataCommitmentStored` event emitted by Blobstream between the `start` and `end`
+/// L1 blocks (inclusive) that match `topic_filter`, along with the L1 block number each event was
+/// emitted in.
+pub async fn get_data_commitment_events<P: Provider>(
+    blobstream_address: Address,
+    eth_provider: &P,
+    start: u64,
+    end: u64,
+    topic_filter: &CommitmentTopicFilter,
+) -> Result<Vec<(u64, SP1BlobstreamDataCommitmentStored)>, Box<dyn core::error::Error>> {
+    // The canonical selector is generated from the ABI so it can't drift from the event definition
+    let topic0: FilterSet<B256> = vec![topic_filter.event.signature_hash].into();
+    let [topic1, topic2, topic3] = topic_filter.topics();
+
+    // Create filter for DataCommitmentStored events
+    let filter = Filter {
+        block_option: FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Number(start)),
+            to_block: Some(BlockNumberOrTag::Number(end)),
+        },
+        address: vec![blobstream_address].into(),
+        topics: [topic0, topic1, topic2, topic3],
+    };
+
+    // Get logs using the client reference
+    let logs = eth_provider.get_logs(&filter).await?;
+
+    // Parse logs using the generated event type
+    let events = logs
+        .iter()
+        .filter_map(|log| {
+            // Pending logs have no block yet, so they can't be ordered against the L1 head.
+            let block_number = log.block_number?;
+            let stored_event = (topic_filter.event.decode)(log)?;
+            if !topic_filter.matches(&stored_event) {
+                return None;
+            }
+            // Blobstream never relays an empty or oversized range, so such an event can't be
+            // trusted to cover a height.
+            if !stored_event.has_valid_range() {
+                warn!(
+                    "Ignoring data commitment event with invalid range: {}",
+                    stored_event
+                );
+                return None;
+            }
+            Some((block_number, stored_event))
+        })
+        .collect();
+
+    Ok(events)
+}
+
+/// Options for generating a [BlobstreamProof].
+#[derive(Debug, Clone, Default)]
+pub struct ProofOptions {
+    /// How many blocks behind the l1 head the storage proof is anchored. The headers in between
+    /// are bundled into the proof so the anchor can be linked back to the l1 head.
+    pub anchor_depth: u64,
+    /// The expected hash of the Blobstream contract code. When set, generation fails if the
+    /// deployed code doesn't match, e.g. after an unexpected upgrade of the contract.
+    pub expected_code_hash: Option<B256>,
+    /// Limits on the storage and account proofs returned by the L1 node.
+    pub limits: ProofLimits,
+    /// Limits on the requests made concurrently to the Celestia and L1 nodes.
+    pub concurrency: ProofConcurrency,
+    /// Counter the calls made to the Celestia node are recorded in, if any.
+    pub rpc_counter: Option<RpcCallCounter>,
+    /// The L1 headers already fetched and checked, shared by the proofs generated with the same
+    /// options.
+    pub header_cache: HeaderCache,
+    /// Blobstream deployments of L1 chains without a canonical one, by L1 chain id.
+    pub blobstream_addresses: BlobstreamAddresses,
+    /// How many blocks the L1 node's head must be ahead of the block the storage proof is
+    /// anchored at, so that the proof isn't invalidated by a reorg. `0` accepts any block.
+    pub min_confirmations: u64,
+    /// Log the time spent in each stage of the proof generation, see [ProofTimings].
+    pub timing: bool,
+}
+
+impl ProofOptions {
+    /// Records a call to the Celestia node in the [ProofOptions::rpc_counter].
+    fn record_celestia_call(&self, method: &str) {
+        if let Some(ref counter) = self.rpc_counter {
+            counter.record(method);
+        }
+    }
+}
+
+/// The time spent in each stage of generating a proof, logged as tracing fields in milliseconds
+/// when [ProofOptions::timing] is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProofTimings {
+    /// Fetching and checking the L1 headers from the l1 head to the anchor block.
+    pub headers: Duration,
+    /// Fetching the L1 chain id the Blobstream deployment is resolved from.
+    pub chain_id: Duration,
+    /// Fetching the Celestia header of the blob's height.
+    pub celestia_header: Duration,
+    /// Fetching the share proof of the blob.
+    pub share_range: Duration,
+    /// Finding the data commitment event covering the blob's height.
+    pub event_scan: Duration,
+    /// Fetching the data root tuple inclusion proof.
+    pub data_root_tuple: Duration,
+    /// Fetching the balance of the Blobstream contract.
+    pub balance: Duration,
+    /// Fetching the code of the Blobstream contract.
+    pub code: Duration,
+    /// Fetching the nonce of the Blobstream contract.
+    pub nonce: Duration,
+    /// Fetching the data commitment stored under the proof nonce.
+    pub stored_commitment: Duration,
+    /// Fetching the storage proof of the data commitment, along with the account proof of the
+    /// Blobstream contract returned by the same call.
+    pub storage_proof: Duration,
+    /// Verifying the share, data root tuple and storage proofs.
+    pub verification: Duration,
+}
+
+impl ProofTimings {
+    /// Logs the timings of the proof of the blob at `height`.
+    fn log(&self, height: u64) {
+        info!(
+            height,
+            headers_ms = self.headers.as_millis() as u64,
+            chain_id_ms = self.chain_id.as_millis() as u64,
+            celestia_header_ms = self.celestia_header.as_millis() as u64,
+            share_range_ms = self.share_range.as_millis() as u64,
+            event_scan_ms = self.event_scan.as_millis() as u64,
+            data_root_tuple_ms = self.data_root_tuple.as_millis() as u64,
+            balance_ms = self.balance.as_millis() as u64,
+            code_ms = self.code.as_millis() as u64,
+            nonce_ms = self.nonce.as_millis() as u64,
+            stored_commitment_ms = self.stored_commitment.as_millis() as u64,
+            storage_proof_ms = self.storage_proof.as_millis() as u64,
+            verification_ms = self.verification.as_millis() as u64,
+            "Blobstream proof timing"
+        );
+    }
+}
+
+/// Limits on the number of proof requests in flight to each node, shared by the proofs generated
+/// with the same [ProofOptions] and their clones. Unset limits don't restrict concurrency.
+///
+/// A permit covers one request, or one group of requests made in a row to the same node, like
+/// the log queries of a data commitment scan or the reads of the Blobstream state.
+#[derive(Debug, Clone, Default)]
+pub struct ProofConcurrency {
+    celestia: Option<Arc<Semaphore>>,
+    l1: Option<Arc<Semaphore>>,
+}
+
+impl ProofConcurrency {
+    /// Creates limits allowing at most `celestia` requests to the Celestia node and `l1`
+    /// requests to the L1 node at a time.
+    pub fn new(celestia: Option<NonZeroUsize>, l1: Option<NonZeroUsize>) -> Self {
+        Self {
+            celestia: celestia.map(|permits| Arc::new(Semaphore::new(permits.get()))),
+            l1: l1.map(|permits| Arc::new(Semaphore::new(permits.get()))),
+        }
+    }
+
+    /// Waits for a slot to make a request to the Celestia node, returning `None` when the
+    /// requests aren't limited.
+    pub async fn celestia_permit(&self) -> Option<SemaphorePermit<'_>> {
+        match self.celestia {
+            Some(ref semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Waits for a slot to make a request to the L1 node, returning `None` when the requests
+    /// aren't limited.
+    pub async fn l1_permit(&self) -> Option<SemaphorePermit<'_>> {
+        match self.l1 {
+            Some(ref semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// Fetches the proofs of several blobs concurrently, the requests made to each node being
+/// limited by `options.concurrency`.
+///
+/// The results are returned in the order of `blobs`, see [get_blobstream_proof].
+pub async fn get_blobstream_proofs<N: CelestiaNode, P: Provider, A: Provider>(
+    celestia_node: &N,
+    l1_provider: &P,
+    l1_head: FixedBytes<32>,
+    blobs: Vec<(u64, Blob)>,
+    archive_provider: Option<&A>,
+    options: &ProofOptions,
+) -> Vec<Result<BlobstreamProof, anyhow::Error>> {
+    join_all(blobs.into_iter().map(|(height, blob)| {
+        get_blobstream_proof(
+            celestia_node,
+            l1_provider,
+            l1_head,
+            height,
+            blob,
+            None,
+            archive_provider,
+            options,
+        )
+    }))
+    .await
+}
+
 /// Fetches a `BlobstreamProof` for the given blob, height, and blobstream contract address
-pub async fn get_blobstream_proof(
-    celestia_node: &Client,
-    l1_provider: &RootProvider,
+///
+/// `celestia_node` is usually a [celestia_rpc::Client], see [CelestiaNode]. `l1_provider` can be
+/// any alloy [Provider], e.g. a caching or failover wrapper around a node. If it no longer has the
+/// Blobstream state at the anchor block, the state is read from `archive_provider` instead, when
+/// given, see [read_historical_state].
+pub async fn get_blobstream_proof<N: CelestiaNode, P: Provider, A: Provider>(
+    celestia_node: &N,
+    l1_provider: &P,
     l1_head: FixedBytes<32>,
     height: u64,
     blob: Blob,
+    commitment_index: Option<&Mutex<CommitmentIndex>>,
+    archive_provider: Option<&A>,
+    options: &ProofOptions,
 ) -> Result<BlobstreamProof, anyhow::Error> {
-    let l1_block = l1_provider.get_block_by_hash(l1_head).await?.unwrap();
+    // An empty blob has no share range to prove, bail before issuing any requests. Blobs encode
+    // to at least one share whatever their length, so emptiness is told from the data.
+    if blob.data.is_empty() {
+        return Err(ProofError::EmptyBlob.into());
+    }
 
-    let block_id = BlockId::Hash(RpcBlockHash::from(B256::from(l1_head)));
+    let mut timings = ProofTimings::default();
+    let mut stage = Instant::now();
 
-    let block_header = l1_block.header;
-    let chain_id = l1_provider.get_chain_id().await?;
+    // Walk back from the l1 head to the anchor block, collecting the headers in between.
+    let mut block_header = checked_header(l1_provider, l1_head, options).await?;
+    let mut header_chain = Vec::with_capacity(options.anchor_depth as usize);
+    for _ in 0..options.anchor_depth {
+        let parent = checked_header(l1_provider, block_header.parent_hash, options).await?;
+        header_chain.push(core::mem::replace(&mut block_header, parent).inner);
+    }
+    let anchor_hash = block_header.hash;
+    timings.headers = stage.elapsed();
+    stage = Instant::now();
 
-    let blobstream_address =
-        blobstream_address(chain_id).expect("No canonical Blobstream address found for chain id");
+    // A block too close to the node's head may still be reorged out, invalidating the proof.
+    if options.min_confirmations > 0 {
+        let node_head = {
+            let _permit = options.concurrency.l1_permit().await;
+            l1_provider.get_block_number().await?
+        };
+        let confirmations = node_head.saturating_sub(block_header.number);
+        if confirmations < options.min_confirmations {
+            return Err(ProofError::InsufficientConfirmations {
+                block: block_header.number,
+                confirmations,
+                required: options.min_confirmations,
+            }
+            .into());
+        }
+    }
 
-    // Fetch the block's data root
-    let header = celestia_node.header_get_by_height(height).await?;
+    timings.headers += stage.elapsed();
+    stage = Instant::now();
 
-    // values needed to verify account proof
-    let blobstream_balance = l1_provider
-        .get_balance(blobstream_address)
-        .block_id(block_id)
-        .await?;
+    let chain_id = {
+        let _permit = options.concurrency.l1_permit().await;
+        l1_provider.get_chain_id().await?
+    };
 
-    let code = l1_provider
-        .get_code_at(blobstream_address)
-        .block_id(block_id)
-        .await?;
+    let blobstream_address = resolve_blobstream_address(chain_id, &options.blobstream_addresses)
+        .ok_or(ProofError::UnknownBlobstreamDeployment(chain_id))?;
 
-    let blobstream_code_hash: B256;
-    if code.is_empty() {
-        anyhow::bail!("Error getting blobstream code hash (address has no code)")
-    } else {
-        blobstream_code_hash = B256::from(alloy_primitives::keccak256(&code));
-    }
+    timings.chain_id = stage.elapsed();
+    stage = Instant::now();
 
-    let blobstream_nonce = l1_provider
-        .get_transaction_count(blobstream_address)
-        .block_id(block_id)
-        .await?;
+    // Fetch the block's data root
+    let header = {
+        let _permit = options.concurrency.celestia_permit().await;
+        options.record_celestia_call("header.GetByHeight");
+        celestia_node
+            .header(height)
+            .await
+            .map_err(|err| classify_celestia_error(err, height))?
+    };
+    timings.celestia_header = stage.elapsed();
 
     // celestia data root
     let blob_index = match blob.index {
         Some(index) => index,
         None => anyhow::bail!("Could not get blob index for blobstream proof"),
     };
-    let data_root = header.dah.hash();
-    let (start_index, end_index) =
-        calculate_indices(header.dah.clone(), blob_index, blob.shares_len() as u64);
+    let dah = N::dah(&header);
+    let data_root = dah.hash();
+    let shares_length = u64::try_from(blob.shares_len()).map_err(|_| {
+        ProofError::InvalidShareIndices("blob share count does not fit in u64".to_string())
+    })?;
+    let (start_index, end_index) = calculate_indices(dah, blob_index, shares_length)?;
 
-    let share_proof = celestia_node
-        .share_get_range(&header, start_index, end_index)
-        .await
-        .expect("Failed getting share proof")
-        .proof;
+    stage = Instant::now();
+    let share_proof = {
+        let _permit = options.concurrency.celestia_permit().await;
+        options.record_celestia_call("share.GetRange");
+        celestia_node
+            .share_proof(&header, start_index, end_index)
+            .await
+            .map_err(|err| classify_celestia_error(err, height))?
+    };
+    timings.share_range = stage.elapsed();
+    stage = Instant::now();
 
     // validate the proof before placing it on the KV store
     match share_proof.verify(data_root) {
-        Ok(_) => info!("Celestia share proof succesfully verified!"),
+        Ok(_) => info!("Celestia share proof successfully verified!"),
         Err(err) => return Err(err.into()),
     }
 
-    let event = find_data_commitment(height, blobstream_address, l1_provider, block_header.number)
-        .await
-        .unwrap();
-
-    let data_root_proof = celestia_node
-        .blobstream_get_data_root_tuple_inclusion_proof(height, event.start_block, event.end_block)
-        .await?;
+    // The share proof only commits to the data root, also make sure it covers the rows of the
+    // data square the indices were computed from.
+    ensure_share_proof_rows(&share_proof, dah, start_index, end_index)?;
+    ensure_share_proof_range(&share_proof, dah, start_index, end_index)?;
+    timings.verification = stage.elapsed();
+    stage = Instant::now();
 
-    let encoded_data_root_tuple = encode_data_root_tuple(height, &data_root);
+    let l1_permit = options.concurrency.l1_permit().await;
+    let event = match commitment_index {
+        // The lookup scans a copy of the index, so the lock is only held to copy it and to merge
+        // the scanned blocks back, not across the RPCs of the scan.
+        Some(index) => {
+            let mut snapshot = index.lock().await.clone();
+            let event = snapshot
+                .find(height, blobstream_address, l1_provider, block_header.number)
+                .await;
+            index.lock().await.merge(snapshot);
+            event
+        }
+        None => {
+            find_data_commitment(
+                height,
+                blobstream_address,
+                l1_provider,
+                block_header.number,
+                None,
+                &CommitmentTopicFilter::default(),
+            )
+            .await
+        }
+    }
+    .map_err(|e| match e.downcast::<ProofError>() {
+        Ok(err) => anyhow::Error::from(*err),
+        Err(e) => anyhow::anyhow!("Failed to find data commitment: {e}"),
+    })?;
+    drop(l1_permit);
+    // Events may come from a commitment index written before ranges were checked.
+    ensure!(
+        event.has_valid_range(),
+        "data commitment covers an invalid range: {}",
+        event
+    );
+    timings.event_scan = stage.elapsed();
+    stage = Instant::now();
 
-    data_root_proof
-        .verify(encoded_data_root_tuple, *event.data_commitment)
-        .expect("failed to verify data root tuple inclusion proof");
+    let data_root_proof = {
+        let _permit = options.concurrency.celestia_permit().await;
+        options.record_celestia_call("blobstream.GetDataRootTupleInclusionProof");
+        celestia_node
+            .data_root_tuple_proof(height, event.start_block, event.end_block)
+            .await?
+    };
+    timings.data_root_tuple = stage.elapsed();
+    stage = Instant::now();
 
-    let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, event.proof_nonce);
+    verify_data_root_tuple(height, &data_root, &data_root_proof, event.data_commitment)?;
+    timings.verification += stage.elapsed();
+    stage = Instant::now();
 
-    let slot_b256 = B256::from_slice(slot.as_slice());
+    let l1_permit = options.concurrency.l1_permit().await;
+    let state = read_historical_state(
+        l1_provider,
+        archive_provider,
+        blobstream_address,
+        anchor_hash,
+        event.proof_nonce,
+        &mut timings,
+    )
+    .await?;
+    drop(l1_permit);
+    stage = Instant::now();
 
-    let proof_response = l1_provider
-        .get_proof(blobstream_address, vec![slot_b256])
-        .block_id(block_id)
-        .await?;
+    if state.code.is_empty() {
+        anyhow::bail!("Error getting blobstream code hash (address has no code)")
+    }
+    let blobstream_code_hash = alloy_primitives::keccak256(&state.code);
+    if let Some(expected) = options.expected_code_hash {
+        if blobstream_code_hash != expected {
+            return Err(ProofError::CodeHashMismatch {
+                expected,
+                actual: blobstream_code_hash,
+            }
+            .into());
+        }
+    }
 
-    ensure!(
-        proof_response.address == blobstream_address,
-        "storage proof address does not match blobstream address"
-    );
+    // Make sure the event's nonce indexes the event's commitment in storage before proving that
+    // slot.
+    if state.stored_commitment != event.data_commitment {
+        return Err(ProofError::CommitmentMismatch {
+            proof_nonce: event.proof_nonce,
+            event: event.data_commitment,
+            stored: state.stored_commitment,
+        }
+        .into());
+    }
+    let BlobstreamState {
+        balance: blobstream_balance,
+        nonce: blobstream_nonce,
+        proof: proof_response,
+        ..
+    } = state;
 
-    // get blobstream address from L1 Provider, check against the proof and also verify in program
+    // The account values were fetched with separate calls, make sure they all describe the same
+    // Blobstream account as the proof.
+    ensure_account_consistency(
+        &proof_response,
+        blobstream_address,
+        blobstream_balance,
+        blobstream_nonce,
+        blobstream_code_hash,
+    )?;
 
     let proof_bytes: Vec<Bytes> = proof_response
         .storage_proof
@@ -194,53 +780,1619 @@ pub async fn get_blobstream_proof(
         .flat_map(|proof| proof.proof.into_iter())
         .collect();
 
-    match verify_data_commitment(
-        proof_response.storage_hash,
-        proof_bytes.clone(),
-        proof_response.account_proof.clone(),
-        event.proof_nonce,
-        event.data_commitment,
+    let proof = BlobstreamProof::new(
         blobstream_address,
+        data_root,
+        event.data_commitment,
+        data_root_proof,
+        share_proof,
+        event.proof_nonce,
+        proof_response.storage_hash,
+        proof_bytes,
+        proof_response.account_proof,
         blobstream_balance,
         blobstream_nonce,
         blobstream_code_hash,
-        block_header.inner.clone(),
-        l1_head,
-    ) {
-        Ok(_) => {
-            println!("Succesfully verified Blobstream data commitment");
+        block_header.inner,
+        header_chain,
+    );
+
+    proof.check_limits(&options.limits)?;
+
+    verify_data_commitment(
+        proof.storage_root,
+        &proof.storage_proof,
+        &proof.account_proof,
+        proof.proof_nonce,
+        proof.data_commitment,
+        blobstream_address,
+        proof.blobstream_balance,
+        proof.blobstream_nonce,
+        proof.blobstream_code_hash,
+        &proof.block_header,
+        anchor_hash,
+    )?;
+    info!("Successfully verified Blobstream data commitment");
+    timings.verification += stage.elapsed();
+
+    if options.timing {
+        timings.log(height);
+    }
+
+    Ok(proof)
+}
+
+/// The Blobstream account and the storage of a data commitment at the anchor block.
+#[derive(Debug)]
+struct BlobstreamState {
+    /// The balance of the Blobstream contract.
+    balance: U256,
+    /// The code of the Blobstream contract.
+    code: Bytes,
+    /// The nonce of the Blobstream contract.
+    nonce: u64,
+    /// The data commitment stored under the proof nonce.
+    stored_commitment: B256,
+    /// The account proof and the storage proof of the data commitment slot.
+    proof: EIP1186AccountProofResponse,
+}
+
+/// Reads the [BlobstreamState] of the contract at `blobstream_address` for `proof_nonce` at the
+/// L1 block `anchor_hash`.
+///
+/// Reading the state of a block that isn't recent requires an archive node. If `l1_provider`
+/// pruned it, every read is retried on `archive_provider`, when given, so that the account
+/// fields, the stored commitment and the proofs all come from the same node.
+///
+/// The time spent on each successful read is added to `timings`.
+async fn read_historical_state<P: Provider, A: Provider>(
+    l1_provider: &P,
+    archive_provider: Option<&A>,
+    blobstream_address: Address,
+    anchor_hash: B256,
+    proof_nonce: U256,
+    timings: &mut ProofTimings,
+) -> Result<BlobstreamState, anyhow::Error> {
+    match read_blobstream_state(
+        l1_provider,
+        blobstream_address,
+        anchor_hash,
+        proof_nonce,
+        timings,
+    )
+    .await
+    {
+        Ok(state) => Ok(state),
+        Err(err) if is_missing_state_error(&err) => match archive_provider {
+            Some(archive_provider) => {
+                warn!(
+                    "L1 node can't serve the Blobstream state at block {}, retrying on the archive node: {}",
+                    anchor_hash, err
+                );
+                Ok(read_blobstream_state(
+                    archive_provider,
+                    blobstream_address,
+                    anchor_hash,
+                    proof_nonce,
+                    timings,
+                )
+                .await?)
+            }
+            None => Err(ProofError::MissingState(anchor_hash).into()),
+        },
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Reads the [BlobstreamState] from a single provider, see [read_historical_state].
+async fn read_blobstream_state<P: Provider>(
+    provider: &P,
+    blobstream_address: Address,
+    anchor_hash: B256,
+    proof_nonce: U256,
+    timings: &mut ProofTimings,
+) -> Result<BlobstreamState, TransportError> {
+    let block_id = BlockId::Hash(RpcBlockHash::from(anchor_hash));
+    let slot = calculate_mapping_slot(DATA_COMMITMENTS_SLOT, proof_nonce);
+
+    let mut stage = Instant::now();
+    let mut lap = |timing: &mut Duration| {
+        *timing += stage.elapsed();
+        stage = Instant::now();
+    };
+
+    let balance = provider
+        .get_balance(blobstream_address)
+        .block_id(block_id)
+        .await?;
+    lap(&mut timings.balance);
+    let code = provider
+        .get_code_at(blobstream_address)
+        .block_id(block_id)
+        .await?;
+    lap(&mut timings.code);
+    let nonce = provider
+        .get_transaction_count(blobstream_address)
+        .block_id(block_id)
+        .await?;
+    lap(&mut timings.nonce);
+    let stored_commitment = provider
+        .get_storage_at(blobstream_address, U256::from_be_bytes(slot.0))
+        .block_id(block_id)
+        .await?;
+    lap(&mut timings.stored_commitment);
+    let proof = provider
+        .get_proof(blobstream_address, vec![slot])
+        .block_id(block_id)
+        .await?;
+    lap(&mut timings.storage_proof);
+
+    Ok(BlobstreamState {
+        balance,
+        code,
+        nonce,
+        stored_commitment: B256::from(stored_commitment),
+        proof,
+    })
+}
 
-            Ok(BlobstreamProof::new(
-                data_root,
-                event.data_commitment,
-                data_root_proof,
-                share_proof,
-                event.proof_nonce,
-                proof_response.storage_hash,
-                proof_bytes,
-                proof_response.account_proof,
-                blobstream_balance,
-                blobstream_nonce,
-                blobstream_code_hash,
-                block_header.inner.clone(),
-            ))
+/// Messages returned by L1 nodes that pruned the state of the requested block.
+const MISSING_STATE_ERRORS: [&str; 2] = [
+    "missing trie node",
+    "distance to target block exceeds maximum proof window",
+];
+
+/// Returns `true` if the RPC error means the node doesn't have the state of the requested block.
+fn is_missing_state_error(err: &TransportError) -> bool {
+    err.as_error_resp().is_some_and(|resp| {
+        MISSING_STATE_ERRORS
+            .iter()
+            .any(|message| resp.message.contains(message))
+    })
+}
+
+/// Turns a Celestia RPC error into a [ProofError::CelestiaHistoryUnavailable] if the node lacks the
+/// history of `height`, so it can be told apart from other failures.
+fn classify_celestia_error(err: ClientError, height: u64) -> anyhow::Error {
+    if is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS) {
+        ProofError::CelestiaHistoryUnavailable {
+            height,
+            message: err.to_string(),
         }
-        Err(err) => Err(err),
+        .into()
+    } else {
+        err.into()
+    }
+}
+
+/// Returns the header of the L1 block `hash`, checked to hash to it, from the
+/// [ProofOptions::header_cache] if possible.
+async fn checked_header<P: Provider>(
+    l1_provider: &P,
+    hash: B256,
+    options: &ProofOptions,
+) -> Result<RpcHeader, anyhow::Error> {
+    if let Some(header) = options.header_cache.get(hash) {
+        return Ok(header);
+    }
+
+    let _permit = options.concurrency.l1_permit().await;
+    let block = l1_provider
+        .get_block_by_hash(hash)
+        .await?
+        .ok_or(ProofError::L1BlockNotFound(hash))?;
+    ensure_header_hash(l1_provider, &block.header.inner, hash).await?;
+    options.header_cache.insert(block.header.clone());
+
+    Ok(block.header)
+}
+
+/// Ensures `header`, returned by the L1 node for the block `hash`, hashes to it, so that a node
+/// serializing headers differently from their consensus encoding is caught here rather than by
+/// the client.
+///
+/// On mismatch, the header is diffed against the consensus encoding served by
+/// `debug_getRawHeader` when the node supports it, and otherwise checked for optional fields the
+/// block doesn't have. The diverging fields are logged and reported in the error.
+async fn ensure_header_hash<P: Provider>(
+    l1_provider: &P,
+    header: &Header,
+    hash: B256,
+) -> Result<(), ProofError> {
+    let computed = header.hash_slow();
+    if computed == hash {
+        return Ok(());
+    }
+
+    let raw_header: Option<Bytes> = l1_provider
+        .raw_request("debug_getRawHeader".into(), (hash,))
+        .await
+        .ok();
+    let fields = match raw_header.and_then(|raw| Header::decode(&mut raw.as_ref()).ok()) {
+        Some(expected) => header_field_diff(header, &expected),
+        None => diagnose_header_hash(header, hash).into_iter().collect(),
+    };
+    let fields = if fields.is_empty() {
+        "unknown".to_string()
+    } else {
+        fields.join(", ")
+    };
+
+    warn!(
+        "L1 header of block {} hashes to {} instead of {}, diverging fields: {}",
+        header.number, computed, hash, fields
+    );
+    Err(ProofError::L1HeaderHashMismatch {
+        expected: hash,
+        computed,
+        fields,
+    })
+}
+
+/// Ensures the account proof was fetched for `blobstream_address` and that the balance, nonce and
+/// code hash fetched separately for that address match the proven account.
+fn ensure_account_consistency(
+    proof_response: &EIP1186AccountProofResponse,
+    blobstream_address: Address,
+    blobstream_balance: U256,
+    blobstream_nonce: u64,
+    blobstream_code_hash: B256,
+) -> Result<(), anyhow::Error> {
+    ensure!(
+        proof_response.address == blobstream_address,
+        "storage proof address does not match blobstream address"
+    );
+    ensure!(
+        proof_response.balance == blobstream_balance,
+        "blobstream balance does not match the account proof"
+    );
+    ensure!(
+        proof_response.nonce == blobstream_nonce,
+        "blobstream nonce does not match the account proof"
+    );
+    ensure!(
+        proof_response.code_hash == blobstream_code_hash,
+        "hash of the blobstream code {} does not match the account proof's {}",
+        blobstream_code_hash,
+        proof_response.code_hash
+    );
+
+    Ok(())
+}
+
+/// Ensures the row roots referenced by `share_proof` are the row roots of `dah` for the rows
+/// holding the shares between `start_index` (inclusive) and `end_index` (exclusive).
+fn ensure_share_proof_rows(
+    share_proof: &ShareProof,
+    dah: &DataAvailabilityHeader,
+    start_index: u64,
+    end_index: u64,
+) -> Result<(), anyhow::Error> {
+    let ods_size = (dah.row_roots().len() / 2) as u64;
+    ensure!(
+        ods_size > 0 && start_index < end_index,
+        "invalid share range for the data square"
+    );
+
+    let start_row = (start_index / ods_size) as usize;
+    let end_row = ((end_index - 1) / ods_size) as usize;
+    let expected_row_roots = dah
+        .row_roots()
+        .get(start_row..=end_row)
+        .ok_or_else(|| anyhow::anyhow!("share range exceeds the data square"))?;
+
+    ensure!(
+        share_proof.row_proof().row_roots() == expected_row_roots,
+        "share proof row roots do not match the data availability header"
+    );
+
+    Ok(())
+}
+
+/// Ensures `share_proof` proves exactly the shares between `start_index` (inclusive) and
+/// `end_index` (exclusive) of the original data square, so it is bound to the blob's shares rather
+/// than to any valid shares of the same rows.
+///
+/// The contiguity of the proven range is checked by [check_share_proof_rows], as the client does,
+/// and the rows themselves by [ensure_share_proof_rows]. Only what needs the width of the square
+/// from the data availability header is checked here: the position of the range and that every
+/// row but the last is proven up to its end.
+fn ensure_share_proof_range(
+    share_proof: &ShareProof,
+    dah: &DataAvailabilityHeader,
+    start_index: u64,
+    end_index: u64,
+) -> Result<(), ProofError> {
+    let ods_size = (dah.row_roots().len() / 2) as u64;
+    let mismatch = |proven: Option<(u64, u64)>| ProofError::ShareRangeMismatch {
+        expected_start: start_index,
+        expected_end: end_index,
+        proven,
+    };
+
+    check_share_proof_rows(share_proof).map_err(|_| mismatch(None))?;
+
+    let share_proofs = share_proof.share_proofs();
+    let (Some(first), Some((last, rows))) = (share_proofs.first(), share_proofs.split_last())
+    else {
+        return Err(mismatch(None));
+    };
+    if rows.iter().any(|row| u64::from(row.end_idx()) != ods_size) {
+        return Err(mismatch(None));
+    }
+    let start_row = start_index / ods_size;
+    let end_row = (end_index - 1) / ods_size;
+    let proven_start = start_row * ods_size + u64::from(first.start_idx());
+    let proven_end = end_row * ods_size + u64::from(last.end_idx());
+    if (proven_start, proven_end) != (start_index, end_index) {
+        return Err(mismatch(Some((proven_start, proven_end))));
     }
+
+    Ok(())
 }
 
+/// Converts the global EDS index of a blob's first share into the range of ODS share indices
+/// covered by the blob, ensuring the blob lies within the original data square.
+///
+/// Malformed data availability headers are reported as [ProofError::InvalidShareIndices] rather
+/// than overflowing.
 fn calculate_indices(
-    data_availability_header: DataAvailabilityHeader,
+    data_availability_header: &DataAvailabilityHeader,
     blob_index: u64,
     shares_length: u64,
-) -> (u64, u64) {
-    let eds_row_roots = data_availability_header.row_roots();
-    let eds_size: u64 = eds_row_roots.len().try_into().unwrap();
+) -> Result<(u64, u64), ProofError> {
+    let invalid = |message: String| ProofError::InvalidShareIndices(message);
+
+    let eds_size = u64::try_from(data_availability_header.row_roots().len())
+        .map_err(|_| invalid("data availability header row count does not fit in u64".into()))?;
+    if eds_size == 0 {
+        return Err(invalid("data availability header has no rows".into()));
+    }
+    if eds_size % 2 != 0 {
+        return Err(invalid(format!(
+            "data availability header has an odd number of rows {eds_size}"
+        )));
+    }
     let ods_size: u64 = eds_size / 2;
+    let eds_shares = eds_size.checked_mul(eds_size).ok_or_else(|| {
+        invalid(format!(
+            "extended data square of width {eds_size} overflows"
+        ))
+    })?;
+    if blob_index >= eds_shares {
+        return Err(invalid(format!(
+            "blob index {blob_index} is outside the extended data square of width {eds_size}"
+        )));
+    }
 
     let first_row_index: u64 = blob_index / eds_size;
+    let first_column_index = blob_index % eds_size;
+    if first_row_index >= ods_size || first_column_index >= ods_size {
+        return Err(invalid(format!(
+            "blob index {blob_index} is not in the original data square of width {ods_size}"
+        )));
+    }
+
+    // The row index is below the ODS width, and the ODS holds fewer shares than the EDS.
     let start_index = blob_index - (first_row_index * ods_size);
-    let end_index = start_index + shares_length;
+    let end_index = start_index.checked_add(shares_length).ok_or_else(|| {
+        invalid(format!(
+            "blob of {shares_length} shares starting at {start_index} overflows"
+        ))
+    })?;
+    if end_index > ods_size * ods_size {
+        return Err(invalid(format!(
+            "blob shares {start_index}..{end_index} exceed the original data square of width {ods_size}"
+        )));
+    }
+
+    Ok((start_index, end_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U64;
+    use alloy_provider::{ProviderBuilder, RootProvider};
+    use alloy_rpc_types_eth::{Block, BlockTransactions, EIP1186StorageProof};
+    use alloy_transport::mock::Asserter;
+    use celestia_types::{AppVersion, MerkleProof};
+    use core::future::{ready, Future};
+    use hana_blobstream::blobstream::DATA_COMMITMENT_MAX;
+    use hana_blobstream::test_utils::{
+        synthetic_namespace, synthetic_proof, SyntheticProof, SYNTHETIC_PROOF_NONCE,
+    };
+    use jsonrpsee::types::ErrorObject;
+
+    const BLOBSTREAM: Address = Address::repeat_byte(0xbb);
+
+    /// A chain id without a canonical Blobstream deployment, resolved through
+    /// [ProofOptions::blobstream_addresses].
+    const CHAIN_ID: u64 = 31337;
+
+    /// A [CelestiaNode] serving the square of a [SyntheticProof], its header being the data
+    /// availability header.
+    struct MockCelestiaNode {
+        dah: DataAvailabilityHeader,
+        share_proof: ShareProof,
+        data_root_tuple_proof: MerkleProof,
+        /// Whether the node pruned the history of every block.
+        pruned: bool,
+    }
+
+    impl MockCelestiaNode {
+        fn new(synthetic: &SyntheticProof) -> Self {
+            Self {
+                dah: synthetic.dah.clone(),
+                share_proof: synthetic.proof.share_proof.clone(),
+                data_root_tuple_proof: synthetic.proof.data_root_tuple_proof.clone(),
+                pruned: false,
+            }
+        }
+    }
+
+    impl CelestiaNode for MockCelestiaNode {
+        type Header = DataAvailabilityHeader;
+
+        fn header(
+            &self,
+            _height: u64,
+        ) -> impl Future<Output = Result<Self::Header, ClientError>> + Send {
+            ready(if self.pruned {
+                Err(ClientError::Call(ErrorObject::owned(
+                    crate::errors::CELESTIA_API_ERROR_CODE,
+                    "header: not found",
+                    None::<()>,
+                )))
+            } else {
+                Ok(self.dah.clone())
+            })
+        }
+
+        fn dah(header: &Self::Header) -> &DataAvailabilityHeader {
+            header
+        }
+
+        fn share_proof(
+            &self,
+            _header: &Self::Header,
+            start: u64,
+            end: u64,
+        ) -> impl Future<Output = Result<ShareProof, ClientError>> + Send {
+            // The synthetic blob is the first share of the square.
+            assert_eq!((start, end), (0, 1));
+            ready(Ok(self.share_proof.clone()))
+        }
+
+        fn data_root_tuple_proof(
+            &self,
+            _height: u64,
+            _start: u64,
+            _end: u64,
+        ) -> impl Future<Output = Result<MerkleProof, ClientError>> + Send {
+            ready(Ok(self.data_root_tuple_proof.clone()))
+        }
+    }
+
+    /// Returns the blob whose share the [SyntheticProof] proves.
+    fn synthetic_blob() -> Blob {
+        let mut blob = Blob::new(
+            synthetic_namespace(),
+            b"hana-blob".to_vec(),
+            None,
+            AppVersion::latest(),
+        )
+        .unwrap();
+        blob.index = Some(0);
+        blob
+    }
+
+    /// Queues the responses of the L1 node to [get_blobstream_proof] of `synthetic` on
+    /// `asserter`, in request order.
+    fn push_l1_responses(asserter: &Asserter, synthetic: &SyntheticProof) {
+        push_l1_head(asserter, synthetic);
+        push_event_scan(asserter, synthetic);
+        push_anchor_state(asserter, synthetic);
+    }
+
+    /// Queues the responses of the event scan: the node head, Blobstream's latest block and the
+    /// event covering the height.
+    fn push_event_scan(asserter: &Asserter, synthetic: &SyntheticProof) {
+        asserter.push_success(&U64::from(synthetic.proof.block_header.number));
+        asserter.push_success(&Bytes::from(
+            U256::from(synthetic.height + 2).to_be_bytes::<32>(),
+        ));
+        asserter.push_success(&vec![commitment_log(synthetic)]);
+    }
+
+    /// Queues the l1 head, which is also the anchor block, and the L1 chain id.
+    fn push_l1_head(asserter: &Asserter, synthetic: &SyntheticProof) {
+        push_l1_block(asserter, synthetic);
+        asserter.push_success(&U64::from(CHAIN_ID));
+    }
+
+    /// Queues the l1 head, which is also the anchor block.
+    fn push_l1_block(asserter: &Asserter, synthetic: &SyntheticProof) {
+        let block: Block = Block {
+            header: RpcHeader {
+                hash: synthetic.l1_head,
+                inner: synthetic.proof.block_header.clone(),
+                total_difficulty: None,
+                size: None,
+            },
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            withdrawals: None,
+        };
+        asserter.push_success(&block);
+    }
+
+    /// The event relaying the commitment of a [SyntheticProof], emitted the block before the l1
+    /// head.
+    fn commitment_log(synthetic: &SyntheticProof) -> Log {
+        let proof = &synthetic.proof;
+        let event = SP1Blobstream::DataCommitmentStored {
+            proofNonce: proof.proof_nonce,
+            startBlock: synthetic.height,
+            endBlock: synthetic.height + 2,
+            dataCommitment: proof.data_commitment,
+        };
+        Log {
+            inner: alloy_primitives::Log {
+                address: synthetic.blobstream_address,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(proof.block_header.number - 1),
+            ..Default::default()
+        }
+    }
+
+    /// Queues the Blobstream state at the anchor block.
+    fn push_anchor_state(asserter: &Asserter, synthetic: &SyntheticProof) {
+        push_anchor_state_storing(asserter, synthetic, synthetic.proof.data_commitment);
+    }
+
+    /// Queues the Blobstream state at the anchor block, the slot of the proof nonce reading
+    /// `stored_commitment`.
+    fn push_anchor_state_storing(
+        asserter: &Asserter,
+        synthetic: &SyntheticProof,
+        stored_commitment: B256,
+    ) {
+        let proof = &synthetic.proof;
+        asserter.push_success(&proof.blobstream_balance);
+        asserter.push_success(&Bytes::from_static(b"SP1Blobstream"));
+        asserter.push_success(&U64::from(proof.blobstream_nonce));
+        asserter.push_success(&U256::from_be_bytes(stored_commitment.0));
+        asserter.push_success(&EIP1186AccountProofResponse {
+            address: synthetic.blobstream_address,
+            balance: proof.blobstream_balance,
+            code_hash: proof.blobstream_code_hash,
+            nonce: proof.blobstream_nonce,
+            storage_hash: proof.storage_root,
+            account_proof: proof.account_proof.clone(),
+            storage_proof: vec![EIP1186StorageProof {
+                key: calculate_mapping_slot(DATA_COMMITMENTS_SLOT, proof.proof_nonce).into(),
+                value: U256::from_be_bytes(proof.data_commitment.0),
+                proof: proof.storage_proof.clone(),
+            }],
+        });
+    }
+
+    fn options() -> ProofOptions {
+        ProofOptions {
+            blobstream_addresses: BlobstreamAddresses::from([(CHAIN_ID, BLOBSTREAM)]),
+            ..Default::default()
+        }
+    }
+
+    /// Queues the responses of [read_blobstream_state] on `asserter`, in request order.
+    fn push_state(asserter: &Asserter, balance: u64, commitment: B256) {
+        asserter.push_success(&U256::from(balance));
+        asserter.push_success(&Bytes::from_static(b"code"));
+        asserter.push_success(&U64::from(1));
+        asserter.push_success(&U256::from_be_bytes(commitment.0));
+        asserter.push_success(&EIP1186AccountProofResponse {
+            address: BLOBSTREAM,
+            balance: U256::from(balance),
+            nonce: 1,
+            ..Default::default()
+        });
+    }
+
+    /// Returns a data availability header with `rows` row and column roots, all the first row
+    /// root of the synthetic square.
+    fn square_of(rows: usize) -> DataAvailabilityHeader {
+        let dah = serde_json::to_value(&synthetic_proof(BLOBSTREAM, 100).dah).unwrap();
+        let root = dah["row_roots"][0].clone();
+        serde_json::from_value(serde_json::json!({
+            "row_roots": vec![root.clone(); rows],
+            "column_roots": vec![root; rows],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_calculate_indices_rejects_shares_outside_the_ods() {
+        // An extended square of width 4, the original square of width 2 holding 4 shares.
+        let dah = square_of(4);
+
+        for (blob_index, shares, message) in [
+            (16, 1, "outside the extended data square"),
+            // The parity shares right of the first row, and below the original square.
+            (2, 1, "not in the original data square"),
+            (8, 1, "not in the original data square"),
+            // Starting at the last share of the original square.
+            (5, 2, "exceed the original data square"),
+        ] {
+            match calculate_indices(&dah, blob_index, shares) {
+                Err(ProofError::InvalidShareIndices(err)) => {
+                    assert!(err.contains(message), "{blob_index}: {err}")
+                }
+                other => panic!("{blob_index}: unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_indices_rejects_malformed_squares() {
+        for (rows, message) in [
+            (0, "has no rows"),
+            (1, "odd number of rows"),
+            (3, "odd number of rows"),
+        ] {
+            match calculate_indices(&square_of(rows), 0, 1) {
+                Err(ProofError::InvalidShareIndices(err)) => {
+                    assert!(err.contains(message), "{rows}: {err}")
+                }
+                other => panic!("{rows}: unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_indices_accepts_blobs_fitting_the_ods() {
+        let dah = square_of(4);
+
+        // The first share of the second row is the third share of the original square.
+        assert_eq!(calculate_indices(&dah, 4, 1).unwrap(), (2, 3));
+        // The whole original square, and its last share.
+        assert_eq!(calculate_indices(&dah, 0, 4).unwrap(), (0, 4));
+        assert_eq!(calculate_indices(&dah, 5, 1).unwrap(), (3, 4));
+    }
+
+    #[tokio::test]
+    async fn test_checked_header_fetches_each_block_once() {
+        let inner = Header {
+            number: 5,
+            ..Default::default()
+        };
+        let hash = inner.hash_slow();
+        let block: Block = Block {
+            header: RpcHeader {
+                hash,
+                inner,
+                total_difficulty: None,
+                size: None,
+            },
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            withdrawals: None,
+        };
+
+        // A single response is queued, a second request would fail.
+        let asserter = Asserter::new();
+        asserter.push_success(&block);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let options = ProofOptions::default();
+        let shared = options.clone();
+        assert_eq!(checked_header(&l1, hash, &options).await.unwrap().number, 5);
+        assert_eq!(checked_header(&l1, hash, &shared).await.unwrap().number, 5);
+    }
+
+    #[tokio::test]
+    async fn test_checked_header_reports_missing_blocks() {
+        // The node returns null for blocks it doesn't have.
+        let asserter = Asserter::new();
+        asserter.push_success(&None::<Block>);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let hash = B256::repeat_byte(1);
+        let err = checked_header(&l1, hash, &ProofOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::L1BlockNotFound(missing)) if *missing == hash
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits_are_shared_by_clones() {
+        let concurrency = ProofConcurrency::new(None, NonZeroUsize::new(1));
+        let clone = concurrency.clone();
+
+        let permit = concurrency.l1_permit().await;
+        assert!(permit.is_some());
+        // The only L1 slot is taken, the clone must wait for it.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), clone.l1_permit())
+                .await
+                .is_err()
+        );
+        drop(permit);
+        assert!(clone.l1_permit().await.is_some());
+
+        // Unset limits never wait.
+        assert!(concurrency.celestia_permit().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_historical_state_is_missing_without_archive() {
+        let asserter = Asserter::new();
+        asserter.push_failure_msg("missing trie node 0123 (path ) state is not available");
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let err = read_historical_state(
+            &l1,
+            None::<&RootProvider>,
+            BLOBSTREAM,
+            B256::repeat_byte(1),
+            U256::from(1),
+            &mut ProofTimings::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::MissingState(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_historical_state_falls_back_to_archive() {
+        let l1_asserter = Asserter::new();
+        l1_asserter.push_failure_msg("missing trie node 0123 (path ) state is not available");
+        let l1 = ProviderBuilder::new().connect_mocked_client(l1_asserter);
+
+        let archive_asserter = Asserter::new();
+        let commitment = B256::repeat_byte(7);
+        push_state(&archive_asserter, 42, commitment);
+        let archive = ProviderBuilder::new().connect_mocked_client(archive_asserter);
+
+        let state = read_historical_state(
+            &l1,
+            Some(&archive),
+            BLOBSTREAM,
+            B256::repeat_byte(1),
+            U256::from(1),
+            &mut ProofTimings::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(state.balance, U256::from(42));
+        assert_eq!(state.nonce, 1);
+        assert_eq!(state.stored_commitment, commitment);
+        assert_eq!(state.proof.address, BLOBSTREAM);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_do_not_fall_back_to_archive() {
+        let l1_asserter = Asserter::new();
+        l1_asserter.push_failure_msg("rate limited");
+        let l1 = ProviderBuilder::new().connect_mocked_client(l1_asserter);
+
+        // The archive has no responses queued, a request to it would fail differently.
+        let archive = ProviderBuilder::new().connect_mocked_client(Asserter::new());
+
+        let err = read_historical_state(
+            &l1,
+            Some(&archive),
+            BLOBSTREAM,
+            B256::repeat_byte(1),
+            U256::from(1),
+            &mut ProofTimings::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("rate limited"));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_against_mock_nodes() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        push_l1_responses(&asserter, &synthetic);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let proof = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &options(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(proof.proof_nonce, U256::from(SYNTHETIC_PROOF_NONCE));
+        assert_eq!(proof.data_commitment, synthetic.proof.data_commitment);
+        assert_eq!(proof.data_root, synthetic.proof.data_root);
+        proof
+            .validate(synthetic.height, synthetic.l1_head, BLOBSTREAM)
+            .unwrap();
+    }
+
+    /// A subscriber recording the names of the fields of every event.
+    #[derive(Debug, Clone, Default)]
+    struct FieldRecorder(Arc<std::sync::Mutex<Vec<&'static str>>>);
+
+    impl tracing::Subscriber for FieldRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            self.0
+                .lock()
+                .unwrap()
+                .extend(event.fields().map(|field| field.name()));
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_logs_timings() {
+        const TIMING_FIELDS: [&str; 12] = [
+            "headers_ms",
+            "chain_id_ms",
+            "celestia_header_ms",
+            "share_range_ms",
+            "event_scan_ms",
+            "data_root_tuple_ms",
+            "balance_ms",
+            "code_ms",
+            "nonce_ms",
+            "stored_commitment_ms",
+            "storage_proof_ms",
+            "verification_ms",
+        ];
+
+        for timing in [false, true] {
+            let synthetic = synthetic_proof(BLOBSTREAM, 100);
+            let celestia = MockCelestiaNode::new(&synthetic);
+            let asserter = Asserter::new();
+            push_l1_responses(&asserter, &synthetic);
+            let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+            let options = ProofOptions {
+                timing,
+                ..options()
+            };
+
+            let recorder = FieldRecorder::default();
+            let _guard = tracing::subscriber::set_default(recorder.clone());
+            get_blobstream_proof(
+                &celestia,
+                &l1,
+                synthetic.l1_head,
+                synthetic.height,
+                synthetic_blob(),
+                None,
+                None::<&RootProvider>,
+                &options,
+            )
+            .await
+            .unwrap();
+
+            let fields = recorder.0.lock().unwrap();
+            for field in TIMING_FIELDS {
+                assert_eq!(fields.contains(&field), timing, "{field}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_rejects_a_mismatched_stored_commitment() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        push_l1_head(&asserter, &synthetic);
+        push_event_scan(&asserter, &synthetic);
+        // The slot of the event's nonce holds another commitment than the event.
+        let stored = B256::repeat_byte(0x33);
+        push_anchor_state_storing(&asserter, &synthetic, stored);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &options(),
+        )
+        .await
+        .unwrap_err();
+        match err.downcast_ref::<ProofError>() {
+            Some(ProofError::CommitmentMismatch {
+                proof_nonce,
+                event,
+                stored: mismatched,
+            }) => {
+                assert_eq!(*proof_nonce, U256::from(SYNTHETIC_PROOF_NONCE));
+                assert_eq!(*event, synthetic.proof.data_commitment);
+                assert_eq!(*mismatched, stored);
+            }
+            other => panic!("unexpected error {other:?}: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_rejects_an_unexpected_code_hash() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        push_l1_responses(&asserter, &synthetic);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let expected = B256::repeat_byte(0x44);
+        let options = ProofOptions {
+            expected_code_hash: Some(expected),
+            ..options()
+        };
+
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &options,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(&ProofError::CodeHashMismatch { expected: e, actual })
+                if e == expected && actual == synthetic.proof.blobstream_code_hash
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_rejects_an_unknown_deployment() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        push_l1_head(&asserter, &synthetic);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        // The chain has no canonical deployment, and none is configured.
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &ProofOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::UnknownBlobstreamDeployment(CHAIN_ID))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_rejects_an_empty_blob() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        // No response is queued, a request to the L1 node would fail differently.
+        let l1 = ProviderBuilder::new().connect_mocked_client(Asserter::new());
+        let mut blob = synthetic_blob();
+        blob.data.clear();
+
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            blob,
+            None,
+            None::<&RootProvider>,
+            &options(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::EmptyBlob)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_rejects_an_unconfirmed_anchor() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        push_l1_block(&asserter, &synthetic);
+        // The node's head is only 10 blocks ahead of the anchor block.
+        let anchor = synthetic.proof.block_header.number;
+        asserter.push_success(&U64::from(anchor + 10));
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let options = ProofOptions {
+            min_confirmations: 64,
+            ..options()
+        };
+
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &options,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(&ProofError::InsufficientConfirmations {
+                block,
+                confirmations: 10,
+                required: 64,
+            }) if block == anchor
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_reports_pruned_celestia_history() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode {
+            pruned: true,
+            ..MockCelestiaNode::new(&synthetic)
+        };
+        let asserter = Asserter::new();
+        push_l1_responses(&asserter, &synthetic);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let err = get_blobstream_proof(
+            &celestia,
+            &l1,
+            synthetic.l1_head,
+            synthetic.height,
+            synthetic_blob(),
+            None,
+            None::<&RootProvider>,
+            &options(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::CelestiaHistoryUnavailable { height: 100, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobstream_proof_resolves_later_proofs_from_the_index() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let celestia = MockCelestiaNode::new(&synthetic);
+        let asserter = Asserter::new();
+        // The first proof scans L1 into the index, the second one must not query any log.
+        push_l1_head(&asserter, &synthetic);
+        asserter.push_success(&vec![commitment_log(&synthetic)]);
+        push_anchor_state(&asserter, &synthetic);
+        push_l1_head(&asserter, &synthetic);
+        push_anchor_state(&asserter, &synthetic);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let index = Mutex::new(CommitmentIndex::new());
+        let options = ProofOptions {
+            header_cache: HeaderCache::new(0),
+            ..options()
+        };
+
+        for _ in 0..2 {
+            let proof = get_blobstream_proof(
+                &celestia,
+                &l1,
+                synthetic.l1_head,
+                synthetic.height,
+                synthetic_blob(),
+                Some(&index),
+                None::<&RootProvider>,
+                &options,
+            )
+            .await
+            .unwrap();
+            assert_eq!(proof.proof_nonce, U256::from(SYNTHETIC_PROOF_NONCE));
+        }
+        assert_eq!(index.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_data_commitment_events_skips_pending_logs() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let asserter = Asserter::new();
+        let pending = Log {
+            block_number: None,
+            ..commitment_log(&synthetic)
+        };
+        asserter.push_success(&vec![pending, commitment_log(&synthetic)]);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let events =
+            get_data_commitment_events(BLOBSTREAM, &l1, 0, 1000, &CommitmentTopicFilter::default())
+                .await
+                .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, synthetic.proof.block_header.number - 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_data_commitment_rejects_oversized_ranges() {
+        for (range, valid) in [
+            (DATA_COMMITMENT_MAX, true),
+            (DATA_COMMITMENT_MAX + 1, false),
+        ] {
+            let event = SP1Blobstream::DataCommitmentStored {
+                proofNonce: U256::from(1),
+                startBlock: 100,
+                endBlock: 100 + range,
+                dataCommitment: B256::repeat_byte(1),
+            };
+            let log = Log {
+                inner: alloy_primitives::Log {
+                    address: BLOBSTREAM,
+                    data: event.encode_log_data(),
+                },
+                block_number: Some(10),
+                ..Default::default()
+            };
+
+            // The l1 head is within the first window, so a single log query reaches genesis.
+            let asserter = Asserter::new();
+            asserter.push_success(&U64::from(1_000));
+            asserter.push_success(&Bytes::from(U256::from(100 + range).to_be_bytes::<32>()));
+            asserter.push_success(&vec![log]);
+            let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+            let result = find_data_commitment(
+                100,
+                BLOBSTREAM,
+                &l1,
+                1_000,
+                None,
+                &CommitmentTopicFilter::default(),
+            )
+            .await;
+            if valid {
+                assert_eq!(result.unwrap().end_block, 100 + DATA_COMMITMENT_MAX);
+            } else {
+                assert!(matches!(
+                    result.unwrap_err().downcast_ref::<ProofError>(),
+                    Some(ProofError::CommitmentNotFound(100))
+                ));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_data_commitment_events_skips_unmatched_topics() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let matching = commitment_log(&synthetic);
+        let other = SP1Blobstream::DataCommitmentStored {
+            proofNonce: U256::from(SYNTHETIC_PROOF_NONCE + 1),
+            startBlock: synthetic.height + 2,
+            endBlock: synthetic.height + 4,
+            dataCommitment: B256::repeat_byte(0x22),
+        };
+        let other = Log {
+            inner: alloy_primitives::Log {
+                address: BLOBSTREAM,
+                data: other.encode_log_data(),
+            },
+            ..matching.clone()
+        };
+
+        // The node ignores the topics of the filter and returns both logs.
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![other, matching]);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let topic_filter = CommitmentTopicFilter {
+            end_block: Some(synthetic.height + 2),
+            ..Default::default()
+        };
+
+        let events = get_data_commitment_events(BLOBSTREAM, &l1, 0, 1000, &topic_filter)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].1.data_commitment, synthetic.proof.data_commitment);
+    }
+
+    alloy_sol_types::sol! {
+        /// The commitment event of a Blobstream fork, renamed and without indexed fields.
+        event CommitmentRelayed(uint256 nonce, uint64 startHeight, uint64 endHeight, bytes32 commitment);
+    }
+
+    /// Decodes a [CommitmentRelayed] log.
+    fn decode_commitment_relayed(log: &Log) -> Option<SP1BlobstreamDataCommitmentStored> {
+        let event = CommitmentRelayed::decode_log(&log.inner).ok()?;
+        Some(SP1BlobstreamDataCommitmentStored {
+            proof_nonce: event.nonce,
+            start_block: event.startHeight,
+            end_block: event.endHeight,
+            data_commitment: event.commitment,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_find_data_commitment_scans_a_custom_event() {
+        let synthetic = synthetic_proof(BLOBSTREAM, 100);
+        let relayed = CommitmentRelayed {
+            nonce: U256::from(7),
+            startHeight: synthetic.height,
+            endHeight: synthetic.height + 2,
+            commitment: B256::repeat_byte(0x77),
+        };
+        let relayed = Log {
+            inner: alloy_primitives::Log {
+                address: BLOBSTREAM,
+                data: relayed.encode_log_data(),
+            },
+            block_number: Some(10),
+            ..Default::default()
+        };
+
+        // The canonical event covers the height as well, but isn't the one the fork emits.
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(1_000));
+        asserter.push_success(&Bytes::from(
+            U256::from(synthetic.height + 2).to_be_bytes::<32>(),
+        ));
+        asserter.push_success(&vec![commitment_log(&synthetic), relayed]);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let topic_filter = CommitmentTopicFilter {
+            event: CommitmentEvent {
+                signature_hash: CommitmentRelayed::SIGNATURE_HASH,
+                decode: decode_commitment_relayed,
+            },
+            ..Default::default()
+        };
+
+        let event = find_data_commitment(
+            synthetic.height,
+            BLOBSTREAM,
+            &l1,
+            1_000,
+            None,
+            &topic_filter,
+        )
+        .await
+        .unwrap();
+        assert_eq!(event.proof_nonce, U256::from(7));
+        assert_eq!(event.data_commitment, B256::repeat_byte(0x77));
+    }
+
+    /// The `DataCommitmentStored` logs recorded for [replay], each relaying 100 Celestia blocks:
+    /// `(proof nonce, first height, L1 block)`. With the scan starting at [REPLAY_HEAD], the first
+    /// window holds the last two commitments and the second window the first two.
+    const RECORDED_COMMITMENTS: [(u64, u64, u64); 4] = [
+        (1, 1_000, 12_000),
+        (2, 1_100, 14_000),
+        (3, 1_200, 17_000),
+        (4, 1_300, 19_500),
+    ];
+
+    /// The L1 head the recorded logs are replayed at.
+    const REPLAY_HEAD: u64 = 20_000;
+
+    /// The latest Celestia block relayed at [REPLAY_HEAD].
+    const REPLAY_LATEST_BLOCK: u64 = 1_400;
+
+    /// A provider replaying [RECORDED_COMMITMENTS], answering each log query with the recorded
+    /// logs of its block range. Other requests are answered by `inner`.
+    struct ReplayProvider<P> {
+        inner: P,
+        logs: Vec<Log>,
+        /// The block ranges of the log queries, in order.
+        queries: std::sync::Mutex<Vec<(u64, u64)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl<P: Provider> Provider for ReplayProvider<P> {
+        fn root(&self) -> &RootProvider {
+            self.inner.root()
+        }
+
+        async fn get_logs(&self, filter: &Filter) -> alloy_transport::TransportResult<Vec<Log>> {
+            let from = filter.get_from_block().expect("scans query block ranges");
+            let to = filter.get_to_block().expect("scans query block ranges");
+            self.queries.lock().unwrap().push((from, to));
+            Ok(self
+                .logs
+                .iter()
+                .filter(|log| (from..=to).contains(&log.block_number.unwrap()))
+                .cloned()
+                .collect())
+        }
+    }
 
-    (start_index, end_index)
+    /// Returns a provider replaying the recorded logs, whose head is [REPLAY_HEAD].
+    fn replay_provider() -> ReplayProvider<impl Provider> {
+        replay_provider_with(&RECORDED_COMMITMENTS)
+    }
+
+    /// Returns a provider replaying the logs of `commitments`, laid out like
+    /// [RECORDED_COMMITMENTS], whose head is [REPLAY_HEAD].
+    fn replay_provider_with(commitments: &[(u64, u64, u64)]) -> ReplayProvider<impl Provider> {
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(REPLAY_HEAD));
+        asserter.push_success(&Bytes::from(
+            U256::from(REPLAY_LATEST_BLOCK).to_be_bytes::<32>(),
+        ));
+        let logs = commitments
+            .iter()
+            .map(|&(nonce, start, l1_block)| {
+                let event = SP1Blobstream::DataCommitmentStored {
+                    proofNonce: U256::from(nonce),
+                    startBlock: start,
+                    endBlock: start + 100,
+                    dataCommitment: B256::repeat_byte(nonce as u8),
+                };
+                Log {
+                    inner: alloy_primitives::Log {
+                        address: BLOBSTREAM,
+                        data: event.encode_log_data(),
+                    },
+                    block_number: Some(l1_block),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        ReplayProvider {
+            inner: ProviderBuilder::new().connect_mocked_client(asserter),
+            logs,
+            queries: Default::default(),
+        }
+    }
+
+    /// Runs [find_data_commitment] for `height` against the recorded logs, returning its result
+    /// and the block ranges it queried.
+    async fn replay(
+        height: u64,
+        hint_block: Option<u64>,
+    ) -> (
+        Result<SP1BlobstreamDataCommitmentStored, Box<dyn core::error::Error>>,
+        Vec<(u64, u64)>,
+    ) {
+        let provider = replay_provider();
+        let result = find_data_commitment(
+            height,
+            BLOBSTREAM,
+            &provider,
+            REPLAY_HEAD,
+            hint_block,
+            &CommitmentTopicFilter::default(),
+        )
+        .await;
+        let queries = provider.queries.into_inner().unwrap();
+        (result, queries)
+    }
+
+    #[tokio::test]
+    async fn test_replay_finds_commitments_in_the_first_window() {
+        // Inside a range, at its first height, and at the height before the next range.
+        for (height, nonce) in [(1_350, 4), (1_300, 4), (1_299, 3), (1_200, 3)] {
+            let (event, queries) = replay(height, None).await;
+            let event = event.unwrap();
+            assert_eq!(event.proof_nonce, U256::from(nonce), "height {height}");
+            assert!(event.covers(height));
+            assert_eq!(queries, [(15_000, 20_000)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_finds_commitments_in_the_second_window() {
+        for (height, nonce) in [(1_199, 2), (1_100, 2), (1_099, 1), (1_000, 1)] {
+            let (event, queries) = replay(height, None).await;
+            assert_eq!(
+                event.unwrap().proof_nonce,
+                U256::from(nonce),
+                "height {height}"
+            );
+            assert_eq!(queries, [(15_000, 20_000), (10_000, 15_000)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_scans_to_genesis_for_uncovered_heights() {
+        let (err, queries) = replay(999, None).await;
+        assert!(matches!(
+            err.unwrap_err().downcast_ref::<ProofError>(),
+            Some(ProofError::CommitmentNotFound(999))
+        ));
+        // The window doubles after the empty window.
+        assert_eq!(
+            queries,
+            [
+                (15_000, 20_000),
+                (10_000, 15_000),
+                (5_000, 10_000),
+                (0, 5_000)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejects_heights_not_yet_relayed() {
+        let (err, queries) = replay(REPLAY_LATEST_BLOCK, None).await;
+        assert!(matches!(
+            err.unwrap_err().downcast_ref::<ProofError>(),
+            Some(ProofError::CommitmentNotYetRelayed { .. })
+        ));
+        assert!(queries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_scans_around_the_hint_block_first() {
+        let (event, queries) = replay(1_150, Some(14_000)).await;
+        assert_eq!(event.unwrap().proof_nonce, U256::from(2));
+        assert_eq!(queries, [(11_500, 16_500)]);
+    }
+
+    #[test]
+    fn test_window_shrinks_over_dense_ranges_and_widens_over_sparse_ones() {
+        let mut window = AdaptiveWindow::new();
+        window.observe(DENSE_WINDOW_EVENTS + 1);
+        assert_eq!(window.size(), FILTER_BLOCK_RANGE / 2);
+        // Windows with a moderate number of events keep their size.
+        window.observe(DENSE_WINDOW_EVENTS);
+        window.observe(1);
+        assert_eq!(window.size(), FILTER_BLOCK_RANGE / 2);
+
+        for _ in 0..10 {
+            window.observe(DENSE_WINDOW_EVENTS + 1);
+        }
+        assert_eq!(window.size(), MIN_FILTER_BLOCK_RANGE);
+        assert!(!window.shrink());
+
+        for _ in 0..10 {
+            window.observe(0);
+        }
+        assert_eq!(window.size(), MAX_FILTER_BLOCK_RANGE);
+    }
+
+    /// Runs [find_data_commitment] for `height` against the logs of `commitments`, returning the
+    /// proof nonce of the event found and the block ranges queried.
+    async fn scan(commitments: &[(u64, u64, u64)], height: u64) -> (U256, Vec<(u64, u64)>) {
+        let provider = replay_provider_with(commitments);
+        let event = find_data_commitment(
+            height,
+            BLOBSTREAM,
+            &provider,
+            REPLAY_HEAD,
+            None,
+            &CommitmentTopicFilter::default(),
+        )
+        .await
+        .unwrap();
+        (event.proof_nonce, provider.queries.into_inner().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_scan_shrinks_its_window_over_a_dense_contract() {
+        // Many commitments relayed just below the head, above the one covering the height.
+        let mut commitments = vec![(1, 1_000, 12_000)];
+        commitments.extend((0..250).map(|i| (2 + i, 1_100 + 100 * i, 16_000 + i)));
+
+        let (nonce, queries) = scan(&commitments, 1_000).await;
+        assert_eq!(nonce, U256::from(1));
+        // The dense window halves the next one, which widens again once it comes back empty.
+        assert_eq!(
+            queries,
+            [(15_000, 20_000), (12_500, 15_000), (7_500, 12_500)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_widens_its_window_over_a_sparse_contract() {
+        let (nonce, queries) = scan(&[(1, 1_000, 1_000)], 1_000).await;
+        assert_eq!(nonce, U256::from(1));
+        // Fixed windows would have taken four queries.
+        assert_eq!(queries, [(15_000, 20_000), (5_000, 15_000), (0, 5_000)]);
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_events_are_ambiguous() {
+        // Two commitments relaying 1_000..1_100 and 1_050..1_150, and the second one again.
+        let commitments = [(1, 1_000, 19_000), (2, 1_050, 19_500), (2, 1_050, 19_600)];
+
+        for height in [1_050, 1_099] {
+            let err = find_data_commitment(
+                height,
+                BLOBSTREAM,
+                &replay_provider_with(&commitments),
+                REPLAY_HEAD,
+                None,
+                &CommitmentTopicFilter::default(),
+            )
+            .await
+            .unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<ProofError>(),
+                    Some(ProofError::AmbiguousCommitment { height: h, first, second })
+                        if *h == height && *first == U256::from(1) && *second == U256::from(2)
+                ),
+                "{err}"
+            );
+        }
+
+        // Heights covered by a single commitment, even if its event is repeated, are found.
+        assert_eq!(scan(&commitments, 1_020).await.0, U256::from(1));
+        assert_eq!(scan(&commitments, 1_120).await.0, U256::from(2));
+    }
+
+    #[tokio::test]
+    async fn test_is_height_committed() {
+        for (height, committed) in [
+            (1_350, true),
+            (1_000, true),
+            (REPLAY_LATEST_BLOCK, false),
+            // Below the first relayed range.
+            (999, false),
+        ] {
+            let result =
+                is_height_committed(height, BLOBSTREAM, &replay_provider(), REPLAY_HEAD).await;
+            assert_eq!(result.unwrap(), committed, "height {height}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_is_height_committed_reports_node_errors() {
+        // The node is behind the l1 head, so the answer is unknown rather than `false`.
+        let err = is_height_committed(1_350, BLOBSTREAM, &replay_provider(), REPLAY_HEAD + 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProofError>(),
+            Some(ProofError::L1NodeBehind { .. })
+        ));
+    }
+
+    #[test]
+    fn test_commitment_event_selector_matches_the_signature() {
+        let selector =
+            alloy_primitives::keccak256("DataCommitmentStored(uint256,uint64,uint64,bytes32)");
+        assert_eq!(
+            selector,
+            SP1Blobstream::DataCommitmentStored::SIGNATURE_HASH
+        );
+        assert_eq!(CommitmentEvent::default().signature_hash, selector);
+    }
 }
@@ -0,0 +1,75 @@
+//! The requests made to a Celestia node when generating a
+//! [BlobstreamProof](hana_blobstream::blobstream::BlobstreamProof).
+//!
+//! [get_blobstream_proof](crate::blobstream_inclusion::get_blobstream_proof) is generic over
+//! [CelestiaNode] rather than tied to a [celestia_rpc::Client], so proofs can be generated against
+//! canned responses in tests.
+
+use celestia_rpc::{blobstream::BlobstreamClient, Client, HeaderClient, ShareClient};
+use celestia_types::{DataAvailabilityHeader, ExtendedHeader, MerkleProof, ShareProof};
+use core::future::Future;
+use jsonrpsee::core::ClientError;
+
+/// A Celestia node serving the headers, share proofs and data root tuple proofs of its blocks.
+pub trait CelestiaNode: Sync {
+    /// The header of a Celestia block.
+    type Header: Send + Sync;
+
+    /// Fetches the header of the block at `height`.
+    fn header(&self, height: u64)
+        -> impl Future<Output = Result<Self::Header, ClientError>> + Send;
+
+    /// Returns the data availability header of `header`, committing to the block's data square.
+    fn dah(header: &Self::Header) -> &DataAvailabilityHeader;
+
+    /// Fetches the proof of the shares between `start` (inclusive) and `end` (exclusive) of the
+    /// original data square of the block of `header`.
+    fn share_proof(
+        &self,
+        header: &Self::Header,
+        start: u64,
+        end: u64,
+    ) -> impl Future<Output = Result<ShareProof, ClientError>> + Send;
+
+    /// Fetches the proof that the data root tuple of `height` is included in the data commitment
+    /// over the blocks between `start` (inclusive) and `end` (exclusive).
+    fn data_root_tuple_proof(
+        &self,
+        height: u64,
+        start: u64,
+        end: u64,
+    ) -> impl Future<Output = Result<MerkleProof, ClientError>> + Send;
+}
+
+impl CelestiaNode for Client {
+    type Header = ExtendedHeader;
+
+    fn header(
+        &self,
+        height: u64,
+    ) -> impl Future<Output = Result<Self::Header, ClientError>> + Send {
+        self.header_get_by_height(height)
+    }
+
+    fn dah(header: &Self::Header) -> &DataAvailabilityHeader {
+        &header.dah
+    }
+
+    fn share_proof(
+        &self,
+        header: &Self::Header,
+        start: u64,
+        end: u64,
+    ) -> impl Future<Output = Result<ShareProof, ClientError>> + Send {
+        async move { Ok(self.share_get_range(header, start, end).await?.proof) }
+    }
+
+    fn data_root_tuple_proof(
+        &self,
+        height: u64,
+        start: u64,
+        end: u64,
+    ) -> impl Future<Output = Result<MerkleProof, ClientError>> + Send {
+        self.blobstream_get_data_root_tuple_inclusion_proof(height, start, end)
+    }
+}
@@ -0,0 +1,451 @@
+//! A persistable index of the data commitments relayed to Blobstream.
+//!
+//! The index records every `DataCommitmentStored` event found while scanning L1, together with the
+//! range of L1 blocks that has been fully scanned. This lets [CommitmentIndex::find] answer
+//! lookups from memory and only scan the L1 blocks that are not yet covered.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use alloy_primitives::{Address, B256, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::BlockNumberOrTag;
+use hana_blobstream::blobstream::{SP1Blobstream, SP1BlobstreamDataCommitmentStored};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+use crate::blobstream_inclusion::{
+    find_data_commitment, get_data_commitment_events, CommitmentTopicFilter, FILTER_BLOCK_RANGE,
+};
+use crate::errors::ProofError;
+
+/// A data commitment event along with the L1 block it was emitted in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedCommitment {
+    /// The L1 block the event was emitted in.
+    pub l1_block: u64,
+    /// The proof nonce of the commitment.
+    pub proof_nonce: U256,
+    /// The first Celestia height covered by the commitment (inclusive).
+    pub start_block: u64,
+    /// The last Celestia height covered by the commitment (exclusive).
+    pub end_block: u64,
+    /// The data commitment.
+    pub data_commitment: B256,
+}
+
+impl From<&IndexedCommitment> for SP1BlobstreamDataCommitmentStored {
+    fn from(entry: &IndexedCommitment) -> Self {
+        Self {
+            proof_nonce: entry.proof_nonce,
+            start_block: entry.start_block,
+            end_block: entry.end_block,
+            data_commitment: entry.data_commitment,
+        }
+    }
+}
+
+/// An index of the data commitments relayed to a Blobstream contract.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitmentIndex {
+    /// The indexed commitments keyed by proof nonce.
+    commitments: BTreeMap<U256, IndexedCommitment>,
+    /// The inclusive range of L1 blocks whose events are all present in the index.
+    scanned: Option<(u64, u64)>,
+    /// The L1 block up to which the indexed commitments were checked against the contract once
+    /// their blocks were finalized, see [CommitmentIndex::validate].
+    #[serde(default)]
+    finalized: u64,
+}
+
+impl CommitmentIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of indexed commitments.
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Returns `true` if the index holds no commitments.
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+
+    /// Loads an index previously written with [CommitmentIndex::save].
+    pub fn load(path: &Path) -> Result<Self, Box<dyn core::error::Error>> {
+        let raw = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&raw)?)
+    }
+
+    /// Writes the index to disk as JSON.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn core::error::Error>> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Checks the index against the current state of the Blobstream contract.
+    ///
+    /// Blobstream relays commitments with sequential nonces, so the indexed nonces must be
+    /// contiguous and below the contract's `state_proofNonce`. The commitments emitted after the
+    /// finalized L1 block of the last validation may have been reorged out since they were
+    /// indexed, each of them must match the commitment the contract stores for its nonce. Those
+    /// now finalized are not checked again.
+    pub async fn validate<P: Provider + Clone>(
+        &mut self,
+        blobstream_address: Address,
+        eth_provider: &P,
+    ) -> Result<(), Box<dyn core::error::Error>> {
+        let (Some(first), Some(last)) = (
+            self.commitments.keys().next().copied(),
+            self.commitments.keys().next_back().copied(),
+        ) else {
+            return Ok(());
+        };
+        if last - first + U256::from(1) != U256::from(self.commitments.len()) {
+            return Err(format!("indexed proof nonces {first}..={last} are not contiguous").into());
+        }
+
+        let finalized = eth_provider
+            .get_block_by_number(BlockNumberOrTag::Finalized)
+            .await?
+            .map(|block| block.header.number);
+
+        let contract = SP1Blobstream::new(blobstream_address, eth_provider.clone());
+        let next_nonce = contract.state_proofNonce().call().await?;
+        if last >= next_nonce {
+            return Err(format!(
+                "indexed proof nonce {last} is not below the contract's state_proofNonce {next_nonce}"
+            )
+            .into());
+        }
+
+        for entry in self
+            .commitments
+            .values()
+            .filter(|entry| entry.l1_block > self.finalized)
+        {
+            let stored = contract
+                .state_dataCommitments(entry.proof_nonce)
+                .call()
+                .await?;
+            if stored != entry.data_commitment {
+                return Err(format!(
+                    "indexed data commitment for nonce {} does not match the contract's",
+                    entry.proof_nonce
+                )
+                .into());
+            }
+        }
+
+        if let Some(finalized) = finalized {
+            self.finalized = self.finalized.max(finalized);
+        }
+        Ok(())
+    }
+
+    /// Returns the indexed commitment covering `celestia_height` that was relayed at or before
+    /// the given L1 block.
+    pub fn lookup(
+        &self,
+        celestia_height: u64,
+        l1_head_block_number: u64,
+    ) -> Option<SP1BlobstreamDataCommitmentStored> {
+        self.commitments
+            .values()
+            .find(|entry| {
+                entry.l1_block <= l1_head_block_number
+                    && entry.start_block <= celestia_height
+                    && celestia_height < entry.end_block
+            })
+            .map(Into::into)
+    }
+
+    /// Finds the data commitment containing the given Celestia height, using the index where
+    /// possible and extending it with any L1 blocks that had to be scanned.
+    ///
+    /// Returns the same result as [find_data_commitment].
+    pub async fn find<P: Provider>(
+        &mut self,
+        celestia_height: u64,
+        blobstream_address: Address,
+        eth_provider: &P,
+        l1_head_block_number: u64,
+    ) -> Result<SP1BlobstreamDataCommitmentStored, Box<dyn core::error::Error>> {
+        let (mut from, to) = match self.scanned {
+            Some((from, _)) if l1_head_block_number < from => {
+                // The index can't tell what was relayed at this L1 head, fall back to a full scan.
+                return find_data_commitment(
+                    celestia_height,
+                    blobstream_address,
+                    eth_provider,
+                    l1_head_block_number,
+                    None,
+                    &CommitmentTopicFilter::default(),
+                )
+                .await;
+            }
+            Some((from, to)) if l1_head_block_number > to => {
+                // Extend the index up to the requested L1 head.
+                self.scan(
+                    blobstream_address,
+                    eth_provider,
+                    to + 1,
+                    l1_head_block_number,
+                )
+                .await?;
+                (from, l1_head_block_number)
+            }
+            Some(scanned) => scanned,
+            None => (l1_head_block_number + 1, l1_head_block_number),
+        };
+        self.scanned = Some((from, to));
+
+        if let Some(event) = self.lookup(celestia_height, l1_head_block_number) {
+            info!(
+                "Found Data Root submission in index proof_nonce={} start={} end={}",
+                event.proof_nonce, event.start_block, event.end_block
+            );
+            return Ok(event);
+        }
+
+        // Scan backwards from the start of the indexed range until the height is found.
+        while from > 0 {
+            let end = from - 1;
+            let start = end.saturating_sub(FILTER_BLOCK_RANGE);
+            self.scan(blobstream_address, eth_provider, start, end)
+                .await?;
+            from = start;
+            self.scanned = Some((from, to));
+
+            if let Some(event) = self.lookup(celestia_height, l1_head_block_number) {
+                return Ok(event);
+            }
+        }
+
+        Err(ProofError::CommitmentNotFound(celestia_height).into())
+    }
+
+    /// Merges `other`, a copy of this index extended by [CommitmentIndex::find] while this one
+    /// may have been extended by other lookups.
+    ///
+    /// Every indexed event was emitted on L1, so the commitments of both are kept. Both scanned
+    /// ranges contain the range of the copy, so they overlap and their union is scanned, unless
+    /// the copy was taken of an empty index: then the wider of the two is kept.
+    pub fn merge(&mut self, other: CommitmentIndex) {
+        self.commitments.extend(other.commitments);
+        self.scanned = match (self.scanned, other.scanned) {
+            (Some((from, to)), Some((other_from, other_to)))
+                if from <= other_to.saturating_add(1) && other_from <= to.saturating_add(1) =>
+            {
+                Some((from.min(other_from), to.max(other_to)))
+            }
+            (Some((from, to)), Some((other_from, other_to))) => {
+                if other_to - other_from > to - from {
+                    Some((other_from, other_to))
+                } else {
+                    Some((from, to))
+                }
+            }
+            (scanned, other_scanned) => scanned.or(other_scanned),
+        };
+    }
+
+    /// Adds every event emitted between the `start` and `end` L1 blocks to the index.
+    async fn scan<P: Provider>(
+        &mut self,
+        blobstream_address: Address,
+        eth_provider: &P,
+        start: u64,
+        end: u64,
+    ) -> Result<(), Box<dyn core::error::Error>> {
+        let mut window_start = start;
+        while window_start <= end {
+            let window_end = end.min(window_start.saturating_add(FILTER_BLOCK_RANGE));
+            let events: Vec<_> = get_data_commitment_events(
+                blobstream_address,
+                eth_provider,
+                window_start,
+                window_end,
+                &CommitmentTopicFilter::default(),
+            )
+            .await?;
+
+            for (l1_block, event) in events {
+                self.commitments.insert(
+                    event.proof_nonce,
+                    IndexedCommitment {
+                        l1_block,
+                        proof_nonce: event.proof_nonce,
+                        start_block: event.start_block,
+                        end_block: event.end_block,
+                        data_commitment: event.data_commitment,
+                    },
+                );
+            }
+
+            window_start = window_end + 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+    use alloy_provider::ProviderBuilder;
+    use alloy_rpc_types_eth::{Block, BlockTransactions, Header, Log};
+    use alloy_sol_types::SolEvent;
+    use alloy_transport::mock::Asserter;
+
+    const BLOBSTREAM: Address = Address::repeat_byte(0xbb);
+
+    /// The event relaying the commitment `nonce` over the heights `[10 * nonce, 10 * nonce + 10)`.
+    fn commitment_log(nonce: u64, l1_block: u64) -> Log {
+        let event = SP1Blobstream::DataCommitmentStored {
+            proofNonce: U256::from(nonce),
+            startBlock: 10 * nonce,
+            endBlock: 10 * nonce + 10,
+            dataCommitment: B256::repeat_byte(nonce as u8),
+        };
+        Log {
+            inner: alloy_primitives::Log {
+                address: BLOBSTREAM,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(l1_block),
+            ..Default::default()
+        }
+    }
+
+    /// Builds an index of `logs` by looking up the first height of the first log at L1 block 1000.
+    async fn indexed(logs: Vec<Log>) -> CommitmentIndex {
+        let asserter = Asserter::new();
+        asserter.push_success(&logs);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        let mut index = CommitmentIndex::new();
+        index.find(10, BLOBSTREAM, &l1, 1000).await.unwrap();
+        index
+    }
+
+    fn finalized_block(number: u64) -> Block {
+        Block {
+            header: Header {
+                hash: B256::ZERO,
+                inner: alloy_consensus::Header {
+                    number,
+                    ..Default::default()
+                },
+                total_difficulty: None,
+                size: None,
+            },
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            withdrawals: None,
+        }
+    }
+
+    /// The ABI encoding of a `uint256` or `bytes32` returned by an `eth_call`.
+    fn word(value: B256) -> Bytes {
+        Bytes::copy_from_slice(value.as_slice())
+    }
+
+    #[tokio::test]
+    async fn test_loaded_index_resolves_lookups_without_log_queries() {
+        let index = indexed(vec![commitment_log(1, 900), commitment_log(2, 950)]).await;
+        let path =
+            std::env::temp_dir().join(format!("hana-commitment-index-{}.json", std::process::id()));
+        index.save(&path).unwrap();
+        let mut loaded = CommitmentIndex::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Nothing is queued, any L1 request fails the lookup.
+        let l1 = ProviderBuilder::new().connect_mocked_client(Asserter::new());
+        let event = loaded.find(25, BLOBSTREAM, &l1, 1000).await.unwrap();
+        assert_eq!(event.proof_nonce, U256::from(2));
+        assert_eq!(loaded.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_merge_keeps_the_extensions_of_both_copies() {
+        let mut index = indexed(vec![commitment_log(1, 900)]).await;
+        let mut snapshot = index.clone();
+
+        // Another lookup extends the index up to L1 block 1200 while the snapshot is scanned.
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![commitment_log(2, 1100)]);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        index.find(25, BLOBSTREAM, &l1, 1200).await.unwrap();
+
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![commitment_log(2, 1100), commitment_log(3, 1250)]);
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+        snapshot.find(35, BLOBSTREAM, &l1, 1300).await.unwrap();
+
+        index.merge(snapshot);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.scanned, Some((0, 1300)));
+        assert_eq!(index.lookup(35, 1300).unwrap().proof_nonce, U256::from(3));
+    }
+
+    #[test]
+    fn test_merge_of_disjoint_ranges_keeps_the_wider_one() {
+        let mut index = CommitmentIndex {
+            scanned: Some((900, 1000)),
+            ..Default::default()
+        };
+        index.merge(CommitmentIndex {
+            scanned: Some((0, 500)),
+            ..Default::default()
+        });
+        assert_eq!(index.scanned, Some((0, 500)));
+
+        index.merge(CommitmentIndex::new());
+        assert_eq!(index.scanned, Some((0, 500)));
+    }
+
+    #[tokio::test]
+    async fn test_validate_checks_unfinalized_commitments_once() {
+        let mut index = indexed(vec![commitment_log(1, 900), commitment_log(2, 950)]).await;
+
+        let asserter = Asserter::new();
+        asserter.push_success(&finalized_block(960));
+        asserter.push_success(&word(U256::from(3).into()));
+        asserter.push_success(&word(B256::repeat_byte(1)));
+        asserter.push_success(&word(B256::repeat_byte(2)));
+        // Both commitments are finalized now, only the nonce is checked again.
+        asserter.push_success(&finalized_block(970));
+        asserter.push_success(&word(U256::from(3).into()));
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        index.validate(BLOBSTREAM, &l1).await.unwrap();
+        index.validate(BLOBSTREAM, &l1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validate_detects_reorged_commitment() {
+        let mut index = indexed(vec![commitment_log(1, 900), commitment_log(2, 950)]).await;
+
+        let asserter = Asserter::new();
+        asserter.push_success(&finalized_block(920));
+        asserter.push_success(&word(U256::from(3).into()));
+        // The commitment of nonce 2 was relayed again with another data commitment.
+        asserter.push_success(&word(B256::repeat_byte(0xee)));
+        let l1 = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let err = index.validate(BLOBSTREAM, &l1).await.unwrap_err();
+        assert!(err.to_string().contains("nonce 2"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_nonce_gaps() {
+        let mut index = indexed(vec![commitment_log(1, 900), commitment_log(3, 950)]).await;
+
+        let l1 = ProviderBuilder::new().connect_mocked_client(Asserter::new());
+        let err = index.validate(BLOBSTREAM, &l1).await.unwrap_err();
+        assert!(err.to_string().contains("not contiguous"));
+    }
+}
@@ -0,0 +1,259 @@
+//! Errors returned while generating Blobstream proofs.
+
+use alloy_primitives::{B256, U256};
+use jsonrpsee::core::ClientError;
+
+/// An error generating a [BlobstreamProof](hana_blobstream::blobstream::BlobstreamProof).
+#[derive(Debug, thiserror::Error)]
+pub enum ProofError {
+    /// The blob has no data, so there is no share range to prove.
+    #[error("blob is empty, cannot generate a share proof")]
+    EmptyBlob,
+    /// The L1 node doesn't have the block, e.g. because it lags behind or pruned it.
+    #[error("L1 block {0} not found, the L1 node may be behind or have pruned it")]
+    L1BlockNotFound(B256),
+    /// The Celestia height hasn't been relayed to Blobstream yet, retrying later may succeed.
+    #[error(
+        "celestia height {height} is not relayed yet, the latest relayed block is {latest_block}"
+    )]
+    CommitmentNotYetRelayed {
+        /// The requested Celestia height.
+        height: u64,
+        /// The latest Celestia block relayed to Blobstream.
+        latest_block: u64,
+    },
+    /// No data commitment covering the Celestia height was ever relayed to Blobstream.
+    #[error("no data commitment found for celestia height {0}")]
+    CommitmentNotFound(u64),
+    /// The L1 node no longer has the state needed to prove the Blobstream storage.
+    #[error(
+        "L1 node is missing the state of block {0} to prove Blobstream storage, configure an archive node"
+    )]
+    MissingState(B256),
+    /// The Celestia node doesn't have the history of the requested height.
+    #[error(
+        "celestia node lacks the history of height {height}, use an archival celestia node: {message}"
+    )]
+    CelestiaHistoryUnavailable {
+        /// The requested Celestia height.
+        height: u64,
+        /// The error returned by the node.
+        message: String,
+    },
+    /// The deployed Blobstream code doesn't match the expected code hash.
+    #[error("blobstream code hash {actual} does not match the expected {expected}")]
+    CodeHashMismatch {
+        /// The code hash the operator expects.
+        expected: B256,
+        /// The hash of the code deployed at the Blobstream address.
+        actual: B256,
+    },
+    /// The commitment stored in Blobstream under the event's nonce differs from the event's.
+    #[error(
+        "data commitment stored for proof nonce {proof_nonce} is {stored}, but the event has {event}"
+    )]
+    CommitmentMismatch {
+        /// The proof nonce of the event.
+        proof_nonce: U256,
+        /// The data commitment carried by the event.
+        event: B256,
+        /// The data commitment stored in the contract.
+        stored: B256,
+    },
+    /// The L1 node's head is below the L1 block to scan from, retrying later may succeed.
+    #[error(
+        "L1 node is behind the requested head {requested} by {} blocks",
+        requested.saturating_sub(*node_head)
+    )]
+    L1NodeBehind {
+        /// The L1 block number requested.
+        requested: u64,
+        /// The latest block number of the L1 node.
+        node_head: u64,
+    },
+    /// The block the storage proof is anchored at is too recent, retrying later may succeed.
+    #[error(
+        "L1 block {block} has {confirmations} confirmations, {required} are required to prove against it"
+    )]
+    InsufficientConfirmations {
+        /// The number of the block the proof is anchored at.
+        block: u64,
+        /// The number of blocks the L1 node's head is ahead of it.
+        confirmations: u64,
+        /// The required number of confirmations.
+        required: u64,
+    },
+    /// The blob's share indices don't fit in the data square of its block.
+    #[error("invalid blob share indices: {0}")]
+    InvalidShareIndices(String),
+    /// The share proof returned by the Celestia node doesn't prove exactly the blob's shares.
+    #[error(
+        "share proof covers {} instead of the blob shares {expected_start}..{expected_end}",
+        .proven.map_or("no contiguous share range".to_string(), |(start, end)| format!("shares {start}..{end}"))
+    )]
+    ShareRangeMismatch {
+        /// The index of the blob's first share in the original data square.
+        expected_start: u64,
+        /// The index after the blob's last share in the original data square.
+        expected_end: u64,
+        /// The range of shares the proof covers, `None` if it proves no shares or leaves
+        /// gaps between them.
+        proven: Option<(u64, u64)>,
+    },
+    /// Several distinct data commitment events cover the Celestia height, which Blobstream never
+    /// emits, so none of them can be trusted.
+    #[error(
+        "celestia height {height} is covered by the data commitments of proof nonces {first} and {second}"
+    )]
+    AmbiguousCommitment {
+        /// The requested Celestia height.
+        height: u64,
+        /// The proof nonce of the first matching event.
+        first: U256,
+        /// The proof nonce of another matching event.
+        second: U256,
+    },
+    /// The L1 chain has no canonical Blobstream deployment and none was configured for it.
+    #[error("no Blobstream deployment known for L1 chain {0}, configure its address")]
+    UnknownBlobstreamDeployment(u64),
+    /// The L1 node returned a header for the l1 head that doesn't hash to it.
+    #[error("L1 header of {expected} hashes to {computed}, diverging fields: {fields}")]
+    L1HeaderHashMismatch {
+        /// The l1 head.
+        expected: B256,
+        /// The hash of the header returned by the L1 node.
+        computed: B256,
+        /// The header fields identified as diverging, or `unknown`.
+        fields: String,
+    },
+}
+
+impl ProofError {
+    /// Returns `true` if the error may go away when retrying later, e.g. once the L1 node or
+    /// Blobstream caught up.
+    pub const fn is_temporary(&self) -> bool {
+        matches!(
+            self,
+            Self::CommitmentNotYetRelayed { .. }
+                | Self::L1BlockNotFound(_)
+                | Self::L1NodeBehind { .. }
+                | Self::InsufficientConfirmations { .. }
+        )
+    }
+}
+
+/// The JSON-RPC error code of the errors returned by the API of celestia-node. Its go-jsonrpc
+/// server returns every error without a registered code with this code and the error's text,
+/// which then tells the errors apart.
+pub const CELESTIA_API_ERROR_CODE: i32 = 1;
+
+/// The texts of the celestia-node errors for a height the node doesn't have, e.g. because it
+/// pruned it or never synced it.
+pub const MISSING_HISTORY_ERRORS: [&str; 2] = ["header: not found", "sampling window"];
+
+/// The text of the celestia-node error for a blob the node doesn't have, e.g. because it wasn't
+/// posted or the node hasn't synced its block yet.
+pub const BLOB_NOT_FOUND_ERROR: &str = "blob: not found";
+
+/// Returns `true` if `err` is an error of the celestia-node API whose text contains one of
+/// `errors`. Transport failures and other JSON-RPC errors never match.
+pub fn is_celestia_api_error(err: &ClientError, errors: &[&str]) -> bool {
+    match err {
+        ClientError::Call(call) => {
+            call.code() == CELESTIA_API_ERROR_CODE
+                && errors.iter().any(|error| call.message().contains(error))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::ErrorObject;
+
+    fn call_error(code: i32, message: &str) -> ClientError {
+        ClientError::Call(ErrorObject::owned(code, message, None::<()>))
+    }
+
+    #[test]
+    fn test_celestia_api_errors_are_matched_by_code_and_text() {
+        let err = call_error(CELESTIA_API_ERROR_CODE, "header: not found");
+        assert!(is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS));
+
+        let err = call_error(
+            CELESTIA_API_ERROR_CODE,
+            "getting header: height 5 is outside the sampling window",
+        );
+        assert!(is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS));
+    }
+
+    #[test]
+    fn test_other_errors_are_not_celestia_api_errors() {
+        let err = call_error(CELESTIA_API_ERROR_CODE, "share: invalid range");
+        assert!(!is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS));
+
+        // The same text with another code, e.g. from a proxy, isn't the node's error.
+        let err = call_error(-32000, "header: not found");
+        assert!(!is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS));
+
+        let err = ClientError::Custom("header: not found".to_string());
+        assert!(!is_celestia_api_error(&err, &MISSING_HISTORY_ERRORS));
+    }
+
+    #[test]
+    fn test_only_errors_retrying_may_fix_are_temporary() {
+        for err in [
+            ProofError::CommitmentNotYetRelayed {
+                height: 100,
+                latest_block: 90,
+            },
+            ProofError::L1BlockNotFound(B256::ZERO),
+            ProofError::L1NodeBehind {
+                requested: 110,
+                node_head: 100,
+            },
+            ProofError::InsufficientConfirmations {
+                block: 100,
+                confirmations: 1,
+                required: 64,
+            },
+        ] {
+            assert!(err.is_temporary(), "{err}");
+        }
+
+        for err in [
+            ProofError::EmptyBlob,
+            ProofError::CommitmentNotFound(100),
+            ProofError::MissingState(B256::ZERO),
+            ProofError::InvalidShareIndices("outside the square".to_string()),
+            ProofError::UnknownBlobstreamDeployment(7),
+        ] {
+            assert!(!err.is_temporary(), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_l1_node_behind_message() {
+        let err = ProofError::L1NodeBehind {
+            requested: 110,
+            node_head: 100,
+        };
+        assert_eq!(
+            err.to_string(),
+            "L1 node is behind the requested head 110 by 10 blocks"
+        );
+    }
+
+    #[test]
+    fn test_l1_node_behind_message_does_not_underflow() {
+        let err = ProofError::L1NodeBehind {
+            requested: 100,
+            node_head: 110,
+        };
+        assert_eq!(
+            err.to_string(),
+            "L1 node is behind the requested head 100 by 0 blocks"
+        );
+    }
+}
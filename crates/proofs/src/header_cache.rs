@@ -0,0 +1,61 @@
+//! A small cache of the L1 headers fetched while generating proofs, so that the proofs of several
+//! blobs anchored at the same L1 head don't fetch and check its headers again.
+
+use alloc::{collections::VecDeque, sync::Arc};
+use alloy_primitives::B256;
+use alloy_rpc_types_eth::Header;
+use std::sync::Mutex;
+
+/// The number of headers a [HeaderCache] keeps by default.
+pub const DEFAULT_HEADER_CACHE_SIZE: usize = 32;
+
+/// A bounded cache of L1 headers by block hash, evicting the least recently used header when
+/// full. Clones share the same headers.
+///
+/// Only headers checked to hash to their block hash are inserted.
+#[derive(Debug, Clone)]
+pub struct HeaderCache {
+    capacity: usize,
+    headers: Arc<Mutex<VecDeque<Header>>>,
+}
+
+impl HeaderCache {
+    /// Creates a cache keeping at most `capacity` headers. A capacity of 0 disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            headers: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Returns the header of the block `hash`, if cached.
+    pub fn get(&self, hash: B256) -> Option<Header> {
+        let mut headers = self.headers.lock().unwrap_or_else(|e| e.into_inner());
+        let position = headers.iter().position(|header| header.hash == hash)?;
+        // Move the header to the back, the most recently used end.
+        let header = headers.remove(position)?;
+        headers.push_back(header.clone());
+        Some(header)
+    }
+
+    /// Caches `header`, evicting the least recently used header if the cache is full.
+    pub fn insert(&self, header: Header) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut headers = self.headers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(position) = headers.iter().position(|cached| cached.hash == header.hash) {
+            headers.remove(position);
+        } else if headers.len() >= self.capacity {
+            headers.pop_front();
+        }
+        headers.push_back(header);
+    }
+}
+
+impl Default for HeaderCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEADER_CACHE_SIZE)
+    }
+}
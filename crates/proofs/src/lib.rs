@@ -5,3 +5,13 @@
 extern crate alloc;
 
 pub mod blobstream_inclusion;
+
+pub mod celestia_node;
+
+pub mod errors;
+
+pub mod commitment_index;
+
+pub mod rpc_counter;
+
+pub mod header_cache;
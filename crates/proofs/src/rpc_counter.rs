@@ -0,0 +1,54 @@
+//! Counting of the RPC calls made while generating proofs, to quantify what a run costs on a
+//! billed RPC provider.
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
+use core::fmt;
+use std::sync::Mutex;
+
+/// Counts RPC calls by method. Clones share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct RpcCallCounter {
+    counts: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+impl RpcCallCounter {
+    /// Creates a counter with no calls recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a call to `method`.
+    pub fn record(&self, method: &str) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        match counts.get_mut(method) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(method.into(), 1);
+            }
+        }
+    }
+
+    /// Returns the number of calls recorded for each method.
+    pub fn counts(&self) -> BTreeMap<String, u64> {
+        self.counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Returns the number of calls recorded across all methods.
+    pub fn total(&self) -> u64 {
+        self.counts().values().sum()
+    }
+}
+
+impl fmt::Display for RpcCallCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self.counts();
+        write!(f, "{} rpc calls", counts.values().sum::<u64>())?;
+        for (method, count) in counts {
+            write!(f, ", {method}: {count}")?;
+        }
+        Ok(())
+    }
+}
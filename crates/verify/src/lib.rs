@@ -0,0 +1,267 @@
+#![doc = include_str!("../README.md")]
+#![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
+#![cfg_attr(not(test), warn(unused_crate_dependencies))]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloy_primitives::{Address, Bytes, B256};
+use celestia_types::{nmt::Namespace, Commitment};
+use hana_blobstream::{blobstream::blobstream_address, payload::OraclePayload};
+
+pub use hana_blobstream::errors::CelestiaVerificationError;
+
+/// An error verifying a Celestia oracle payload.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    /// The payload isn't a valid encoding of an oracle payload, or its proofs exceed the default
+    /// proof limits.
+    #[error("failed to decode celestia oracle payload: {0}")]
+    Decode(String),
+    /// The payload failed verification, the same errors the oracle provider of the client returns.
+    #[error("celestia blob verification failed: {0}")]
+    Verification(#[from] CelestiaVerificationError),
+}
+
+/// Decodes the oracle payload in `payload_bytes` and verifies that its blob is the one with
+/// `commitment` in `namespace` included at the Celestia `height`, against the canonical
+/// Blobstream deployment of `chain_id` at the L1 block `l1_head`. Returns the verified blob.
+///
+/// See [verify_celestia_payload_at] for chains without a canonical deployment, or custom ones.
+///
+/// Security Note: `commitment`, `namespace`, `l1_head` and `chain_id` must come from a secure
+/// source, e.g. the public inputs of the guest.
+pub fn verify_celestia_payload(
+    payload_bytes: &[u8],
+    height: u64,
+    commitment: Commitment,
+    namespace: Namespace,
+    l1_head: B256,
+    chain_id: u64,
+) -> Result<Bytes, VerifyError> {
+    let expected_blobstream_address =
+        blobstream_address(chain_id).ok_or(CelestiaVerificationError::UnknownChainId(chain_id))?;
+
+    verify_celestia_payload_at(
+        payload_bytes,
+        height,
+        commitment,
+        namespace,
+        l1_head,
+        expected_blobstream_address,
+    )
+}
+
+/// Same as [verify_celestia_payload], against the Blobstream deployment at
+/// `expected_blobstream_address` rather than the canonical one of a chain, e.g. one resolved from
+/// a table of custom deployments with
+/// [resolve_blobstream_address](hana_blobstream::blobstream::resolve_blobstream_address).
+///
+/// The payload is decoded with [OraclePayload::from_bytes] and verified like the oracle provider
+/// of the client, see
+/// [BlobstreamProof::validate](hana_blobstream::blobstream::BlobstreamProof::validate) and
+/// [OraclePayload::verify_blob].
+///
+/// Security Note: `commitment`, `namespace`, `l1_head` and `expected_blobstream_address` must
+/// come from a secure source, e.g. the public inputs of the guest.
+pub fn verify_celestia_payload_at(
+    payload_bytes: &[u8],
+    height: u64,
+    commitment: Commitment,
+    namespace: Namespace,
+    l1_head: B256,
+    expected_blobstream_address: Address,
+) -> Result<Bytes, VerifyError> {
+    let payload =
+        OraclePayload::from_bytes(payload_bytes).map_err(|e| VerifyError::Decode(e.to_string()))?;
+
+    payload
+        .blobstream_proof
+        .validate(height, l1_head, expected_blobstream_address)
+        .map_err(CelestiaVerificationError::from_proof_error)?;
+
+    // Bind the verified blob to the requested one.
+    payload.verify_blob(commitment, Some(namespace))?;
+
+    Ok(payload.blob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hana_blobstream::blobstream::{
+        resolve_blobstream_address, BlobstreamAddresses, VerificationStage,
+    };
+    use hana_blobstream::test_utils::{synthetic_namespace, synthetic_proof, SyntheticProof};
+
+    /// Ethereum mainnet, which has a canonical Blobstream deployment.
+    const CHAIN_ID: u64 = 1;
+
+    fn fixture() -> (SyntheticProof, OraclePayload) {
+        let address = blobstream_address(CHAIN_ID).unwrap();
+        let fixture = synthetic_proof(address, 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), fixture.proof.clone());
+        (fixture, payload)
+    }
+
+    fn verify(
+        fixture: &SyntheticProof,
+        payload: &OraclePayload,
+        commitment: Commitment,
+        namespace: Namespace,
+    ) -> Result<Bytes, VerifyError> {
+        verify_celestia_payload(
+            &payload.to_bytes().unwrap(),
+            fixture.height,
+            commitment,
+            namespace,
+            fixture.l1_head,
+            CHAIN_ID,
+        )
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_returns_the_blob() {
+        let (fixture, payload) = fixture();
+        let commitment = payload.proven_commitment().unwrap();
+
+        let blob = verify(&fixture, &payload, commitment, synthetic_namespace()).unwrap();
+        assert_eq!(blob, payload.blob);
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_rejects_another_commitment() {
+        let (fixture, payload) = fixture();
+        let other = OraclePayload::new(
+            Bytes::from_static(b"other"),
+            payload.blobstream_proof.clone(),
+        );
+        let commitment = other.proven_commitment().unwrap();
+
+        let err = verify(&fixture, &payload, commitment, synthetic_namespace()).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                VerifyError::Verification(CelestiaVerificationError::CommitmentMismatch { .. })
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_rejects_another_namespace() {
+        let (fixture, payload) = fixture();
+        let commitment = payload.proven_commitment().unwrap();
+        let namespace = Namespace::new_v0(b"other").unwrap();
+
+        let err = verify(&fixture, &payload, commitment, namespace).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                VerifyError::Verification(CelestiaVerificationError::NamespaceMismatch { .. })
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_rejects_unproven_shares() {
+        // The host pairs a blob with the proof of another blob's shares at the same height.
+        let (fixture, mut payload) = fixture();
+        payload.blob = Bytes::from_static(b"hana-evil");
+        let commitment = payload.proven_commitment().unwrap();
+
+        let err = verify(&fixture, &payload, commitment, synthetic_namespace()).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                VerifyError::Verification(CelestiaVerificationError::Stage {
+                    stage: VerificationStage::ShareProof,
+                    ..
+                })
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_rejects_an_invalid_proof() {
+        let (fixture, payload) = fixture();
+        let commitment = payload.proven_commitment().unwrap();
+
+        let err = verify_celestia_payload(
+            &payload.to_bytes().unwrap(),
+            fixture.height + 1,
+            commitment,
+            synthetic_namespace(),
+            fixture.l1_head,
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                VerifyError::Verification(CelestiaVerificationError::Stage {
+                    stage: VerificationStage::DataRootTuple,
+                    ..
+                })
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_rejects_malformed_bytes() {
+        let err = verify_celestia_payload(
+            &[0; 8],
+            1,
+            Commitment::new([0; 32]),
+            synthetic_namespace(),
+            B256::ZERO,
+            CHAIN_ID,
+        )
+        .unwrap_err();
+        assert!(matches!(err, VerifyError::Decode(_)), "{err}");
+    }
+
+    #[test]
+    fn test_verify_celestia_payload_at_a_custom_deployment() {
+        // A private deployment on a chain without a canonical one.
+        let chain_id = 424242;
+        let custom = BlobstreamAddresses::from([(chain_id, Address::repeat_byte(0xbb))]);
+        let address = resolve_blobstream_address(chain_id, &custom).unwrap();
+        let fixture = synthetic_proof(address, 100);
+        let payload = OraclePayload::new(Bytes::from_static(b"hana-blob"), fixture.proof.clone());
+        let bytes = payload.to_bytes().unwrap();
+        let commitment = payload.proven_commitment().unwrap();
+
+        let blob = verify_celestia_payload_at(
+            &bytes,
+            fixture.height,
+            commitment,
+            synthetic_namespace(),
+            fixture.l1_head,
+            address,
+        )
+        .unwrap();
+        assert_eq!(blob, payload.blob);
+
+        let err = verify_celestia_payload(
+            &bytes,
+            fixture.height,
+            commitment,
+            synthetic_namespace(),
+            fixture.l1_head,
+            chain_id,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                VerifyError::Verification(CelestiaVerificationError::UnknownChainId(424242))
+            ),
+            "{err}"
+        );
+    }
+}